@@ -798,7 +798,7 @@ function initCareer() {
     const result = await window.api.pickPdf();
     if (!result) return;
     // Get page count automatically
-    const pageCount = await window.api.getPdfPages(result.fileName);
+    const pageCount = await window.api.getPdfPages(result.fileName).catch(() => 0);
     // Ask user for file type
     const fileType = await showFileTypeDialog();
     if (!fileType) { await window.api.deletePdf(result.fileName); return; }
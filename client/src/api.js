@@ -15,15 +15,35 @@ const api = {
   // Career
   loadCareer: () => window.api.loadCareer(),
   saveCareer: (data) => window.api.saveCareer(data),
+  getCareerStats: (rules) => window.api.getCareerStats(rules),
+  getPrerequisites: () => window.api.getPrerequisites(),
+  getRequirements: () => window.api.getRequirements(),
+  listCareers: () => window.api.listCareers(),
+  createCareer: (data, recognize) => window.api.createCareer(data, recognize),
+  switchCareer: (id) => window.api.switchCareer(id),
+  deleteCareer: (id) => window.api.deleteCareer(id),
+  importDegreeTemplate: (replace) => window.api.importDegreeTemplate(null, replace),
+  exportDegreeTemplate: () => window.api.exportDegreeTemplate(null),
+  exportCareerReport: (format) => window.api.exportCareerReport(null, format),
+  importTranscript: (path, mapping, dryRun) => window.api.importTranscript(path, mapping, dryRun),
 
   // PDF
   pickPdf: () => window.api.pickPdf(),
-  openPdf: (fileName) => window.api.openPdf(fileName),
+  openPdf: (fileName, page) => window.api.openPdf(fileName, page),
   deletePdf: (fileName) => window.api.deletePdf(fileName),
   getPdfPages: (fileName) => window.api.getPdfPages(fileName),
+  getPdfOutline: (fileName) => window.api.getPdfOutline(fileName),
+  getPdfThumbnail: (fileName, page, width) => window.api.getPdfThumbnail(fileName, page, width),
+  verifyAttachments: () => window.api.verifyAttachments(),
+  collectAttachments: (dryRun) => window.api.collectAttachments(dryRun),
+  searchPdfs: (query, limit) => window.api.searchPdfs(query, limit),
+  onPdfIndexUpdated: (cb) => window.api.onPdfIndexUpdated(cb),
 
   // Notifications
   showNotification: (data) => window.api.showNotification(data),
+  acknowledgeReminder: (eventId) => window.api.acknowledgeReminder(eventId),
+  getNotificationStatus: () => window.api.getNotificationStatus(),
+  sendTestNotification: () => window.api.sendTestNotification(),
 
   // Platform
   isMac: () => window.api.isMac(),
@@ -0,0 +1,110 @@
+import { useState, useEffect } from 'react';
+import { X, ShieldCheck, Trash2 } from 'lucide-react';
+import toast from 'react-hot-toast';
+import api from '../api';
+
+const formatSize = (bytes) => (bytes >= 1048576 ? `${(bytes / 1048576).toFixed(1)} MB` : `${Math.ceil(bytes / 1024)} KB`);
+
+function FileList({ title, files, tone }) {
+  if (!files?.length) return null;
+  return (
+    <div className="mb-4">
+      <div className={`text-xs font-semibold mb-1.5 ${tone}`}>{title} ({files.length})</div>
+      <div className="space-y-1">
+        {files.map(f => (
+          <div key={f.fileName} className="glass-card !p-2 text-[11px] flex items-center gap-2">
+            <span className="text-white truncate">{f.originalName || f.fileName}</span>
+            {f.examName && <span className="ml-auto text-text-dim truncate">{f.examName}</span>}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
+
+// Integrity check of the attached PDFs (verify_attachments) and cleanup of
+// the files no career uses anymore (collect_attachments).
+export default function AttachmentsModal({ show, onClose }) {
+  const [report, setReport] = useState(null);
+  const [busy, setBusy] = useState(false);
+
+  const runVerify = async () => {
+    setBusy(true);
+    try {
+      setReport(await api.verifyAttachments());
+    } catch (err) {
+      toast.error(err.message);
+    }
+    setBusy(false);
+  };
+
+  useEffect(() => {
+    if (!show) return;
+    setReport(null);
+    runVerify();
+  }, [show]);
+
+  const handleCollect = async () => {
+    setBusy(true);
+    try {
+      const res = await api.collectAttachments();
+      toast.success(res.removed.length
+        ? `Eliminati ${res.removed.length} file (${formatSize(res.freed)})`
+        : 'Nessun file da eliminare');
+    } catch (err) {
+      toast.error(err.message);
+    }
+    await runVerify();
+  };
+
+  const orphans = report?.orphans || [];
+  const collectable = orphans.filter(o => !o.recent);
+  const problems = (report?.missing?.length || 0) + (report?.altered?.length || 0);
+
+  return (
+    <div className={`modal-overlay ${show ? 'show' : ''}`} onClick={(e) => e.target === e.currentTarget && onClose()}>
+      <div className="modal-content" style={{ maxWidth: 520, maxHeight: '90vh', overflowY: 'auto' }}>
+        <div className="flex items-center justify-between mb-5">
+          <h2 className="text-lg font-bold text-white">Allegati</h2>
+          <button onClick={onClose} className="btn-icon"><X size={20} /></button>
+        </div>
+
+        {!report ? (
+          <div className="text-center py-6 text-text-dim text-xs">{busy ? 'Verifica in corso...' : ''}</div>
+        ) : (
+          <>
+            <div className={`glass-card !p-3 mb-4 flex items-center gap-2 text-xs ${problems ? 'text-red-400' : 'text-green-400'}`}>
+              <ShieldCheck size={16} />
+              {problems
+                ? `${problems} allegat${problems === 1 ? 'o' : 'i'} mancant${problems === 1 ? 'e' : 'i'} o modificat${problems === 1 ? 'o' : 'i'}`
+                : `${report.ok} allegati integri`}
+              <span className="ml-auto text-text-dim">{report.files} file · {formatSize(report.totalSize)}</span>
+            </div>
+            {report.shared > 0 && (
+              <div className="text-[11px] text-text-dim mb-4">
+                {report.shared} file in comune tra più esami, salvat{report.shared === 1 ? 'o' : 'i'} una sola volta
+              </div>
+            )}
+
+            <FileList title="Mancanti" files={report.missing} tone="text-red-400" />
+            <FileList title="Modificati o danneggiati" files={report.altered} tone="text-red-400" />
+            <FileList title="Non verificabili (importati con una versione precedente)" files={report.unchecked} tone="text-text-muted" />
+
+            {orphans.length > 0 && (
+              <div className="glass-card !p-3 flex items-center gap-2 text-xs">
+                <span className="text-text-muted">
+                  {orphans.length} file non usati da nessuna carriera ({formatSize(report.orphanSize)})
+                  {collectable.length < orphans.length && ' · i più recenti vengono tenuti per un\'ora'}
+                </span>
+                <button onClick={handleCollect} disabled={busy || !collectable.length}
+                  className="btn-secondary text-xs !py-1.5 ml-auto shrink-0">
+                  <Trash2 size={13} /> Elimina
+                </button>
+              </div>
+            )}
+          </>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -1,7 +1,13 @@
 import { useState, useEffect, useRef } from 'react';
 import { X, Plus, ExternalLink, Trash2 } from 'lucide-react';
+import toast from 'react-hot-toast';
 import api from '../api';
-import { generateId, calcAutoProgress } from '../lib/constants';
+import {
+  generateId, calcAutoProgress, ATTEMPT_OUTCOMES, ACTIVITY_LABELS,
+  CHAPTER_STATUSES, chapterPages, calcChapterProgress, pagesFromChapters,
+} from '../lib/constants';
+
+const parseGrade = (g) => (/^\d+$/.test(g) ? parseInt(g) : (g || null));
 
 export default function CareerExamModal({ show, exam, career, onClose, onSave, onDelete }) {
   const [name, setName] = useState('');
@@ -12,6 +18,12 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
   const [grade, setGrade] = useState('');
   const [passDate, setPassDate] = useState('');
   const [examDate, setExamDate] = useState('');
+  const [attempts, setAttempts] = useState([]);
+  const [appelli, setAppelli] = useState([]);
+  const [targetAppello, setTargetAppello] = useState(null);
+  const [prerequisites, setPrerequisites] = useState([]);
+  const [activity, setActivity] = useState('exam');
+  const [group, setGroup] = useState('');
   const [progress, setProgress] = useState(0);
   const [pdfs, setPdfs] = useState([]);
   const [activeFileTab, setActiveFileTab] = useState(0);
@@ -30,6 +42,12 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
         setGrade(exam.grade || '');
         setPassDate(exam.passDate || '');
         setExamDate(exam.examDate || '');
+        setAttempts(JSON.parse(JSON.stringify(exam.attempts || [])));
+        setAppelli(JSON.parse(JSON.stringify(exam.appelli || [])));
+        setTargetAppello(exam.targetAppello || null);
+        setPrerequisites(exam.prerequisites || []);
+        setActivity(exam.activity || 'exam');
+        setGroup(exam.group || '');
         setProgress(exam.progress || 0);
         setPdfs(JSON.parse(JSON.stringify(exam.pdfs || [])));
         setActiveFileTab(0);
@@ -42,6 +60,12 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
         setGrade('');
         setPassDate('');
         setExamDate('');
+        setAttempts([]);
+        setAppelli([]);
+        setTargetAppello(null);
+        setPrerequisites([]);
+        setActivity('exam');
+        setGroup('');
         setProgress(0);
         setPdfs([]);
         setActiveFileTab(0);
@@ -50,6 +74,50 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
     }
   }, [show, exam]);
 
+  const updateAttempt = (idx, field, val) => {
+    const next = attempts.map((a, i) => (i === idx ? { ...a, [field]: val } : a));
+    const a = next[idx];
+    if (a.outcome !== 'passed' && a.outcome !== 'rejected') a.grade = null;
+    setAttempts(next);
+    // A passed attempt is the exam result
+    if (a.outcome === 'passed') {
+      setStatus('passed');
+      setGrade(a.grade != null ? String(a.grade) : '');
+      setPassDate(a.date || '');
+    } else if (attempts[idx].outcome === 'passed') {
+      setStatus('pending');
+    }
+  };
+
+  const addAttempt = () => {
+    setAttempts(prev => [...prev, { date: '', outcome: 'failed', grade: null }]);
+  };
+
+  const deleteAttempt = (idx) => {
+    if (attempts[idx].outcome === 'passed') setStatus('pending');
+    setAttempts(prev => prev.filter((_, i) => i !== idx));
+  };
+
+  const updateAppello = (idx, field, val) => {
+    const next = appelli.map((a, i) => (i === idx ? { ...a, [field]: val } : a));
+    setAppelli(next);
+    if (field === 'date' && next[idx].id === targetAppello) setExamDate(val);
+  };
+
+  const addAppello = () => {
+    setAppelli(prev => [...prev, { id: generateId(), date: '', registrationDeadline: '', registered: false }]);
+  };
+
+  const deleteAppello = (idx) => {
+    if (appelli[idx].id === targetAppello) setTargetAppello(null);
+    setAppelli(prev => prev.filter((_, i) => i !== idx));
+  };
+
+  const chooseAppello = (appello) => {
+    setTargetAppello(appello.id);
+    setExamDate(appello.date);
+  };
+
   // Recalc progress when PDFs change
   useEffect(() => {
     const prog = calcAutoProgress({ pdfs });
@@ -64,6 +132,12 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
     try {
       const result = await api.pickPdf();
       if (result) {
+        if (result.pagesError) toast.error(`${result.originalName}: ${result.pagesError}`);
+        if (pdfs.some(p => p.fileName === result.fileName)) {
+          toast(`${result.originalName} è già allegato a questo esame`, { icon: '⚠️' });
+          return;
+        }
+        if (result.duplicate) toast(`${result.originalName} era già importato: uso la stessa copia`, { icon: 'ℹ️' });
         const newPdf = {
           fileName: result.fileName,
           originalName: result.originalName,
@@ -71,6 +145,12 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
           pages: type === 'appunti' ? { total: result.pages || 0, read: 0, studied: 0, repeated: 0 } : undefined,
           exercises: type === 'esercizi' ? [] : undefined,
         };
+        if (type === 'appunti' && !result.pagesError) {
+          try {
+            const outline = await api.getPdfOutline(result.fileName);
+            if (outline.chapters.length) newPdf.chapters = toChapters(outline.chapters);
+          } catch {}
+        }
         setPdfs(prev => [...prev, newPdf]);
       }
     } catch {}
@@ -95,9 +175,15 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
       year: parseInt(year) || 1,
       sem: parseInt(sem) || 1,
       status,
-      grade: status === 'passed' ? (parseInt(grade) || null) : null,
+      grade: status === 'passed' ? parseGrade(grade) : null,
       passDate: status === 'passed' ? passDate : '',
       examDate: status !== 'passed' ? examDate : '',
+      attempts: reconcileAttempts(attempts, { status, grade: parseGrade(grade), passDate, examDate, appello: targetAppello }),
+      appelli: appelli.filter(a => a.date),
+      targetAppello: appelli.some(a => a.id === targetAppello && a.date) ? targetAppello : null,
+      prerequisites,
+      activity,
+      group: group || null,
       progress,
       pdfs,
     };
@@ -166,7 +252,9 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
                     {Array.from({ length: 13 }, (_, i) => 18 + i).map(v => (
                       <option key={v} value={v}>{v}</option>
                     ))}
-                    <option value="31">30 e Lode</option>
+                    <option value="30L">30 e Lode</option>
+                    <option value="idoneo">Idoneo</option>
+                    <option value="convalidato">Convalidato</option>
                   </select>
                 </div>
                 <div>
@@ -182,7 +270,10 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
             <div className="grid grid-cols-2 gap-3">
               <div>
                 <label className="block text-xs font-medium text-text-muted mb-1">Data esame</label>
-                <input type="date" value={examDate} onChange={e => setExamDate(e.target.value)} className="form-input" />
+                <input type="date" value={examDate} onChange={e => {
+                  setExamDate(e.target.value);
+                  if (!appelli.some(a => a.id === targetAppello && a.date === e.target.value)) setTargetAppello(null);
+                }} className="form-input" />
               </div>
               <div>
                 <label className="block text-xs font-medium text-text-muted mb-1">Preparazione (auto)</label>
@@ -202,6 +293,122 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
             </div>
           )}
 
+          {/* Activity / Elective group */}
+          <div className="grid grid-cols-2 gap-3">
+            <div>
+              <label className="block text-xs font-medium text-text-muted mb-1">Tipo</label>
+              <select value={activity} onChange={e => setActivity(e.target.value)} className="form-select">
+                {Object.entries(ACTIVITY_LABELS).map(([k, v]) => <option key={k} value={k}>{v}</option>)}
+              </select>
+            </div>
+            <div>
+              <label className="block text-xs font-medium text-text-muted mb-1">Gruppo a scelta</label>
+              <select value={group} onChange={e => setGroup(e.target.value)} className="form-select">
+                <option value="">Obbligatorio</option>
+                {(career?.electiveGroups || []).map(g => <option key={g.id} value={g.id}>{g.name} ({g.cfu} CFU)</option>)}
+              </select>
+            </div>
+          </div>
+
+          {/* Prerequisites */}
+          {(career?.exams || []).some(e => e.id !== exam?.id) && (
+            <div>
+              <label className="block text-xs font-medium text-text-muted mb-1">Propedeuticità</label>
+              <div className="flex gap-1.5 flex-wrap max-h-24 overflow-y-auto">
+                {career.exams.filter(e => e.id !== exam?.id).map(e => {
+                  const on = prerequisites.includes(e.id);
+                  return (
+                    <button key={e.id} type="button"
+                      onClick={() => setPrerequisites(prev => on ? prev.filter(p => p !== e.id) : [...prev, e.id])}
+                      className={`text-xs px-2.5 py-1 rounded-lg transition-all ${
+                        on ? 'bg-primary/20 text-primary font-semibold' : 'bg-white/[0.03] text-text-dim hover:text-text-muted'
+                      }`}>
+                      {e.name}
+                    </button>
+                  );
+                })}
+              </div>
+            </div>
+          )}
+
+          {/* Appelli (pending only) */}
+          {status !== 'passed' && (
+            <div>
+              <div className="flex items-center justify-between mb-2">
+                <h3 className="text-sm font-semibold text-white">Appelli</h3>
+                <button type="button" onClick={addAppello} className="btn-secondary text-xs !py-1.5 !px-3">
+                  <Plus size={14} /> Aggiungi appello
+                </button>
+              </div>
+              {appelli.length === 0 ? (
+                <div className="text-center py-2 text-text-dim text-xs">Nessun appello in calendario</div>
+              ) : (
+                <div className="space-y-1.5">
+                  <div className="flex items-center gap-2 text-[10px] text-text-dim">
+                    <span className="w-5" />
+                    <span className="flex-1">Data appello</span>
+                    <span className="flex-1">Scadenza iscrizione</span>
+                    <span className="w-14 text-center">Iscritto</span>
+                    <span className="w-7" />
+                  </div>
+                  {appelli.map((a, i) => (
+                    <div key={a.id} className="flex items-center gap-2">
+                      <input type="radio" name="targetAppello" checked={a.id === targetAppello}
+                        onChange={() => chooseAppello(a)} title="Appello scelto" className="w-5" />
+                      <input type="date" value={a.date || ''} onChange={e => updateAppello(i, 'date', e.target.value)}
+                        className="form-input !py-1.5 text-xs flex-1" />
+                      <input type="date" value={a.registrationDeadline || ''} onChange={e => updateAppello(i, 'registrationDeadline', e.target.value)}
+                        className="form-input !py-1.5 text-xs flex-1" />
+                      <input type="checkbox" checked={!!a.registered} onChange={e => updateAppello(i, 'registered', e.target.checked)}
+                        className="w-14" />
+                      <button type="button" onClick={() => deleteAppello(i)} className="btn-icon !w-7 !h-7 hover:!text-danger">
+                        <X size={12} />
+                      </button>
+                    </div>
+                  ))}
+                </div>
+              )}
+            </div>
+          )}
+
+          {/* Attempt history */}
+          <div>
+            <div className="flex items-center justify-between mb-2">
+              <h3 className="text-sm font-semibold text-white">Tentativi</h3>
+              <button type="button" onClick={addAttempt} className="btn-secondary text-xs !py-1.5 !px-3">
+                <Plus size={14} /> Aggiungi tentativo
+              </button>
+            </div>
+            {attempts.length === 0 ? (
+              <div className="text-center py-2 text-text-dim text-xs">Nessun tentativo registrato</div>
+            ) : (
+              <div className="space-y-1.5">
+                {attempts.map((a, i) => (
+                  <div key={i} className="flex items-center gap-2">
+                    <input type="date" value={a.date || ''} onChange={e => updateAttempt(i, 'date', e.target.value)}
+                      className="form-input !py-1.5 text-xs flex-1" />
+                    <select value={a.outcome || 'scheduled'} onChange={e => updateAttempt(i, 'outcome', e.target.value)}
+                      className="form-select !py-1.5 text-xs !w-auto">
+                      {Object.entries(ATTEMPT_OUTCOMES).map(([k, v]) => <option key={k} value={k}>{v}</option>)}
+                    </select>
+                    {(a.outcome === 'passed' || a.outcome === 'rejected') ? (
+                      <select value={a.grade ?? ''} onChange={e => updateAttempt(i, 'grade', parseGrade(e.target.value))}
+                        className="form-select !py-1.5 text-xs !w-auto">
+                        <option value="">—</option>
+                        {Array.from({ length: 13 }, (_, j) => 18 + j).map(v => <option key={v} value={v}>{v}</option>)}
+                        <option value="30L">30L</option>
+                        {a.outcome === 'passed' && <option value="idoneo">Idoneo</option>}
+                      </select>
+                    ) : <div className="w-[68px]" />}
+                    <button type="button" onClick={() => deleteAttempt(i)} className="btn-icon !w-7 !h-7 hover:!text-danger">
+                      <X size={12} />
+                    </button>
+                  </div>
+                ))}
+              </div>
+            )}
+          </div>
+
           {/* Divider */}
           <div className="border-t border-white/5" />
 
@@ -220,7 +427,7 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
               <div className="space-y-1.5">
                 {pdfs.map((pdf, i) => (
                   <div key={i} className="flex items-center gap-3 p-2.5 rounded-lg bg-white/[0.03]">
-                    <span className="text-lg">{pdf.type === 'esercizi' ? '🏋️' : '📖'}</span>
+                    <PdfThumb fileName={pdf.fileName} fallback={pdf.type === 'esercizi' ? '🏋️' : '📖'} />
                     <div className="flex-1 min-w-0">
                       <div className="text-sm text-white truncate">{pdf.originalName}</div>
                       <div className="text-[10px] text-text-dim">
@@ -303,6 +510,34 @@ export default function CareerExamModal({ show, exam, career, onClose, onSave, o
   );
 }
 
+// ─── Attempts ───
+// The status/grade/date fields above win over the history: a passed exam
+// gets (or updates) its passed attempt, a pending one books `examDate`.
+function reconcileAttempts(attempts, { status, grade, passDate, examDate, appello }) {
+  let list = attempts.filter(a => a.date || a.outcome !== 'scheduled').map(a => ({ ...a }));
+  if (status === 'passed') {
+    let passed = list.find(a => a.outcome === 'passed')
+      || list.find(a => a.outcome === 'scheduled' && a.date === passDate);
+    if (!passed) {
+      passed = {};
+      list.push(passed);
+    }
+    Object.assign(passed, { date: passDate, outcome: 'passed', grade });
+  } else {
+    list = list.filter(a => a.outcome !== 'passed');
+    const booked = list.filter(a => a.outcome === 'scheduled').sort((a, b) => a.date.localeCompare(b.date));
+    if (!examDate) {
+      list = list.filter(a => a !== booked[0]);
+    } else if (!booked.some(a => a.date === examDate)) {
+      if (booked[0]) booked[0].date = examDate;
+      else list.push({ date: examDate, outcome: 'scheduled', grade: null });
+    }
+    const target = list.find(a => a.outcome === 'scheduled' && a.date === examDate);
+    if (target && appello) target.appello = appello;
+  }
+  return list.sort((a, b) => (a.date || '').localeCompare(b.date || ''));
+}
+
 // ─── File Type Dialog ───
 function showFileTypeDialog() {
   return new Promise((resolve) => {
@@ -343,15 +578,53 @@ function showFileTypeDialog() {
   });
 }
 
+const toChapters = (chapters) => chapters.map(c => ({
+  title: c.title, startPage: c.startPage, endPage: c.endPage, status: '',
+}));
+
+// Page counts follow the chapter statuses when the PDF has chapters.
+function syncChapterPages(pdf) {
+  if (!pdf.chapters?.length) return;
+  if (!pdf.pages) pdf.pages = { total: 0, read: 0, studied: 0, repeated: 0 };
+  const derived = pagesFromChapters(pdf.chapters);
+  const total = pdf.pages.total || 0;
+  Object.entries(derived).forEach(([k, v]) => { pdf.pages[k] = Math.min(v, total); });
+}
+
+// ─── PDF Thumbnail ───
+// First page of an attachment (get_pdf_thumbnail); the emoji stays when
+// the page can't be rendered.
+function PdfThumb({ fileName, fallback }) {
+  const [src, setSrc] = useState(null);
+
+  useEffect(() => {
+    let alive = true;
+    setSrc(null);
+    api.getPdfThumbnail(fileName, 1, 96)
+      .then(t => { if (alive) setSrc(t.dataUrl); })
+      .catch(() => {});
+    return () => { alive = false; };
+  }, [fileName]);
+
+  if (!src) return <span className="text-lg">{fallback}</span>;
+  return (
+    <button type="button" onClick={() => api.openPdf(fileName)} className="shrink-0">
+      <img src={src} alt="" className="w-8 rounded-sm border border-white/10 bg-white" />
+    </button>
+  );
+}
+
 // ─── Appunti Tracking Component ───
 function AppuntiTracking({ pdf, onChange }) {
   const p = pdf.pages || { total: 0, read: 0, studied: 0, repeated: 0 };
+  const hasChapters = pdf.chapters?.length > 0;
 
   const updateField = (field, val) => {
     if (!pdf.pages) pdf.pages = { total: 0, read: 0, studied: 0, repeated: 0 };
     const v = Math.max(0, val);
     const total = field === 'total' ? v : (pdf.pages.total || 0);
     pdf.pages[field] = field === 'total' ? v : Math.min(v, total);
+    syncChapterPages(pdf);
     onChange();
   };
 
@@ -370,6 +643,9 @@ function AppuntiTracking({ pdf, onChange }) {
             {f.color && <span className="w-2 h-2 rounded-full" style={{ background: f.color }} />}
             {f.label}
           </span>
+          {hasChapters && f.key !== 'total' ? (
+            <span className="text-xs text-text-muted w-[88px] text-center">{p[f.key] || 0}</span>
+          ) : (
           <div className="flex items-center gap-1">
             <button type="button" onClick={() => updateField(f.key, (p[f.key] || 0) - 1)}
               className="w-6 h-6 rounded bg-white/5 text-text-dim text-xs flex items-center justify-center hover:bg-white/10">▼</button>
@@ -383,9 +659,81 @@ function AppuntiTracking({ pdf, onChange }) {
             <button type="button" onClick={() => updateField(f.key, (p[f.key] || 0) + 1)}
               className="w-6 h-6 rounded bg-white/5 text-text-dim text-xs flex items-center justify-center hover:bg-white/10">▲</button>
           </div>
+          )}
         </div>
       ))}
       {p.total > 0 && <PageChart pages={p} />}
+      <ChapterTracking pdf={pdf} onChange={onChange} />
+    </div>
+  );
+}
+
+// ─── Chapter Tracking Component ───
+function ChapterTracking({ pdf, onChange }) {
+  const chapters = pdf.chapters || [];
+  const [loading, setLoading] = useState(false);
+
+  const loadChapters = async () => {
+    setLoading(true);
+    try {
+      const outline = await api.getPdfOutline(pdf.fileName);
+      if (!outline.chapters.length) {
+        toast('Il PDF non contiene segnalibri');
+        return;
+      }
+      pdf.chapters = toChapters(outline.chapters);
+      if (!pdf.pages) pdf.pages = { total: 0, read: 0, studied: 0, repeated: 0 };
+      if (!pdf.pages.total) pdf.pages.total = outline.pages;
+      syncChapterPages(pdf);
+      onChange();
+    } catch (err) {
+      toast.error(err.message);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const setStatus = (idx, status) => {
+    pdf.chapters[idx].status = status;
+    syncChapterPages(pdf);
+    onChange();
+  };
+
+  const removeChapters = () => {
+    delete pdf.chapters;
+    onChange();
+  };
+
+  if (!chapters.length) {
+    return (
+      <button type="button" onClick={loadChapters} disabled={loading} className="btn-secondary text-xs !py-1.5">
+        {loading ? 'Lettura segnalibri...' : 'Traccia per capitoli'}
+      </button>
+    );
+  }
+
+  return (
+    <div className="pt-2 space-y-1.5">
+      <div className="flex items-center justify-between">
+        <span className="text-xs font-medium text-text-muted">Capitoli</span>
+        <button type="button" onClick={removeChapters} className="text-[11px] text-text-dim hover:text-danger">
+          Traccia per pagine
+        </button>
+      </div>
+      {chapters.map((ch, i) => (
+        <div key={i} className="flex items-center gap-2">
+          <div className="flex-1 min-w-0">
+            <div className="text-xs text-white truncate" title={ch.title}>{ch.title}</div>
+            <div className="text-[10px] text-text-dim">
+              pp. {ch.startPage}-{ch.endPage} · {chapterPages(ch)} pag. · {calcChapterProgress(ch)}%
+            </div>
+          </div>
+          <select value={ch.status || ''} onChange={e => setStatus(i, e.target.value)}
+            className="form-select !py-1 text-xs !w-auto">
+            {CHAPTER_STATUSES.map(([value, label]) => <option key={value} value={value}>{label}</option>)}
+          </select>
+        </div>
+      ))}
     </div>
   );
 }
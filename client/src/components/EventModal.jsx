@@ -1,6 +1,7 @@
 import { useState, useEffect, useRef } from 'react';
 import { X } from 'lucide-react';
-import { CAT_COLORS, CAT_LABELS, generateId } from '../lib/constants';
+import api from '../api';
+import { CAT_COLORS, CAT_LABELS, generateId, chapterLabel } from '../lib/constants';
 
 const CATEGORIES = Object.keys(CAT_COLORS);
 
@@ -30,6 +31,9 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
   const [reminder1Time, setReminder1Time] = useState('20:00');
   const [reminder2On, setReminder2On] = useState(false);
   const [reminder2Time, setReminder2Time] = useState('07:00');
+  const [repeatMinutes, setRepeatMinutes] = useState(15);
+  const [study, setStudy] = useState(null);
+  const [chapters, setChapters] = useState([]);
   const titleRef = useRef(null);
 
   const isEdit = !!event?.id;
@@ -47,6 +51,8 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
         setReminder1Time(event.reminders?.dayBefore?.time || '20:00');
         setReminder2On(event.reminders?.sameDay?.enabled || false);
         setReminder2Time(event.reminders?.sameDay?.time || '07:00');
+        setRepeatMinutes(event.reminders?.repeatMinutes ?? 15);
+        setStudy(event.study || null);
       } else {
         const nextTime = getNextHalfHour();
         setTitle('');
@@ -59,11 +65,40 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
         setReminder1Time('20:00');
         setReminder2On(false);
         setReminder2Time('07:00');
+        setRepeatMinutes(15);
+        setStudy(null);
       }
       setTimeout(() => titleRef.current?.focus(), 100);
     }
   }, [show, event, defaultDate]);
 
+  // Chapters of the PDFs of exams still to pass, for study sessions
+  useEffect(() => {
+    if (!show) return;
+    api.loadCareer().then(career => {
+      const list = [];
+      (career?.exams || []).filter(ex => ex.status !== 'passed').forEach(ex => {
+        (ex.pdfs || []).forEach(pdf => {
+          (pdf.chapters || []).forEach(ch => {
+            list.push({
+              examId: ex.id, examName: ex.name, fileName: pdf.fileName,
+              title: ch.title, startPage: ch.startPage, endPage: ch.endPage,
+            });
+          });
+        });
+      });
+      setChapters(list);
+    }).catch(() => setChapters([]));
+  }, [show]);
+
+  const studyKey = (s) => (s ? `${s.examId}|${s.fileName}|${s.startPage}|${s.title}` : '');
+
+  const handleStudyChange = (key) => {
+    const target = chapters.find(c => studyKey(c) === key) || null;
+    setStudy(target);
+    if (target && !title.trim()) setTitle(`${target.examName}: ${chapterLabel(target)}`);
+  };
+
   // Auto end time
   useEffect(() => {
     if (timeStart && !isEdit) {
@@ -86,10 +121,14 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
       timeEnd,
       category,
       notes: notes.trim(),
+      study: category === 'studio' && study ? study : undefined,
       completed: event?.completed || false,
       reminders: {
+        // Fields without a control here (customRemindTime) are kept
+        ...event?.reminders,
         dayBefore: { enabled: reminder1On, time: reminder1Time },
         sameDay: { enabled: reminder2On, time: reminder2Time },
+        repeatMinutes: category === 'esame' ? repeatMinutes : undefined,
       },
     };
     onSave(data);
@@ -166,6 +205,22 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
             </div>
           </div>
 
+          {/* Study target */}
+          {category === 'studio' && (chapters.length > 0 || study) && (
+            <div>
+              <label className="block text-xs font-medium text-text-muted mb-1.5">Capitolo (opzionale)</label>
+              <select value={studyKey(study)} onChange={e => handleStudyChange(e.target.value)} className="form-select">
+                <option value="">—</option>
+                {study && !chapters.some(c => studyKey(c) === studyKey(study)) && (
+                  <option value={studyKey(study)}>{study.examName}: {chapterLabel(study)}</option>
+                )}
+                {chapters.map(c => (
+                  <option key={studyKey(c)} value={studyKey(c)}>{c.examName}: {chapterLabel(c)}</option>
+                ))}
+              </select>
+            </div>
+          )}
+
           {/* Notes */}
           <div>
             <label className="block text-xs font-medium text-text-muted mb-1.5">Note (opzionale)</label>
@@ -200,6 +255,16 @@ export default function EventModal({ show, event, defaultDate, onClose, onSave,
                 <input type="time" value={reminder2Time} onChange={e => setReminder2Time(e.target.value)}
                   className="form-input !w-24 !py-1.5 !px-2 text-xs" />
               </div>
+              {category === 'esame' && (
+                <div className="flex items-center gap-3">
+                  <span className="text-sm text-text-muted">Ripeti il giorno dell'esame</span>
+                  <select value={repeatMinutes} onChange={e => setRepeatMinutes(Number(e.target.value))}
+                    className="form-select !w-auto !py-1.5 text-xs">
+                    <option value={0}>Mai</option>
+                    {[5, 10, 15, 30, 60].map(m => <option key={m} value={m}>ogni {m} min</option>)}
+                  </select>
+                </div>
+              )}
             </div>
           </div>
 
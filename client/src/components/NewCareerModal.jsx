@@ -0,0 +1,117 @@
+import { useState, useEffect } from 'react';
+import { X, Check } from 'lucide-react';
+import toast from 'react-hot-toast';
+import api from '../api';
+import { CAREER_TYPES, gradeLabel } from '../lib/constants';
+
+// New career (e.g. the magistrale after the triennale): the current one is
+// archived and the chosen passed exams are carried over as recognized CFU.
+export default function NewCareerModal({ show, current, onClose, onCreated }) {
+  const [uniName, setUniName] = useState('');
+  const [courseName, setCourseName] = useState('');
+  const [careerType, setCareerType] = useState('magistrale');
+  const [recognize, setRecognize] = useState([]);
+
+  useEffect(() => {
+    if (!show) return;
+    setUniName(current?.university || '');
+    setCourseName('');
+    setCareerType(current?.type === 'triennale' ? 'magistrale' : (current?.type || 'triennale'));
+    setRecognize([]);
+  }, [show, current]);
+
+  const passed = (current?.exams || []).filter(e => e.status === 'passed');
+  const recognizedCfu = passed.filter(e => recognize.includes(e.id)).reduce((s, e) => s + (e.cfu || 0), 0);
+
+  const toggle = (id) => setRecognize(prev => (prev.includes(id) ? prev.filter(x => x !== id) : [...prev, id]));
+
+  const handleSubmit = async (e) => {
+    e.preventDefault();
+    const ct = CAREER_TYPES[careerType];
+    const data = {
+      university: uniName.trim(),
+      course: courseName.trim(),
+      type: careerType,
+      totalCfu: ct.cfu,
+      years: ct.years,
+      exams: [],
+    };
+    try {
+      const created = await api.createCareer(data, recognize);
+      toast.success(recognize.length
+        ? `Carriera creata: ${recognize.length} esami riconosciuti (${recognizedCfu} CFU)`
+        : 'Carriera creata');
+      onCreated(created);
+      onClose();
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  return (
+    <div className={`modal-overlay ${show ? 'show' : ''}`} onClick={(e) => e.target === e.currentTarget && onClose()}>
+      <div className="modal-content" style={{ maxWidth: 560, maxHeight: '90vh' }}>
+        <div className="flex items-center justify-between mb-5">
+          <h2 className="text-lg font-bold text-white">Nuova carriera</h2>
+          <button onClick={onClose} className="btn-icon"><X size={20} /></button>
+        </div>
+
+        <form onSubmit={handleSubmit} className="space-y-4 overflow-y-auto" style={{ maxHeight: 'calc(90vh - 120px)' }}>
+          <p className="text-xs text-text-dim">
+            La carriera attuale viene archiviata: potrai riattivarla in qualsiasi momento.
+          </p>
+          <div>
+            <label className="block text-xs font-medium text-text-muted mb-1">Università</label>
+            <input type="text" value={uniName} onChange={e => setUniName(e.target.value)}
+              placeholder="Es: Politecnico di Torino" className="form-input" />
+          </div>
+          <div>
+            <label className="block text-xs font-medium text-text-muted mb-1">Corso di Laurea</label>
+            <input type="text" value={courseName} onChange={e => setCourseName(e.target.value)}
+              placeholder="Es: Ingegneria Informatica" className="form-input" required />
+          </div>
+          <div>
+            <label className="block text-xs font-medium text-text-muted mb-1">Tipo di percorso</label>
+            <select value={careerType} onChange={e => setCareerType(e.target.value)} className="form-select">
+              {Object.entries(CAREER_TYPES).map(([key, val]) => (
+                <option key={key} value={key}>{val.label} ({val.cfu} CFU)</option>
+              ))}
+            </select>
+          </div>
+
+          {/* CFU recognition */}
+          {passed.length > 0 && (
+            <div>
+              <div className="flex items-center justify-between mb-2">
+                <label className="text-xs font-medium text-text-muted">Esami da riconoscere</label>
+                <span className="text-[11px] text-text-dim">{recognizedCfu} CFU</span>
+              </div>
+              <div className="space-y-1 max-h-56 overflow-y-auto">
+                {passed.map(e => {
+                  const on = recognize.includes(e.id);
+                  return (
+                    <button key={e.id} type="button" onClick={() => toggle(e.id)}
+                      className={`w-full flex items-center gap-2 px-2.5 py-1.5 rounded-lg text-left text-xs transition-all ${
+                        on ? 'bg-primary/20 text-white' : 'bg-white/[0.03] text-text-dim hover:text-text-muted'
+                      }`}>
+                      <span className={`w-4 h-4 rounded flex items-center justify-center shrink-0 ${on ? 'bg-primary' : 'bg-white/10'}`}>
+                        {on && <Check size={11} />}
+                      </span>
+                      <span className="flex-1 truncate">{e.name}</span>
+                      <span>{e.cfu} CFU · {gradeLabel(e.grade)}</span>
+                    </button>
+                  );
+                })}
+              </div>
+            </div>
+          )}
+
+          <div className="flex justify-end gap-2 pt-2">
+            <button type="button" onClick={onClose} className="btn-secondary">Annulla</button>
+            <button type="submit" className="btn-primary"><Check size={16} /> Crea carriera</button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,107 @@
+import { useState, useEffect, useRef } from 'react';
+import { X, Search } from 'lucide-react';
+import api from '../api';
+
+// Marks the words of `query` in a snippet, ignoring case and accents.
+function highlight(snippet, query) {
+  // One char per char, so positions in `folded` are positions in `snippet`
+  const fold = (s) => s.split('').map(c => c.normalize('NFD').charAt(0).toLowerCase()).join('');
+  const words = query.split(/\s+/).map(fold).filter(Boolean);
+  const folded = fold(snippet);
+  if (!words.length) return snippet;
+  const marks = new Array(snippet.length).fill(false);
+  words.forEach(w => {
+    for (let i = folded.indexOf(w); i >= 0; i = folded.indexOf(w, i + 1)) {
+      for (let j = i; j < i + w.length; j++) marks[j] = true;
+    }
+  });
+  const parts = [];
+  let start = 0;
+  for (let i = 1; i <= snippet.length; i++) {
+    if (i === snippet.length || marks[i] !== marks[start]) {
+      const text = snippet.slice(start, i);
+      parts.push(marks[start] ? <mark key={start} className="bg-primary/30 text-white rounded-sm">{text}</mark> : text);
+      start = i;
+    }
+  }
+  return parts;
+}
+
+// Full-text search in the attached PDFs (search_pdfs); a hit opens the
+// file at its page.
+export default function PdfSearchModal({ show, onClose }) {
+  const [query, setQuery] = useState('');
+  const [res, setRes] = useState(null);
+  const inputRef = useRef(null);
+  const queryRef = useRef('');
+  queryRef.current = query;
+
+  const runSearch = async (q) => {
+    try {
+      setRes(await api.searchPdfs(q));
+    } catch {
+      setRes(null);
+    }
+  };
+
+  useEffect(() => {
+    if (!show) return;
+    setQuery('');
+    runSearch('');
+    setTimeout(() => inputRef.current?.focus(), 100);
+  }, [show]);
+
+  useEffect(() => {
+    if (!show) return;
+    const t = setTimeout(() => runSearch(query), 250);
+    return () => clearTimeout(t);
+  }, [query]);
+
+  // Results follow the background indexer
+  useEffect(() => {
+    if (!show) return;
+    const unlisten = api.onPdfIndexUpdated(() => runSearch(queryRef.current));
+    return () => { unlisten?.then?.(f => f()); };
+  }, [show]);
+
+  const results = res?.results || [];
+
+  return (
+    <div className={`modal-overlay ${show ? 'show' : ''}`} onClick={(e) => e.target === e.currentTarget && onClose()}>
+      <div className="modal-content" style={{ maxWidth: 640, maxHeight: '90vh' }}>
+        <div className="flex items-center justify-between mb-5">
+          <h2 className="text-lg font-bold text-white">Cerca nei PDF</h2>
+          <button onClick={onClose} className="btn-icon"><X size={20} /></button>
+        </div>
+
+        <div className="relative mb-2">
+          <Search size={14} className="absolute left-3 top-1/2 -translate-y-1/2 text-text-dim" />
+          <input ref={inputRef} type="text" value={query} onChange={e => setQuery(e.target.value)}
+            placeholder="Es: teorema di Rolle" className="form-input !pl-9" />
+        </div>
+        <div className="text-[11px] text-text-dim mb-3">
+          {res?.indexing
+            ? `Indicizzazione in corso (${res.progress[0]}/${res.progress[1]})...`
+            : res ? `${res.files} file · ${res.pages} pagine indicizzate` : ''}
+        </div>
+
+        <div className="space-y-1.5 overflow-y-auto" style={{ maxHeight: 'calc(90vh - 200px)' }}>
+          {query.trim() && results.length === 0 && (
+            <div className="text-center py-6 text-text-dim text-xs">Nessun risultato</div>
+          )}
+          {results.map(r => (
+            <button key={`${r.fileName}-${r.page}`} type="button" onClick={() => api.openPdf(r.fileName, r.page)}
+              className="w-full text-left glass-card !p-2.5 hover:bg-white/[0.05] transition-all">
+              <div className="flex items-center gap-2 text-xs">
+                <span className="text-white font-medium truncate">{r.originalName}</span>
+                <span className="text-text-dim shrink-0">p. {r.page}</span>
+                {r.examName && <span className="ml-auto text-text-dim truncate">{r.examName}</span>}
+              </div>
+              <div className="text-[11px] text-text-muted mt-1">{highlight(r.snippet, query)}</div>
+            </button>
+          ))}
+        </div>
+      </div>
+    </div>
+  );
+}
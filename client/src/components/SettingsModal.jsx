@@ -1,7 +1,49 @@
 import { useState, useEffect } from 'react';
-import { X } from 'lucide-react';
+import { X, BellRing } from 'lucide-react';
+import toast from 'react-hot-toast';
 import api from '../api';
 
+const PERMISSION_LABELS = {
+  granted: 'concesso',
+  denied: 'negato',
+  prompt: 'da richiedere',
+  'prompt-with-rationale': 'da richiedere',
+};
+
+// Delivery diagnostics from get_notification_status: OS permission,
+// scheduler heartbeat, next planned notification and last error.
+function NotificationStatus({ status, busy, onTest }) {
+  if (!status) return null;
+  const permission = PERMISSION_LABELS[status.permission] || 'sconosciuto';
+  const rows = [
+    ['Permesso di sistema', permission, status.permission === 'denied' ? 'text-red-400' : 'text-white'],
+    ['Pianificazione', status.schedulerAlive ? 'attiva' : 'non attiva', status.schedulerAlive ? 'text-green-400' : 'text-red-400'],
+    ['Prossima notifica', status.next ? `${status.next.at} · ${status.next.title}` : 'nessuna', 'text-white'],
+    ['Ultima consegnata', status.lastDelivered?.at || 'nessuna', 'text-white'],
+  ];
+  return (
+    <div className="p-4 rounded-xl bg-white/[0.03] border border-white/5">
+      <div className="flex items-center justify-between mb-3">
+        <div className="text-sm font-medium text-white">Stato notifiche</div>
+        <button onClick={onTest} disabled={busy} className="btn-secondary text-xs !py-1.5">
+          <BellRing size={13} /> Invia prova
+        </button>
+      </div>
+      <div className="space-y-1 text-[11px]">
+        {rows.map(([label, value, tone]) => (
+          <div key={label} className="flex gap-2">
+            <span className="text-text-muted shrink-0">{label}</span>
+            <span className={`ml-auto truncate ${tone}`}>{value}</span>
+          </div>
+        ))}
+        {status.lastError && (
+          <div className="text-red-400 pt-1">Ultimo errore ({status.lastError.at}): {status.lastError.message}</div>
+        )}
+      </div>
+    </div>
+  );
+}
+
 export default function SettingsModal({ show, onClose }) {
   const [morningOn, setMorningOn] = useState(true);
   const [morningTime, setMorningTime] = useState('07:30');
@@ -9,6 +51,14 @@ export default function SettingsModal({ show, onClose }) {
   const [afternoonTime, setAfternoonTime] = useState('14:00');
   const [eveningOn, setEveningOn] = useState(true);
   const [eveningTime, setEveningTime] = useState('21:00');
+  const [status, setStatus] = useState(null);
+  const [testing, setTesting] = useState(false);
+
+  const loadStatus = async () => {
+    try {
+      setStatus(await api.getNotificationStatus());
+    } catch {}
+  };
 
   useEffect(() => {
     if (show) {
@@ -25,9 +75,22 @@ export default function SettingsModal({ show, onClose }) {
           }
         } catch {}
       })();
+      loadStatus();
     }
   }, [show]);
 
+  const handleTest = async () => {
+    setTesting(true);
+    try {
+      await api.sendTestNotification();
+      toast.success('Notifica di prova inviata');
+    } catch (err) {
+      toast.error(`Notifica non inviata: ${err.message}`);
+    }
+    await loadStatus();
+    setTesting(false);
+  };
+
   const handleSave = async () => {
     try {
       await api.saveSettings({
@@ -104,6 +167,8 @@ export default function SettingsModal({ show, onClose }) {
               </label>
             </div>
           </div>
+
+          <NotificationStatus status={status} busy={testing} onTest={handleTest} />
         </div>
 
         {/* Actions */}
@@ -0,0 +1,155 @@
+import { useState, useEffect } from 'react';
+import { X } from 'lucide-react';
+import toast from 'react-hot-toast';
+import api from '../api';
+import { gradeLabel } from '../lib/constants';
+
+const COLUMN_FIELDS = [
+  ['name', 'Nome'],
+  ['cfu', 'CFU'],
+  ['grade', 'Voto'],
+  ['date', 'Data'],
+  ['ssd', 'SSD'],
+  ['year', 'Anno'],
+];
+
+const ACTION_STYLES = {
+  add: ['Nuovo', 'bg-success/15 text-success'],
+  update: ['Aggiorna', 'bg-primary/15 text-primary'],
+  unchanged: ['Invariato', 'bg-white/5 text-text-dim'],
+  skip: ['Ignorato', 'bg-warning/15 text-warning'],
+};
+
+const FIELD_LABELS = { cfu: 'CFU', year: 'anno', ssd: 'SSD', status: 'stato', grade: 'voto', passDate: 'data' };
+
+function formatValue(field, value) {
+  if (value == null || value === '') return '—';
+  if (field === 'grade') return gradeLabel(value);
+  if (field === 'status') return value === 'passed' ? 'superato' : 'da sostenere';
+  return String(value);
+}
+
+// Dry-run preview of import_transcript: the user checks the detected
+// columns and the changes row by row, then applies the import.
+export default function TranscriptImportModal({ show, onClose, onImported }) {
+  const [preview, setPreview] = useState(null);
+  const [mapping, setMapping] = useState({});
+  const [busy, setBusy] = useState(false);
+
+  const runPreview = async (path, nextMapping) => {
+    setBusy(true);
+    try {
+      const res = await api.importTranscript(path, nextMapping, true);
+      if (!res) { onClose(); return; }
+      setPreview(res);
+    } catch (err) {
+      toast.error(err.message);
+      if (!path) onClose();
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  useEffect(() => {
+    if (!show) return;
+    setPreview(null);
+    setMapping({});
+    runPreview(null, null);
+  }, [show]);
+
+  const changeColumn = (field, header) => {
+    const next = { ...mapping, [field]: header || undefined };
+    setMapping(next);
+    runPreview(preview.path, next);
+  };
+
+  const handleApply = async () => {
+    setBusy(true);
+    try {
+      const res = await api.importTranscript(preview.path, mapping, false);
+      toast.success(`Libretto importato: ${res.added} esami aggiunti, ${res.updated} aggiornati`);
+      onImported(res.career);
+      onClose();
+    } catch (err) {
+      toast.error(err.message);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className={`modal-overlay ${show ? 'show' : ''}`} onClick={(e) => e.target === e.currentTarget && onClose()}>
+      <div className="modal-content" style={{ maxWidth: 720, maxHeight: '90vh' }}>
+        <div className="flex items-center justify-between mb-5">
+          <h2 className="text-lg font-bold text-white">Importa libretto</h2>
+          <button onClick={onClose} className="btn-icon"><X size={20} /></button>
+        </div>
+
+        {!preview ? (
+          <div className="py-10 text-center text-sm text-text-dim">{busy ? 'Lettura del file...' : ''}</div>
+        ) : (
+          <div className="space-y-4 overflow-y-auto" style={{ maxHeight: 'calc(90vh - 120px)' }}>
+            {/* Column mapping */}
+            <div>
+              <div className="text-xs font-medium text-text-muted mb-2">Colonne (riga {preview.headerRow})</div>
+              <div className="grid grid-cols-3 gap-3">
+                {COLUMN_FIELDS.map(([field, label]) => (
+                  <div key={field}>
+                    <label className="block text-[11px] text-text-dim mb-1">{label}</label>
+                    <select value={mapping[field] ?? preview.columns[field] ?? ''} disabled={busy}
+                      onChange={e => changeColumn(field, e.target.value)} className="form-select">
+                      <option value="">—</option>
+                      {preview.headers.filter(Boolean).map((h, i) => <option key={i} value={h}>{h}</option>)}
+                    </select>
+                  </div>
+                ))}
+              </div>
+            </div>
+
+            {/* Summary */}
+            <div className="text-xs text-text-muted">
+              {preview.added} nuovi · {preview.updated} da aggiornare · {preview.unchanged} invariati · {preview.skipped} ignorati
+            </div>
+            {preview.issues.map((msg, i) => (
+              <div key={i} className="text-xs text-danger">⚠️ {msg}</div>
+            ))}
+
+            {/* Changes */}
+            <div className="space-y-1.5">
+              {preview.changes.map(c => {
+                const [label, cls] = ACTION_STYLES[c.action];
+                return (
+                  <div key={c.row} className="glass-card !p-2.5 flex items-start gap-3">
+                    <span className={`text-[10px] font-semibold px-2 py-0.5 rounded-md shrink-0 ${cls}`}>{label}</span>
+                    <div className="min-w-0 flex-1">
+                      <div className="text-sm text-white truncate">
+                        {c.name}
+                        {c.examName && c.examName !== c.name && <span className="text-text-dim"> → {c.examName}</span>}
+                      </div>
+                      {c.reason && <div className="text-[11px] text-warning">Riga {c.row}: {c.reason}</div>}
+                      {c.changes?.length > 0 && (
+                        <div className="text-[11px] text-text-dim">
+                          {c.changes.map(d => c.action === 'add'
+                            ? `${FIELD_LABELS[d.field]} ${formatValue(d.field, d.to)}`
+                            : `${FIELD_LABELS[d.field]} ${formatValue(d.field, d.from)} → ${formatValue(d.field, d.to)}`).join(' · ')}
+                        </div>
+                      )}
+                    </div>
+                  </div>
+                );
+              })}
+            </div>
+
+            <div className="flex justify-end gap-2 pt-2">
+              <button onClick={onClose} className="btn-secondary">Annulla</button>
+              <button onClick={handleApply} disabled={busy || preview.issues.length > 0 || (preview.added + preview.updated) === 0}
+                className="btn-primary">
+                Importa
+              </button>
+            </div>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -34,6 +34,27 @@ export const CAREER_TYPES = {
   'ciclo-unico-6': { label: 'Ciclo Unico 6 anni',  cfu: 360, years: 6 },
 };
 
+// Voti esame: 18-30, '30L', 'idoneo', 'convalidato' (vedi src-tauri/src/career.rs)
+export function gradeLabel(grade) {
+  if (grade === 'idoneo') return 'Idoneo';
+  if (grade === 'convalidato') return 'Conv.';
+  return grade || '—';
+}
+
+export const ACTIVITY_LABELS = {
+  exam: 'Esame',
+  internship: 'Tirocinio',
+  thesis: 'Prova finale',
+};
+
+export const ATTEMPT_OUTCOMES = {
+  scheduled: 'Prenotato',
+  passed: 'Superato',
+  failed: 'Respinto',
+  withdrawn: 'Ritirato',
+  rejected: 'Rifiutato',
+};
+
 export const HOUR_START = 7;
 export const HOUR_END = 23;
 
@@ -72,6 +93,9 @@ export function getWeekDates(offset = 0) {
  * Weights: read=20%, studied=30%, repeated=50% (for appunti)
  * For esercizi: fatto=100%, in-corso=50%, da-fare=0%
  */
+// Weight of each study pass in the progress of an 'appunti' PDF.
+const PAGE_WEIGHTS = { read: 0.2, studied: 0.3, repeated: 0.5 };
+
 export function calcAutoProgress(ex) {
   const pdfs = ex.pdfs || [];
   if (!pdfs.length) return 0;
@@ -82,7 +106,7 @@ export function calcAutoProgress(ex) {
       const readPct = (pdf.pages.read || 0) / t;
       const studiedPct = (pdf.pages.studied || 0) / t;
       const repeatedPct = (pdf.pages.repeated || 0) / t;
-      const fileProg = readPct * 0.2 + studiedPct * 0.3 + repeatedPct * 0.5;
+      const fileProg = readPct * PAGE_WEIGHTS.read + studiedPct * PAGE_WEIGHTS.studied + repeatedPct * PAGE_WEIGHTS.repeated;
       totalScore += fileProg * t;
       totalWeight += t;
     }
@@ -101,3 +125,48 @@ export function calcAutoProgress(ex) {
   if (totalWeight === 0) return 0;
   return Math.round((totalScore / totalWeight) * 100);
 }
+
+// ─── Chapters ───
+// Chapters of an 'appunti' PDF come from its outline (get_pdf_outline):
+// { title, startPage, endPage, status } with status '' (da leggere), 'read',
+// 'studied' or 'repeated'.  Each status includes the previous ones.
+export const CHAPTER_STATUSES = [
+  ['', 'Da leggere'],
+  ['read', 'Letto'],
+  ['studied', 'Studiato'],
+  ['repeated', 'Ripetuto'],
+];
+
+const CHAPTER_STAGE = { read: 1, studied: 2, repeated: 3 };
+
+export function chapterPages(ch) {
+  return Math.max(0, (ch.endPage || 0) - (ch.startPage || 0) + 1);
+}
+
+export function calcChapterProgress(ch) {
+  const stage = CHAPTER_STAGE[ch.status] || 0;
+  const prog = (stage >= 1 ? PAGE_WEIGHTS.read : 0)
+    + (stage >= 2 ? PAGE_WEIGHTS.studied : 0)
+    + (stage >= 3 ? PAGE_WEIGHTS.repeated : 0);
+  return Math.round(prog * 100);
+}
+
+// Read/studied/repeated page counts implied by the chapter statuses, so
+// calcAutoProgress and the page charts follow the chapters.
+export function pagesFromChapters(chapters) {
+  const pages = { read: 0, studied: 0, repeated: 0 };
+  chapters.forEach(ch => {
+    const stage = CHAPTER_STAGE[ch.status] || 0;
+    const n = chapterPages(ch);
+    if (stage >= 1) pages.read += n;
+    if (stage >= 2) pages.studied += n;
+    if (stage >= 3) pages.repeated += n;
+  });
+  return pages;
+}
+
+export function chapterLabel(ch) {
+  return ch.startPage === ch.endPage
+    ? `${ch.title}, p. ${ch.startPage}`
+    : `${ch.title}, pp. ${ch.startPage}-${ch.endPage}`;
+}
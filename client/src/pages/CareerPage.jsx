@@ -1,14 +1,27 @@
 import { useState, useEffect, useCallback } from 'react';
-import { Plus, Trash2, GraduationCap, ChevronRight, Check } from 'lucide-react';
+import { Plus, Trash2, GraduationCap, ChevronRight, Check, Upload, Download, FileText, Search, ShieldCheck } from 'lucide-react';
+import toast from 'react-hot-toast';
 import api from '../api';
-import { CAREER_TYPES, MONTHS_IT, generateId, calcAutoProgress } from '../lib/constants';
+import { CAREER_TYPES, MONTHS_IT, generateId, calcAutoProgress, gradeLabel } from '../lib/constants';
 import CareerExamModal from '../components/CareerExamModal';
+import TranscriptImportModal from '../components/TranscriptImportModal';
+import NewCareerModal from '../components/NewCareerModal';
+import PdfSearchModal from '../components/PdfSearchModal';
+import AttachmentsModal from '../components/AttachmentsModal';
 
 export default function CareerPage() {
   const [career, setCareer] = useState(null);
   const [loading, setLoading] = useState(true);
   const [statusFilter, setStatusFilter] = useState('all');
   const [examModal, setExamModal] = useState({ show: false, exam: null });
+  const [showTranscript, setShowTranscript] = useState(false);
+  const [showNewCareer, setShowNewCareer] = useState(false);
+  const [showPdfSearch, setShowPdfSearch] = useState(false);
+  const [showAttachments, setShowAttachments] = useState(false);
+  const [careers, setCareers] = useState([]);
+  const [stats, setStats] = useState(null);
+  const [prereqs, setPrereqs] = useState(null);
+  const [requirements, setRequirements] = useState(null);
 
   // Setup form
   const [uniName, setUniName] = useState('');
@@ -20,9 +33,18 @@ export default function CareerPage() {
       const c = await api.loadCareer();
       setCareer(c && c.type ? c : null);
     } catch { setCareer(null); }
+    api.listCareers().then(setCareers).catch(() => setCareers([]));
     setLoading(false);
   }, []);
 
+  // Averages, CFU and base di laurea are computed by the backend
+  useEffect(() => {
+    if (!career) { setStats(null); setPrereqs(null); setRequirements(null); return; }
+    api.getRequirements().then(setRequirements).catch(() => setRequirements(null));
+    api.getCareerStats().then(setStats).catch(() => setStats(null));
+    api.getPrerequisites().then(setPrereqs).catch(() => setPrereqs(null));
+  }, [career]);
+
   useEffect(() => {
     load();
     window.addEventListener('app-data-changed', load);
@@ -67,7 +89,14 @@ export default function CareerPage() {
     } else {
       updated.exams.push(exam);
     }
-    await api.saveCareer(updated);
+    try {
+      const res = await api.saveCareer(updated);
+      (res?.unlocked || []).forEach(e => toast.success(`Ora puoi sostenere ${e.name}`));
+      (res?.warnings || []).forEach(w => toast(w, { icon: '⚠️' }));
+    } catch (err) {
+      toast.error(err.message);
+      return;
+    }
     setCareer(updated);
     setExamModal({ show: false, exam: null });
   };
@@ -80,12 +109,96 @@ export default function CareerPage() {
         try { await api.deletePdf(pdf.fileName); } catch {}
       }
     }
-    updated.exams = updated.exams.filter(e => e.id !== id);
+    updated.exams = updated.exams
+      .filter(e => e.id !== id)
+      .map(e => (e.prerequisites?.includes(id) ? { ...e, prerequisites: e.prerequisites.filter(p => p !== id) } : e));
     await api.saveCareer(updated);
     setCareer(updated);
     setExamModal({ show: false, exam: null });
   };
 
+  // Degree-program templates (docs/degree-template.md)
+  const handleImportTemplate = async () => {
+    const replace = !!career?.exams?.length
+      && confirm('Sostituire la carriera attuale con il template?\nAnnulla per unire il template agli esami esistenti.');
+    try {
+      const res = await api.importDegreeTemplate(replace);
+      if (!res) return;
+      toast.success(`Template importato: ${res.added} esami aggiunti, ${res.updated} aggiornati`);
+      setCareer(res.career);
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  const handleExportTemplate = async () => {
+    try {
+      const path = await api.exportDegreeTemplate();
+      if (path) toast.success('Template esportato');
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  const handleExportReport = async () => {
+    try {
+      const path = await api.exportCareerReport();
+      if (path) toast.success('Report carriera esportato');
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  // Elective groups ("scegli 12 CFU tra…", crediti a scelta libera)
+  const handleAddGroup = async () => {
+    const name = prompt('Nome del gruppo (es. "Esami a scelta 3° anno"):');
+    if (!name?.trim()) return;
+    const cfu = parseInt(prompt('CFU richiesti dal gruppo:', '12'));
+    if (!cfu) return;
+    const free = confirm('Crediti a scelta libera?\nOK = qualsiasi esame, Annulla = scelta da un elenco.');
+    const updated = { ...career, electiveGroups: [...(career.electiveGroups || []), { id: generateId(), name: name.trim(), cfu, kind: free ? 'free' : 'elective' }] };
+    try {
+      await api.saveCareer(updated);
+      setCareer(updated);
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  const handleDeleteGroup = async (id) => {
+    if (!confirm('Eliminare il gruppo? Gli esami del gruppo diventano obbligatori.')) return;
+    const updated = {
+      ...career,
+      electiveGroups: (career.electiveGroups || []).filter(g => g.id !== id),
+      exams: career.exams.map(e => (e.group === id ? { ...e, group: null } : e)),
+    };
+    await api.saveCareer(updated);
+    setCareer(updated);
+  };
+
+  // Multiple careers: the active one is career.json, the others are archived
+  const handleSwitchCareer = async (id) => {
+    try {
+      const c = await api.switchCareer(id);
+      setCareer(c);
+      api.listCareers().then(setCareers);
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  const handleDeleteCareer = async (c) => {
+    if (!confirm(`Eliminare definitivamente la carriera "${c.label}" e i suoi allegati?`)) return;
+    try {
+      await api.deleteCareer(c.id);
+      setCareers(prev => prev.filter(x => x.id !== c.id));
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
+  const archived = careers.filter(c => !c.active);
+
   if (loading) return <div className="h-full flex items-center justify-center text-text-dim">Caricamento...</div>;
 
   // ─── SETUP SCREEN ───
@@ -118,21 +231,51 @@ export default function CareerPage() {
             </div>
           </div>
 
-          <button onClick={handleSetup} className="btn-primary mt-6">
-            <Check size={16} /> Crea Piano Carriera
-          </button>
+          <div className="flex items-center justify-center gap-3 mt-6">
+            <button onClick={handleSetup} className="btn-primary">
+              <Check size={16} /> Crea Piano Carriera
+            </button>
+            <button onClick={handleImportTemplate} className="btn-secondary">
+              <Upload size={16} /> Importa template
+            </button>
+            <button onClick={() => setShowTranscript(true)} className="btn-secondary">
+              <Upload size={16} /> Importa libretto
+            </button>
+          </div>
+
+          {archived.length > 0 && (
+            <div className="mt-8 text-left">
+              <div className="text-xs font-semibold text-text-muted uppercase tracking-wider mb-2">Carriere archiviate</div>
+              <div className="space-y-2">
+                {archived.map(c => (
+                  <div key={c.id} className="glass-card !p-3 flex items-center gap-3">
+                    <div className="flex-1 min-w-0">
+                      <div className="text-sm font-semibold text-white truncate">{c.label}</div>
+                      <div className="text-[11px] text-text-dim">{c.cfuEarned ?? 0}/{c.cfuTotal ?? 0} CFU · {c.examsPassed ?? 0} esami</div>
+                    </div>
+                    <button onClick={() => handleSwitchCareer(c.id)} className="btn-secondary text-xs !py-1.5">Riattiva</button>
+                    <button onClick={() => handleDeleteCareer(c)} className="btn-icon !w-7 !h-7 hover:!text-danger"><Trash2 size={13} /></button>
+                  </div>
+                ))}
+              </div>
+            </div>
+          )}
         </div>
+        <TranscriptImportModal
+          show={showTranscript}
+          onClose={() => setShowTranscript(false)}
+          onImported={setCareer}
+        />
       </div>
     );
   }
 
   // ─── CAREER DASHBOARD ───
   const passedExams = career.exams.filter(e => e.status === 'passed');
-  const cfuDone = passedExams.reduce((s, e) => s + (e.cfu || 0), 0);
-  const media = passedExams.length > 0
-    ? (passedExams.reduce((s, e) => s + (e.grade || 0) * (e.cfu || 0), 0) / passedExams.reduce((s, e) => s + (e.cfu || 0), 0)).toFixed(1)
-    : '—';
-  const progressPct = career.totalCfu > 0 ? Math.round(cfuDone / career.totalCfu * 100) : 0;
+  const cfuDone = stats?.cfuEarned ?? 0;
+  const media = stats?.weightedAverage != null ? stats.weightedAverage.toFixed(1) : '—';
+  const base = stats?.baseScore != null ? stats.baseScore.toFixed(1) : '—';
+  const progressPct = stats?.progressPct ?? 0;
 
   // Upcoming exams
   const nowStr = toDateStr(new Date());
@@ -141,6 +284,10 @@ export default function CareerPage() {
     .sort((a, b) => a.examDate.localeCompare(b.examDate))
     .slice(0, 5);
 
+  // Prerequisites: exams that can be taken next, and what blocks the others
+  const eligible = (prereqs?.eligible || []).slice(0, 6);
+  const blockedById = Object.fromEntries((prereqs?.blocked || []).map(b => [b.id, b]));
+
   // Filtered & sorted exams
   let filtered = career.exams;
   if (statusFilter === 'passed') filtered = filtered.filter(e => e.status === 'passed');
@@ -158,7 +305,15 @@ export default function CareerPage() {
       <div className="flex items-start justify-between mb-4">
         <div>
           <h1 className="text-2xl font-bold text-white">Piano Carriera</h1>
-          <p className="text-sm text-text-muted mt-1">{career.university} · {career.course}</p>
+          {archived.length > 0 ? (
+            <select value="" onChange={e => e.target.value && handleSwitchCareer(e.target.value)}
+              className="form-select !w-auto !py-1 !text-sm mt-1">
+              <option value="">{career.university} · {career.course}</option>
+              {archived.map(c => <option key={c.id} value={c.id}>Passa a: {c.label}</option>)}
+            </select>
+          ) : (
+            <p className="text-sm text-text-muted mt-1">{career.university} · {career.course}</p>
+          )}
         </div>
         <div className="flex items-center gap-4">
           {[
@@ -166,6 +321,7 @@ export default function CareerPage() {
             { label: 'CFU totali', val: career.totalCfu },
             { label: 'Esami superati', val: passedExams.length },
             { label: 'Media pond.', val: media },
+            { label: 'Base laurea', val: base },
           ].map((s, i) => (
             <div key={i} className="text-center">
               <div className="text-lg font-bold text-white">{s.val}</div>
@@ -215,6 +371,60 @@ export default function CareerPage() {
         </div>
       )}
 
+      {/* Requirements per group */}
+      {requirements && (requirements.groups.length > 0 || requirements.issues.length > 0) && (
+        <div className="mb-6">
+          <div className="flex items-center justify-between mb-3">
+            <div className="text-xs font-semibold text-text-muted uppercase tracking-wider">Requisiti</div>
+            <button onClick={handleAddGroup} className="btn-secondary text-xs !py-1 !px-2.5">
+              <Plus size={12} /> Gruppo
+            </button>
+          </div>
+          <div className="grid grid-cols-2 gap-3">
+            {[{ id: null, name: 'Obbligatori', ...requirements.core }, ...requirements.groups].map(g => {
+              const pct = g.cfuRequired > 0 ? Math.min(100, Math.round(g.cfuEarned / g.cfuRequired * 100)) : 0;
+              return (
+                <div key={g.id || 'core'} className="glass-card p-3">
+                  <div className="flex items-center justify-between">
+                    <span className="text-sm font-semibold text-white truncate">{g.name}</span>
+                    {g.id && (
+                      <button onClick={() => handleDeleteGroup(g.id)} className="btn-icon !w-6 !h-6 hover:!text-danger">
+                        <Trash2 size={12} />
+                      </button>
+                    )}
+                  </div>
+                  <div className="text-[11px] text-text-dim mt-0.5">
+                    {g.cfuEarned}/{g.cfuRequired} CFU
+                    {g.cfuRemaining > 0 ? ` · mancano ${g.cfuRemaining}` : ' · completato'}
+                    {g.cfuExtra > 0 && ` · ${g.cfuExtra} in sovrannumero`}
+                  </div>
+                  <div className="mt-2 h-1.5 bg-white/5 rounded-full overflow-hidden">
+                    <div className="h-full rounded-full bg-primary" style={{ width: `${pct}%` }} />
+                  </div>
+                </div>
+              );
+            })}
+          </div>
+          {requirements.issues.map((msg, i) => (
+            <div key={i} className="text-xs text-warning mt-2">⚠️ {msg}</div>
+          ))}
+        </div>
+      )}
+
+      {/* Eligible-next strip */}
+      {eligible.length > 0 && (
+        <div className="mb-6">
+          <div className="text-xs font-semibold text-text-muted uppercase tracking-wider mb-3">Puoi sostenere</div>
+          <div className="flex gap-2 flex-wrap">
+            {eligible.map(e => (
+              <span key={e.id} className="text-xs px-2.5 py-1 rounded-lg bg-white/[0.03] text-text-muted">
+                {e.name} <span className="text-text-dim">· {e.year}° anno</span>
+              </span>
+            ))}
+          </div>
+        </div>
+      )}
+
       {/* Actions */}
       <div className="flex items-center gap-3 mb-4">
         <button onClick={() => setExamModal({ show: true, exam: null })} className="btn-primary">
@@ -226,6 +436,32 @@ export default function CareerPage() {
           <option value="passed">Superati</option>
         </select>
         <div className="flex-1" />
+        {!career.electiveGroups?.length && (
+          <button onClick={handleAddGroup} className="btn-secondary text-xs !py-2">
+            <Plus size={14} /> Gruppo a scelta
+          </button>
+        )}
+        <button onClick={handleImportTemplate} className="btn-secondary text-xs !py-2">
+          <Upload size={14} /> Importa
+        </button>
+        <button onClick={handleExportTemplate} className="btn-secondary text-xs !py-2">
+          <Download size={14} /> Esporta
+        </button>
+        <button onClick={() => setShowTranscript(true)} className="btn-secondary text-xs !py-2">
+          <Upload size={14} /> Libretto
+        </button>
+        <button onClick={handleExportReport} className="btn-secondary text-xs !py-2">
+          <FileText size={14} /> Report
+        </button>
+        <button onClick={() => setShowPdfSearch(true)} className="btn-secondary text-xs !py-2">
+          <Search size={14} /> Cerca nei PDF
+        </button>
+        <button onClick={() => setShowAttachments(true)} className="btn-secondary text-xs !py-2">
+          <ShieldCheck size={14} /> Allegati
+        </button>
+        <button onClick={() => setShowNewCareer(true)} className="btn-secondary text-xs !py-2">
+          <GraduationCap size={14} /> Nuova carriera
+        </button>
         <button onClick={handleReset} className="btn-danger text-xs !py-2">
           <Trash2 size={14} /> Reset
         </button>
@@ -241,7 +477,7 @@ export default function CareerPage() {
         <div className="space-y-1.5">
           {filtered.map(ex => {
             const isPassed = ex.status === 'passed';
-            const gradeDisplay = isPassed && ex.grade ? (ex.grade === 31 ? '30L' : ex.grade) : '—';
+            const gradeDisplay = isPassed ? gradeLabel(ex.grade) : '—';
             const prog = ex.progress || 0;
             const progColor = prog >= 80 ? '#34d399' : prog >= 50 ? '#fbbf24' : '#f87171';
 
@@ -254,6 +490,9 @@ export default function CareerPage() {
               countdown = dd < 0 ? 'Passato' : dd === 0 ? 'Oggi!' : `${ds} (${dd}g)`;
             }
 
+            const blocked = !isPassed ? blockedById[ex.id] : null;
+            const attemptsSat = (ex.attempts || []).filter(a => a.outcome && a.outcome !== 'scheduled').length;
+
             // File badges
             const appunti = (ex.pdfs || []).filter(p => p.type === 'appunti').length;
             const esercizi = (ex.pdfs || []).filter(p => p.type === 'esercizi').length;
@@ -278,6 +517,23 @@ export default function CareerPage() {
                   </div>
                   <div className="text-[11px] text-text-dim mt-0.5 flex items-center gap-2">
                     <span>{ex.year}° anno, {ex.sem}° sem</span>
+                    {blocked && (
+                      <>
+                        <span>·</span>
+                        <span className="text-warning">🔒 Richiede {blocked.missing.map(m => m.name).join(', ')}</span>
+                      </>
+                    )}
+                    {ex.recognized ? (
+                      <>
+                        <span>·</span>
+                        <span className="text-primary">Riconosciuto da {ex.recognized.career}</span>
+                      </>
+                    ) : attemptsSat > 0 && (
+                      <>
+                        <span>·</span>
+                        <span>{attemptsSat} {attemptsSat === 1 ? 'tentativo' : 'tentativi'}</span>
+                      </>
+                    )}
                     {countdown && (
                       <>
                         <span>·</span>
@@ -320,6 +576,22 @@ export default function CareerPage() {
       )}
 
       {/* Exam Modal */}
+      <TranscriptImportModal
+        show={showTranscript}
+        onClose={() => setShowTranscript(false)}
+        onImported={setCareer}
+      />
+
+      <PdfSearchModal show={showPdfSearch} onClose={() => setShowPdfSearch(false)} />
+      <AttachmentsModal show={showAttachments} onClose={() => setShowAttachments(false)} />
+
+      <NewCareerModal
+        show={showNewCareer}
+        current={career}
+        onClose={() => setShowNewCareer(false)}
+        onCreated={(c) => { setCareer(c); api.listCareers().then(setCareers); }}
+      />
+
       <CareerExamModal
         show={examModal.show}
         exam={examModal.exam}
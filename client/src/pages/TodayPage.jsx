@@ -1,7 +1,8 @@
 import { useState, useEffect, useRef, useCallback } from 'react';
-import { Plus } from 'lucide-react';
+import { Plus, BellOff } from 'lucide-react';
+import toast from 'react-hot-toast';
 import api from '../api';
-import { CAT_COLORS, DAYS_IT, MONTHS_IT, HOUR_START, HOUR_END, toDateStr } from '../lib/constants';
+import { CAT_COLORS, DAYS_IT, MONTHS_IT, HOUR_START, HOUR_END, toDateStr, chapterLabel } from '../lib/constants';
 
 export default function TodayPage({ onAddEvent, onEditEvent }) {
   const [events, setEvents] = useState([]);
@@ -82,6 +83,23 @@ export default function TodayPage({ onAddEvent, onEditEvent }) {
     } catch {}
   };
 
+  // Exam-day reminders repeat until the user says they've seen them
+  const nowTime = `${String(now.getHours()).padStart(2, '0')}:${String(now.getMinutes()).padStart(2, '0')}`;
+  const isRepeating = (ev) => ev.category === 'esame' && !ev.completed
+    && (ev.reminders?.repeatMinutes ?? 15) > 0
+    && (ev.timeStart || '') > nowTime
+    && !(ev.reminderAckAt || '').startsWith(todayStr);
+
+  const acknowledge = async (id) => {
+    try {
+      await api.acknowledgeReminder(id);
+      toast.success('Promemoria fermato');
+      load();
+    } catch (err) {
+      toast.error(err.message);
+    }
+  };
+
   // Timeline hours
   const hours = [];
   for (let h = HOUR_START; h <= HOUR_END; h++) hours.push(h);
@@ -182,8 +200,17 @@ export default function TodayPage({ onAddEvent, onEditEvent }) {
                       <div className={`text-sm font-medium truncate ${ev.completed ? 'text-text-dim line-through' : 'text-white'}`}>
                         {ev.title}
                       </div>
-                      <div className="text-[10px] text-text-dim">{ev.timeStart} – {ev.timeEnd}</div>
+                      <div className="text-[10px] text-text-dim">
+                        {ev.timeStart} – {ev.timeEnd}
+                        {ev.study && ` · ${chapterLabel(ev.study)}`}
+                      </div>
                     </div>
+                    {isRepeating(ev) && (
+                      <button onClick={(e) => { e.stopPropagation(); acknowledge(ev.id); }}
+                        className="btn-icon !w-7 !h-7 flex-shrink-0" title="Ho visto: ferma il promemoria">
+                        <BellOff size={14} />
+                      </button>
+                    )}
                     <div className="w-2 h-2 rounded-full flex-shrink-0" style={{ background: CAT_COLORS[ev.category] || '#8070d0' }} />
                   </div>
                 ))}
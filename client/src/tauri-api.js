@@ -62,17 +62,49 @@ window.api = {
 
   // PDF
   pickPdf: () => safeInvoke('pick_pdf'),
-  openPdf: (fileName) => safeInvoke('open_pdf', { fileName }),
+  openPdf: (fileName, page) => safeInvoke('open_pdf', { fileName, page: page ?? null }),
   deletePdf: (fileName) => safeInvoke('delete_pdf', { fileName }),
   getPdfPages: (fileName) => safeInvoke('get_pdf_pages', { fileName }),
+  getPdfOutline: (fileName) => safeInvoke('get_pdf_outline', { fileName }),
+  getPdfThumbnail: (fileName, page, width) => safeInvoke('get_pdf_thumbnail', { fileName, page: page ?? null, width: width ?? null }),
+  verifyAttachments: () => safeInvoke('verify_attachments'),
+  collectAttachments: (dryRun) => safeInvoke('collect_attachments', { dryRun: dryRun ?? null }),
+  searchPdfs: (query, limit) => safeInvoke('search_pdfs', { query, limit: limit ?? null }),
+  onPdfIndexUpdated: (cb) => listen('pdf-index-updated', (event) => cb(event.payload)),
 
   // Widgets
   getWidgetToday: () => safeInvoke('get_widget_today'),
   getWidgetExams: () => safeInvoke('get_widget_exams'),
   getWidgetWeek: () => safeInvoke('get_widget_week'),
   getWidgetCareer: () => safeInvoke('get_widget_career'),
+  getCareerStats: (rules) => safeInvoke('get_career_stats', { rules: rules || null }),
+  simulateCareer: (grades, target) => safeInvoke('simulate_career', { grades: grades || {}, target: target ?? null }),
+  getPrerequisites: () => safeInvoke('get_prerequisites'),
+  getRequirements: () => safeInvoke('get_requirements'),
+  listCareers: () => safeInvoke('list_careers'),
+  createCareer: (data, recognize) => safeInvoke('create_career', { data, recognize: recognize || null }),
+  switchCareer: (id) => safeInvoke('switch_career', { id }),
+  deleteCareer: (id) => safeInvoke('delete_career', { id }),
+  importDegreeTemplate: (path, replace) => safeInvoke('import_degree_template', { path: path || null, replace: !!replace }),
+  exportDegreeTemplate: (path) => safeInvoke('export_degree_template', { path: path || null }),
+  exportCareerReport: (path, format) => safeInvoke('export_career_report', { path: path || null, format: format || null }),
+  importTranscript: (path, mapping, dryRun) => safeInvoke('import_transcript', { path: path || null, mapping: mapping || null, dryRun: !!dryRun }),
+  listAppelli: () => safeInvoke('list_appelli'),
+  selectAppello: (examId, appelloId) => safeInvoke('select_appello', { examId, appelloId: appelloId || null }),
+  setAppelloRegistered: (examId, appelloId, registered) => safeInvoke('set_appello_registered', { examId, appelloId, registered }),
   showMainWindow: (opts) => safeInvoke('show_main_window', { opts: opts || null }),
 
+  // Notifications
+  previewBriefing: (kind) => safeInvoke('preview_briefing', { kind }),
+  refillNotifications: () => safeInvoke('refill_notifications'),
+  getNotificationPlan: () => safeInvoke('get_notification_plan'),
+  acknowledgeReminder: (eventId) => safeInvoke('acknowledge_reminder', { eventId }),
+  getNotificationStatus: () => safeInvoke('get_notification_status'),
+  sendTestNotification: () => safeInvoke('send_test_notification'),
+  onNotificationPlanUpdated: (cb) => {
+    listen('notification-plan-updated', (event) => cb(event.payload));
+  },
+
   // Event listeners from Rust
   onNavigate: (cb) => {
     listen('navigate', (event) => cb(event.payload));
@@ -88,6 +120,11 @@ window.api = {
   isMac: () => safeInvoke('get_is_mac'),
 };
 
+// Mobile: a delivered notification frees a slot → let Rust plan the next ones
+window.__TAURI__.notification?.onNotificationReceived?.(() => {
+  safeInvoke('refill_notifications').catch(() => {});
+});
+
 // Listen for notification events from Rust scheduler
 listen('show-notification', async (event) => {
   try {
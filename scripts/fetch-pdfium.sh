@@ -0,0 +1,54 @@
+#!/usr/bin/env bash
+# ═══════════════════════════════════════════════════════════════
+#  FETCH-PDFIUM — Scarica la libreria Pdfium per le anteprime PDF
+#
+#  Le build sono quelle di bblanchon/pdfium-binaries, nella release
+#  che corrisponde alla feature `pdfium_<n>` di pdfium-render in
+#  src-tauri/Cargo.toml.  Il bundle le prende da:
+#
+#    mac | win | linux → src-tauri/pdfium/  (tauri.<os>.conf.json)
+#    android           → src-tauri/gen/android/app/src/main/jniLibs/<abi>/
+#                        (dopo `tauri android init`)
+#
+#  Uso: scripts/fetch-pdfium.sh mac|win|linux|android
+# ═══════════════════════════════════════════════════════════════
+
+set -euo pipefail
+
+RELEASE=7543
+BASE="https://github.com/bblanchon/pdfium-binaries/releases/download/chromium%2F${RELEASE}"
+ROOT="$(cd "$(dirname "$0")/.." && pwd)"
+
+# fetch <archivio> <file nell'archivio> <destinazione>
+fetch() {
+  local tmp
+  tmp="$(mktemp -d)"
+  curl -fsSL "$BASE/pdfium-$1.tgz" | tar -xz -C "$tmp"
+  mkdir -p "$(dirname "$3")"
+  cp "$tmp/$2" "$3"
+  rm -rf "$tmp"
+  echo "Pdfium $RELEASE ($1) → ${3#"$ROOT"/}"
+}
+
+case "${1:-}" in
+  mac)
+    fetch mac-univ lib/libpdfium.dylib "$ROOT/src-tauri/pdfium/libpdfium.dylib"
+    ;;
+  win)
+    fetch win-x64 bin/pdfium.dll "$ROOT/src-tauri/pdfium/pdfium.dll"
+    ;;
+  linux)
+    fetch linux-x64 lib/libpdfium.so "$ROOT/src-tauri/pdfium/libpdfium.so"
+    ;;
+  android)
+    JNI="$ROOT/src-tauri/gen/android/app/src/main/jniLibs"
+    fetch android-arm64 lib/libpdfium.so "$JNI/arm64-v8a/libpdfium.so"
+    fetch android-arm lib/libpdfium.so "$JNI/armeabi-v7a/libpdfium.so"
+    fetch android-x86 lib/libpdfium.so "$JNI/x86/libpdfium.so"
+    fetch android-x64 lib/libpdfium.so "$JNI/x86_64/libpdfium.so"
+    ;;
+  *)
+    echo "Uso: $0 mac|win|linux|android" >&2
+    exit 1
+    ;;
+esac
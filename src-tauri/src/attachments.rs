@@ -0,0 +1,371 @@
+// ===== Content-Addressed Attachments =====
+//
+// PDFs in pdf-notes/ are stored under the SHA-256 of their content
+// ("<hash>.pdf"): importing the same notes twice reuses the stored file,
+// and the name itself is the checksum that `verify` compares against.
+// Files from older versions ("<timestamp>_<original name>") are renamed
+// once by `migrate_legacy`.
+//
+// Attachments are referenced by `fileName` from the exams of career.json
+// and of the archived careers in careers.json (study sessions in
+// events.json point at them too, but never keep a file alive on their
+// own).  A file nothing references is an orphan and `collect` removes it,
+// except when it was imported in the last `GRACE_SECS`: a PDF picked in
+// the exam dialog is referenced only once the exam is saved.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Orphans younger than this are kept (see above).
+pub const GRACE_SECS: u64 = 60 * 60;
+
+/// SHA-256 of the file at `path`, lowercase hex.
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Impossibile leggere {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Impossibile leggere {}: {}", path.display(), e))?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Name of the stored file for content with hash `hash`.
+pub fn stored_name(hash: &str) -> String {
+    format!("{}.pdf", hash)
+}
+
+/// The hash in a content-addressed file name; `None` for legacy names.
+pub fn hash_of(file_name: &str) -> Option<&str> {
+    let hash = file_name.strip_suffix(".pdf")?;
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))).then_some(hash)
+}
+
+/// Copies `src` into `pdf_dir` under its hash.  Returns the file name and
+/// whether the same content was already stored, in which case nothing is
+/// copied.
+pub fn store(pdf_dir: &Path, src: &Path) -> Result<(String, bool), String> {
+    let hash = hash_file(src)?;
+    let file_name = stored_name(&hash);
+    let dest = pdf_dir.join(&file_name);
+    if dest.is_file() && hash_file(&dest).ok().as_deref() == Some(hash.as_str()) {
+        // Restart the grace period of an orphan that is being reused
+        if let Ok(f) = fs::File::options().append(true).open(&dest) {
+            let _ = f.set_modified(SystemTime::now());
+        }
+        return Ok((file_name, true));
+    }
+    // Through a temporary file, so a stored file is always complete
+    let tmp = pdf_dir.join(format!("{}.tmp", file_name));
+    fs::copy(src, &tmp).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &dest).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })?;
+    Ok((file_name, false))
+}
+
+// ── References ──
+
+/// How many PDF entries of `careers` point at each file.
+pub fn refcounts<'a>(careers: impl IntoIterator<Item = &'a Value>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for career in careers {
+        for exam in career["exams"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+            for pdf in exam["pdfs"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+                if let Some(name) = pdf["fileName"].as_str().filter(|n| !n.is_empty()) {
+                    *counts.entry(name.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    counts
+}
+
+/// PDFs stored in `pdf_dir` (temporary files of an interrupted copy
+/// included, so they get collected too).
+fn stored_files(pdf_dir: &Path) -> Vec<(String, fs::Metadata)> {
+    let mut files: Vec<_> = fs::read_dir(pdf_dir).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let lower = name.to_lowercase();
+            if !lower.ends_with(".pdf") && !lower.ends_with(".pdf.tmp") { return None; }
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((name, meta))
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+fn is_recent(meta: &fs::Metadata, now: SystemTime) -> bool {
+    meta.modified().ok()
+        .and_then(|m| now.duration_since(m).ok())
+        .is_some_and(|age| age < Duration::from_secs(GRACE_SECS))
+}
+
+/// Removes the orphans of `pdf_dir` (all of them with `grace` false).
+/// Returns the removed file names and the bytes freed; with `dry_run`
+/// nothing is removed.
+pub fn collect(pdf_dir: &Path, refs: &BTreeMap<String, usize>, grace: bool, dry_run: bool) -> (Vec<String>, u64) {
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+    let mut freed = 0;
+    for (name, meta) in stored_files(pdf_dir) {
+        if refs.contains_key(&name) || (grace && is_recent(&meta, now)) { continue; }
+        if dry_run || fs::remove_file(pdf_dir.join(&name)).is_ok() {
+            freed += meta.len();
+            removed.push(name);
+        }
+    }
+    (removed, freed)
+}
+
+// ── Integrity ──
+
+/// Result of `verify`.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Referenced files whose content matches their name.
+    pub ok: usize,
+    /// Referenced but not in pdf-notes/.
+    pub missing: Vec<String>,
+    /// Content no longer matches the hash in the name (changed on disk,
+    /// truncated, or unreadable).
+    pub altered: Vec<String>,
+    /// Referenced files with a legacy name, which have no hash to check.
+    pub unchecked: Vec<String>,
+    /// Stored but not referenced by any career: name, size, and whether
+    /// it is still in its grace period.
+    pub orphans: Vec<(String, u64, bool)>,
+    /// Bytes in pdf-notes/.
+    pub total_size: u64,
+}
+
+/// Hashes every referenced file again and compares it with its name.
+pub fn verify(pdf_dir: &Path, refs: &BTreeMap<String, usize>) -> Report {
+    let now = SystemTime::now();
+    let mut report = Report::default();
+    let stored = stored_files(pdf_dir);
+    report.total_size = stored.iter().map(|(_, m)| m.len()).sum();
+    for name in refs.keys() {
+        let path = pdf_dir.join(name);
+        if !path.starts_with(pdf_dir) || !path.is_file() {
+            report.missing.push(name.clone());
+            continue;
+        }
+        match hash_of(name) {
+            Some(expected) => match hash_file(&path) {
+                Ok(actual) if actual == expected => report.ok += 1,
+                _ => report.altered.push(name.clone()),
+            },
+            None => report.unchecked.push(name.clone()),
+        }
+    }
+    report.orphans = stored.into_iter()
+        .filter(|(name, _)| !refs.contains_key(name))
+        .map(|(name, meta)| {
+            let recent = is_recent(&meta, now);
+            (name, meta.len(), recent)
+        })
+        .collect();
+    report
+}
+
+impl Report {
+    pub fn to_json(&self, refs: &BTreeMap<String, usize>) -> Value {
+        serde_json::json!({
+            "ok": self.ok,
+            "missing": self.missing,
+            "altered": self.altered,
+            "unchecked": self.unchecked,
+            "orphans": self.orphans.iter()
+                .map(|(name, size, recent)| serde_json::json!({ "fileName": name, "size": size, "recent": recent }))
+                .collect::<Vec<_>>(),
+            "orphanSize": self.orphans.iter().map(|(_, size, _)| size).sum::<u64>(),
+            "totalSize": self.total_size,
+            "files": refs.len(),
+            // Files attached to more than one exam
+            "shared": refs.values().filter(|n| **n > 1).count(),
+        })
+    }
+}
+
+// ── Legacy names ──
+
+/// Copies the legacy files of `pdf_dir` to their hash; identical files end
+/// up as one.  Returns old name → new name.  The old files stay in place:
+/// once the references are rewritten they are orphans like any other.
+pub fn migrate_legacy(pdf_dir: &Path) -> BTreeMap<String, String> {
+    let mut renamed = BTreeMap::new();
+    for (name, _) in stored_files(pdf_dir) {
+        if hash_of(&name).is_some() || name.ends_with(".tmp") { continue; }
+        match store(pdf_dir, &pdf_dir.join(&name)) {
+            Ok((new_name, _)) => { renamed.insert(name, new_name); }
+            Err(e) => eprintln!("[StudyPlan] Attachment {} not migrated: {}", name, e),
+        }
+    }
+    renamed
+}
+
+/// Replaces every `fileName` in `data` found in `renamed`; returns whether
+/// anything changed.
+pub fn rename_refs(data: &mut Value, renamed: &BTreeMap<String, String>) -> bool {
+    match data {
+        Value::Object(obj) => {
+            let mut changed = false;
+            for (key, value) in obj.iter_mut() {
+                if key == "fileName" {
+                    if let Some(new_name) = value.as_str().and_then(|n| renamed.get(n)) {
+                        *value = Value::from(new_name.clone());
+                        changed = true;
+                    }
+                } else {
+                    changed |= rename_refs(value, renamed);
+                }
+            }
+            changed
+        }
+        Value::Array(items) => items.iter_mut().fold(false, |changed, v| rename_refs(v, renamed) | changed),
+        _ => false,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn age(path: &Path, secs: u64) {
+        let f = fs::File::options().append(true).open(path).unwrap();
+        f.set_modified(SystemTime::now() - Duration::from_secs(secs)).unwrap();
+    }
+
+    fn stored(dir: &Path) -> Vec<String> {
+        stored_files(dir).into_iter().map(|(name, _)| name).collect()
+    }
+
+    fn career(files: &[&str]) -> Value {
+        json!({ "exams": files.iter().map(|f| json!({ "pdfs": [{ "fileName": f }] })).collect::<Vec<_>>() })
+    }
+
+    #[test]
+    fn identical_content_is_stored_once() {
+        let src = tempfile::tempdir().unwrap();
+        let pdf_dir = tempfile::tempdir().unwrap();
+        let a = write(src.path(), "appunti.pdf", "%PDF-1.4 appunti");
+        let b = write(src.path(), "copia.pdf", "%PDF-1.4 appunti");
+        let c = write(src.path(), "altro.pdf", "%PDF-1.4 altro");
+
+        let (name, existed) = store(pdf_dir.path(), &a).unwrap();
+        assert_eq!(hash_of(&name), Some(hash_file(&a).unwrap().as_str()));
+        assert!(!existed);
+        assert_eq!(store(pdf_dir.path(), &b).unwrap(), (name.clone(), true));
+        let (other, existed) = store(pdf_dir.path(), &c).unwrap();
+        assert!(!existed);
+        let mut expected = vec![name, other];
+        expected.sort();
+        assert_eq!(stored(pdf_dir.path()), expected);
+    }
+
+    #[test]
+    fn references_count_across_active_and_archived_careers() {
+        let active = career(&["a.pdf", "b.pdf", ""]);
+        let archive = [career(&["a.pdf"]), json!({ "exams": [{ "pdfs": [{ "fileName": "a.pdf" }, { "fileName": "c.pdf" }] }] })];
+        let refs = refcounts(std::iter::once(&active).chain(&archive));
+        assert_eq!(refs, BTreeMap::from([("a.pdf".to_string(), 3), ("b.pdf".to_string(), 1), ("c.pdf".to_string(), 1)]));
+        assert!(refcounts([&Value::Null]).is_empty());
+    }
+
+    #[test]
+    fn collect_keeps_referenced_files_and_fresh_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let used = write(dir.path(), "used.pdf", "usato");
+        let fresh = write(dir.path(), "fresh.pdf", "appena importato");
+        let old = write(dir.path(), "old.pdf", "vecchio");
+        let tmp = write(dir.path(), "broken.pdf.tmp", "copia interrotta");
+        write(dir.path(), "notes.txt", "non un PDF");
+        for path in [&used, &old, &tmp] {
+            age(path, GRACE_SECS + 60);
+        }
+        let refs = refcounts([&career(&["used.pdf"])]);
+
+        let (removed, freed) = collect(dir.path(), &refs, true, true);
+        assert_eq!(removed, ["broken.pdf.tmp", "old.pdf"]);
+        assert_eq!(freed, ("copia interrotta".len() + "vecchio".len()) as u64);
+        assert_eq!(stored(dir.path()).len(), 4);
+
+        assert_eq!(collect(dir.path(), &refs, true, false).0, ["broken.pdf.tmp", "old.pdf"]);
+        assert_eq!(stored(dir.path()), ["fresh.pdf", "used.pdf"]);
+        assert!(fresh.is_file());
+
+        // Without the grace period only referenced files stay
+        assert_eq!(collect(dir.path(), &refs, false, false).0, ["fresh.pdf"]);
+        assert_eq!(stored(dir.path()), ["used.pdf"]);
+        assert!(dir.path().join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn storing_again_restarts_the_grace_period() {
+        let src = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let pdf = write(src.path(), "appunti.pdf", "appunti");
+        let (name, _) = store(dir.path(), &pdf).unwrap();
+        age(&dir.path().join(&name), GRACE_SECS + 60);
+        store(dir.path(), &pdf).unwrap();
+        assert!(collect(dir.path(), &BTreeMap::new(), true, false).0.is_empty());
+    }
+
+    #[test]
+    fn legacy_files_are_copied_to_their_hash_and_references_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "1700000000_Analisi.pdf", "analisi");
+        write(dir.path(), "1700000001_Analisi (copia).pdf", "analisi");
+        write(dir.path(), "1700000002_Fisica.pdf", "fisica");
+        let renamed = migrate_legacy(dir.path());
+
+        let analisi = stored_name(&hash_file(&dir.path().join("1700000000_Analisi.pdf")).unwrap());
+        let fisica = stored_name(&hash_file(&dir.path().join("1700000002_Fisica.pdf")).unwrap());
+        assert_eq!(renamed, BTreeMap::from([
+            ("1700000000_Analisi.pdf".to_string(), analisi.clone()),
+            ("1700000001_Analisi (copia).pdf".to_string(), analisi.clone()),
+            ("1700000002_Fisica.pdf".to_string(), fisica.clone()),
+        ]));
+        // The old files stay until they are collected as orphans
+        assert_eq!(stored(dir.path()).len(), 5);
+        // Running again (a failed rewrite) maps to the same files
+        assert_eq!(migrate_legacy(dir.path()), renamed);
+        assert_eq!(stored(dir.path()).len(), 5);
+
+        let mut data = json!([
+            career(&["1700000000_Analisi.pdf", "1700000002_Fisica.pdf"]),
+            { "sessions": [{ "fileName": "1700000001_Analisi (copia).pdf", "page": 3 }, { "fileName": "altro.pdf" }] },
+        ]);
+        assert!(rename_refs(&mut data, &renamed));
+        assert_eq!(data, json!([
+            career(&[&analisi, &fisica]),
+            { "sessions": [{ "fileName": analisi, "page": 3 }, { "fileName": "altro.pdf" }] },
+        ]));
+        assert!(!rename_refs(&mut data, &renamed));
+
+        let refs = refcounts([&data[0]]);
+        let report = verify(dir.path(), &refs);
+        assert_eq!(report.ok, 2);
+        assert_eq!(collect(dir.path(), &refs, false, false).0.len(), 3);
+    }
+}
@@ -0,0 +1,198 @@
+// ===== Daily Briefings =====
+//
+// Builds the morning / afternoon / evening briefing for a given day.  Used by
+// the mobile AOT scheduler, the desktop cron job and `preview_briefing`, so
+// the preview shows exactly the text that will be delivered.
+//
+// Content:
+//   morning   — today's agenda (first `briefingMaxItems` events with times),
+//               next exam countdown, yesterday's completion rate
+//   afternoon — events still pending today
+//   evening   — today's completion, tomorrow's agenda, next exam countdown
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::career::CareerExam;
+use crate::i18n::{self, Lang};
+use crate::notifications::briefing_events;
+
+pub const DEFAULT_BRIEFING_MAX_ITEMS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BriefingKind {
+    Morning,
+    Afternoon,
+    Evening,
+}
+
+impl BriefingKind {
+    pub const ALL: [BriefingKind; 3] = [BriefingKind::Morning, BriefingKind::Afternoon, BriefingKind::Evening];
+
+    pub fn parse(kind: &str) -> Option<BriefingKind> {
+        match kind {
+            "morning" => Some(BriefingKind::Morning),
+            "afternoon" => Some(BriefingKind::Afternoon),
+            "evening" => Some(BriefingKind::Evening),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            BriefingKind::Morning => "morning",
+            BriefingKind::Afternoon => "afternoon",
+            BriefingKind::Evening => "evening",
+        }
+    }
+
+    pub fn enabled(self, settings: &Value) -> bool {
+        settings.get(format!("{}Notif", self.key())).and_then(|v| v.as_bool()).unwrap_or(true)
+    }
+
+    /// Delivery time ("HH:MM") from settings.<kind>Time.
+    pub fn time(self, settings: &Value) -> String {
+        let default = match self {
+            BriefingKind::Morning => "07:30",
+            BriefingKind::Afternoon => "14:00",
+            BriefingKind::Evening => "21:00",
+        };
+        settings.get(format!("{}Time", self.key()))
+            .and_then(|v| v.as_str())
+            .unwrap_or(default)
+            .to_string()
+    }
+}
+
+pub struct Briefing {
+    pub title: String,
+    pub body: String,
+}
+
+fn is_completed(event: &Value) -> bool {
+    event.get("completed").and_then(|c| c.as_bool()).unwrap_or(false)
+}
+
+/// Bullet list of the first `max_items` events, sorted by start time.
+fn agenda_lines(lang: Lang, events: &[&Value], max_items: usize) -> Vec<String> {
+    let mut sorted: Vec<&Value> = events.to_vec();
+    sorted.sort_by(|a, b| {
+        let ta = a.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
+        let tb = b.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
+        ta.cmp(tb)
+    });
+
+    let fallback_title = i18n::t(lang, "fallback.event");
+    let mut lines: Vec<String> = sorted.iter().take(max_items).map(|e| {
+        let title = e.get("title").and_then(|t| t.as_str()).unwrap_or(&fallback_title);
+        match e.get("timeStart").and_then(|t| t.as_str()).filter(|t| !t.is_empty()) {
+            Some(time) => i18n::tf(lang, "briefing.item", &[("time", &time), ("title", &title)]),
+            None => i18n::tf(lang, "briefing.item_untimed", &[("title", &title)]),
+        }
+    }).collect();
+
+    let remaining = sorted.len().saturating_sub(max_items);
+    if remaining > 0 {
+        lines.push(i18n::tn(lang, "briefing.more", remaining as i64, &[]));
+    }
+    lines
+}
+
+/// Countdown line for the closest exam in career.json on or after `date`.
+fn next_exam_line(lang: Lang, career: &Value, date: NaiveDate) -> Option<String> {
+    let exams = career.get("exams").and_then(|e| e.as_array())?;
+    let fallback_name = i18n::t(lang, "fallback.exam");
+    let (exam_date, name) = exams.iter()
+        .filter_map(|e| serde_json::from_value::<CareerExam>(e.clone()).ok())
+        .filter(|e| !e.is_passed())
+        .filter_map(|e| {
+            let d = NaiveDate::parse_from_str(e.planned_date()?, "%Y-%m-%d").ok()?;
+            let name = if e.name.is_empty() { fallback_name.clone() } else { e.name };
+            Some((d, name))
+        })
+        .filter(|(d, _)| *d >= date)
+        .min_by_key(|(d, _)| *d)?;
+
+    let days = (exam_date - date).num_days();
+    Some(match days {
+        0 => i18n::tf(lang, "briefing.exam.today", &[("exam", &name)]),
+        1 => i18n::tf(lang, "briefing.exam.tomorrow", &[("exam", &name)]),
+        n => i18n::tn(lang, "briefing.exam.days", n, &[("exam", &name)]),
+    })
+}
+
+/// Completion rate of the day before `date`, if anything was planned.
+fn yesterday_line(lang: Lang, events: &Value, settings: &Value, date: NaiveDate) -> Option<String> {
+    let yesterday = (date - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let planned = briefing_events(events, settings, &yesterday);
+    if planned.is_empty() { return None; }
+    let done = planned.iter().filter(|e| is_completed(e)).count();
+    let pct = done * 100 / planned.len();
+    Some(i18n::tn(lang, "briefing.yesterday", planned.len() as i64, &[("done", &done), ("pct", &pct)]))
+}
+
+/// Evening headline: today's completion plus tomorrow's load.
+fn evening_headline(lang: Lang, today_completed: usize, today_count: usize, tomorrow_count: usize) -> String {
+    let tomorrow_events = i18n::tn(lang, "events", tomorrow_count as i64, &[]);
+    if today_count > 0 && tomorrow_count > 0 {
+        i18n::tf(lang, "body.evening.both", &[("done", &today_completed), ("total", &today_count), ("events", &tomorrow_events)])
+    } else if today_count > 0 {
+        i18n::tn(lang, "body.evening.today", today_count as i64, &[("done", &today_completed)])
+    } else {
+        i18n::tf(lang, "body.evening.tomorrow", &[("events", &tomorrow_events)])
+    }
+}
+
+/// Briefing of `kind` for `date`, or `None` when there is nothing to report.
+pub fn build(kind: BriefingKind, events: &Value, settings: &Value, career: &Value, date: NaiveDate, lang: Lang) -> Option<Briefing> {
+    build_inner(kind, events, settings, career, date, lang, true)
+}
+
+/// Same as `build`, for briefings planned days in advance (mobile): the
+/// previous day's completion rate is not known yet and is left out.
+pub fn build_planned(kind: BriefingKind, events: &Value, settings: &Value, career: &Value, date: NaiveDate, lang: Lang) -> Option<Briefing> {
+    build_inner(kind, events, settings, career, date, lang, false)
+}
+
+fn build_inner(kind: BriefingKind, events: &Value, settings: &Value, career: &Value, date: NaiveDate, lang: Lang, with_history: bool) -> Option<Briefing> {
+    let max_items = settings.get("briefingMaxItems")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize)
+        .unwrap_or(DEFAULT_BRIEFING_MAX_ITEMS);
+    let day = date.format("%Y-%m-%d").to_string();
+    let day_events = briefing_events(events, settings, &day);
+    let pending: Vec<&Value> = day_events.iter().copied().filter(|e| !is_completed(e)).collect();
+
+    let mut lines = Vec::new();
+    match kind {
+        BriefingKind::Morning => {
+            if pending.is_empty() { return None; }
+            let count = i18n::tn(lang, "events", pending.len() as i64, &[]);
+            lines.push(i18n::tf(lang, "briefing.morning.header", &[("events", &count)]));
+            lines.extend(agenda_lines(lang, &pending, max_items));
+            lines.extend(next_exam_line(lang, career, date));
+            if with_history {
+                lines.extend(yesterday_line(lang, events, settings, date));
+            }
+        }
+        BriefingKind::Afternoon => {
+            if pending.is_empty() { return None; }
+            lines.push(i18n::tn(lang, "briefing.afternoon.header", pending.len() as i64, &[]));
+            lines.extend(agenda_lines(lang, &pending, max_items));
+        }
+        BriefingKind::Evening => {
+            let tomorrow = (date + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+            let tomorrow_events = briefing_events(events, settings, &tomorrow);
+            if day_events.is_empty() && tomorrow_events.is_empty() { return None; }
+            let completed = day_events.len() - pending.len();
+            lines.push(evening_headline(lang, completed, day_events.len(), tomorrow_events.len()));
+            lines.extend(agenda_lines(lang, &tomorrow_events, max_items));
+            lines.extend(next_exam_line(lang, career, date + chrono::Duration::days(1)));
+        }
+    }
+
+    Some(Briefing {
+        title: i18n::t(lang, &format!("title.{}", kind.key())),
+        body: lines.join("\n"),
+    })
+}
@@ -0,0 +1,800 @@
+// ===== Career Model =====
+//
+// Typed view of career.json.  The file stays the source of truth and keeps
+// any field this model does not know about (`extra`), so the frontend can
+// add data without a backend change.
+//
+// Grades are stored as:
+//   18..=30        numeric grade
+//   "30L"          30 e lode (older files use the number 31, see `migrate`)
+//   "idoneo"       idoneità / pass without a grade
+//   "convalidato"  recognised from a previous career, without a grade
+//
+// Each exam keeps its history in `attempts` (date, outcome, grade).  The
+// summary fields `status`, `grade`, `passDate` and `examDate` are derived
+// from it by `migrate`, so older readers of career.json keep working:
+//   passed attempt        → status "passed", grade, passDate
+//   target appello        → examDate
+//   earliest scheduled    → examDate, when no appello is targeted
+//
+// `activity` tells exams from the tirocinio ("internship") and the prova
+// finale ("thesis"), which may be passed without a grade.  `group` puts an
+// exam in one of the career's `electiveGroups` (CFU to choose from a list,
+// or free-choice CFU); see `requirements` for how CFU count.
+//
+// `appelli` lists the candidate exam dates of the session, each with its
+// registration window; `targetAppello` is the id of the one the student
+// plans to sit, chosen with `select_appello`.
+//
+// `recognized` marks an exam carried over from another career of the
+// profile (see `careers`); like other unknown fields it lives in `extra`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    Numeric(u8),
+    Lode,
+    Idoneo,
+    Convalidato,
+}
+
+impl Grade {
+    pub fn parse(value: &Value) -> Result<Grade, String> {
+        match value {
+            Value::Number(n) => match n.as_u64() {
+                Some(v @ 18..=30) => Ok(Grade::Numeric(v as u8)),
+                Some(31) => Ok(Grade::Lode),
+                _ => Err(format!("Voto non valido: {} (ammessi 18-30, 30L, idoneo, convalidato)", n)),
+            },
+            Value::String(s) => {
+                let s = s.trim().to_lowercase();
+                match s.as_str() {
+                    "30l" | "30 e lode" | "30 lode" | "30elode" => Ok(Grade::Lode),
+                    "idoneo" | "idonea" | "idoneità" | "idoneita" => Ok(Grade::Idoneo),
+                    "convalidato" | "convalidata" | "riconosciuto" => Ok(Grade::Convalidato),
+                    _ => match s.parse::<u64>() {
+                        Ok(v) => Grade::parse(&Value::from(v)),
+                        Err(_) => Err(format!("Voto non valido: \"{}\" (ammessi 18-30, 30L, idoneo, convalidato)", s)),
+                    },
+                }
+            }
+            other => Err(format!("Voto non valido: {}", other)),
+        }
+    }
+
+    pub fn to_value(self) -> Value {
+        match self {
+            Grade::Numeric(v) => Value::from(v),
+            Grade::Lode => Value::from("30L"),
+            Grade::Idoneo => Value::from("idoneo"),
+            Grade::Convalidato => Value::from("convalidato"),
+        }
+    }
+}
+
+impl Serialize for Grade {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grade {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Grade::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExamStatus {
+    #[default]
+    Pending,
+    Passed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttemptOutcome {
+    /// Booked, not sat yet
+    #[default]
+    Scheduled,
+    Passed,
+    Failed,
+    /// Ritirato during the exam
+    Withdrawn,
+    /// Grade offered and refused (rifiutato)
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamAttempt {
+    #[serde(default)]
+    pub date: String,
+    #[serde(default)]
+    pub outcome: AttemptOutcome,
+    #[serde(default)]
+    pub grade: Option<Grade>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ExamAttempt {
+    /// Sat attempts, as opposed to bookings.
+    pub fn is_concluded(&self) -> bool {
+        self.outcome != AttemptOutcome::Scheduled
+    }
+}
+
+/// One candidate exam date (appello) and its registration window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Appello {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub date: String,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub registration_opens: String,
+    #[serde(default)]
+    pub registration_deadline: String,
+    #[serde(default)]
+    pub registered: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Appello {
+    /// "upcoming", "open" or "closed" on `today`; always "open" without a
+    /// registration window.
+    pub fn registration_state(&self, today: &str) -> &'static str {
+        if !self.registration_opens.is_empty() && today < self.registration_opens.as_str() {
+            "upcoming"
+        } else if !self.registration_deadline.is_empty() && today > self.registration_deadline.as_str() {
+            "closed"
+        } else {
+            "open"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activity {
+    #[default]
+    Exam,
+    /// Tirocinio
+    Internship,
+    /// Prova finale
+    Thesis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupKind {
+    /// Choose `cfu` among the exams listed in the group
+    #[default]
+    Elective,
+    /// Crediti a scelta libera: any exam the student assigns to the group
+    Free,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElectiveGroup {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// CFU required from the group
+    #[serde(default)]
+    pub cfu: u32,
+    #[serde(default)]
+    pub kind: GroupKind,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_one() -> u32 { 1 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CareerExam {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub cfu: u32,
+    #[serde(default = "default_one")]
+    pub year: u32,
+    #[serde(default = "default_one")]
+    pub sem: u32,
+    #[serde(default)]
+    pub status: ExamStatus,
+    #[serde(default)]
+    pub grade: Option<Grade>,
+    #[serde(default)]
+    pub pass_date: String,
+    #[serde(default)]
+    pub exam_date: String,
+    #[serde(default)]
+    pub attempts: Vec<ExamAttempt>,
+    #[serde(default)]
+    pub appelli: Vec<Appello>,
+    #[serde(default)]
+    pub target_appello: Option<String>,
+    /// Ids of the exams to pass first (propedeuticità), see `prereq`
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    #[serde(default)]
+    pub activity: Activity,
+    /// Id of the elective group the exam belongs to
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CareerExam {
+    pub fn is_passed(&self) -> bool {
+        self.status == ExamStatus::Passed
+    }
+
+    /// The appello chosen with `select_appello`, if it still exists.
+    pub fn target(&self) -> Option<&Appello> {
+        let id = self.target_appello.as_deref()?;
+        self.appelli.iter().find(|a| a.id == id)
+    }
+
+    /// Date the student is preparing for: the target appello, else the
+    /// earliest scheduled attempt, else `examDate`.
+    pub fn planned_date(&self) -> Option<&str> {
+        if let Some(appello) = self.target().filter(|a| !a.date.is_empty()) {
+            return Some(appello.date.as_str());
+        }
+        self.attempts.iter()
+            .filter(|a| a.outcome == AttemptOutcome::Scheduled && !a.date.is_empty())
+            .map(|a| a.date.as_str())
+            .min()
+            .or(Some(self.exam_date.as_str()).filter(|d| !d.is_empty()))
+    }
+
+    /// Next date on or after `today` (YYYY-MM-DD) the exam is planned for:
+    /// the target appello, else the earliest upcoming scheduled attempt.
+    pub fn next_attempt_date(&self, today: &str) -> Option<&str> {
+        if let Some(appello) = self.target().filter(|a| a.date.as_str() >= today) {
+            return Some(appello.date.as_str());
+        }
+        if self.attempts.is_empty() {
+            return Some(self.exam_date.as_str()).filter(|d| !d.is_empty() && *d >= today);
+        }
+        self.attempts.iter()
+            .filter(|a| a.outcome == AttemptOutcome::Scheduled && a.date.as_str() >= today)
+            .map(|a| a.date.as_str())
+            .min()
+    }
+
+    /// Number of attempts actually sat (any outcome but scheduled).
+    pub fn attempts_sat(&self) -> usize {
+        self.attempts.iter().filter(|a| a.is_concluded()).count()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Career {
+    #[serde(default)]
+    pub university: String,
+    #[serde(default)]
+    pub course: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub total_cfu: u32,
+    #[serde(default)]
+    pub years: u32,
+    #[serde(default)]
+    pub exams: Vec<CareerExam>,
+    #[serde(default)]
+    pub elective_groups: Vec<ElectiveGroup>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Career {
+    /// Typed career from career.json; `null` (no career yet) is an empty one.
+    pub fn from_value(value: &Value) -> Result<Career, String> {
+        if value.is_null() {
+            return Ok(Career::default());
+        }
+        serde_json::from_value(value.clone()).map_err(|e| format!("career.json non valido: {}", e))
+    }
+
+    /// Consistency problems, one message per issue; empty when valid.
+    /// Values older files contain are reported by `warnings` instead.
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        let mut group_ids = std::collections::HashSet::new();
+        for group in &self.elective_groups {
+            if group.id.is_empty() || !group_ids.insert(group.id.as_str()) {
+                issues.push(format!("Gruppo a scelta \"{}\" senza ID o con ID duplicato", group.name));
+            }
+            if group.cfu == 0 {
+                issues.push(format!("Gruppo a scelta \"{}\": i CFU richiesti devono essere maggiori di zero", group.name));
+            }
+        }
+        if self.exams.iter().filter(|e| e.activity == Activity::Thesis).count() > 1 {
+            issues.push("Più di una prova finale nel piano".to_string());
+        }
+        let mut seen = std::collections::HashSet::new();
+        for exam in &self.exams {
+            let label = if exam.name.is_empty() { exam.id.as_str() } else { exam.name.as_str() };
+            if exam.name.trim().is_empty() {
+                issues.push(format!("Esame {} senza nome", exam.id));
+            }
+            if !exam.id.is_empty() && !seen.insert(exam.id.as_str()) {
+                issues.push(format!("ID esame duplicato: {}", exam.id));
+            }
+            match (exam.status, exam.grade) {
+                (ExamStatus::Passed, None) if exam.activity == Activity::Exam => {
+                    issues.push(format!("{}: esame superato senza voto", label));
+                }
+                (ExamStatus::Pending, Some(_)) => issues.push(format!("{}: voto presente su un esame non superato", label)),
+                _ => {}
+            }
+            let passed = exam.attempts.iter().filter(|a| a.outcome == AttemptOutcome::Passed).count();
+            if passed > 1 {
+                issues.push(format!("{}: più di un tentativo superato", label));
+            }
+            let mut appello_ids = std::collections::HashSet::new();
+            for appello in &exam.appelli {
+                if appello.id.is_empty() || !appello_ids.insert(appello.id.as_str()) {
+                    issues.push(format!("{}: appello senza ID o con ID duplicato", label));
+                }
+                if chrono::NaiveDate::parse_from_str(&appello.date, "%Y-%m-%d").is_err() {
+                    issues.push(format!("{}: data appello non valida: {}", label, appello.date));
+                }
+                for d in [&appello.registration_opens, &appello.registration_deadline] {
+                    if !d.is_empty() && chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() {
+                        issues.push(format!("{}: data iscrizione non valida: {}", label, d));
+                    }
+                }
+                if !appello.registration_deadline.is_empty() && appello.registration_deadline > appello.date {
+                    issues.push(format!("{}: scadenza iscrizione dopo l'appello del {}", label, appello.date));
+                }
+                if !appello.registration_opens.is_empty() && !appello.registration_deadline.is_empty()
+                    && appello.registration_opens > appello.registration_deadline
+                {
+                    issues.push(format!("{}: apertura iscrizioni dopo la scadenza (appello del {})", label, appello.date));
+                }
+            }
+            if let Some(group) = exam.group.as_deref().filter(|g| !group_ids.contains(g)) {
+                issues.push(format!("{}: gruppo a scelta inesistente ({})", label, group));
+            }
+            for prereq in &exam.prerequisites {
+                if *prereq == exam.id {
+                    issues.push(format!("{}: un esame non può essere propedeutico a se stesso", label));
+                } else if !self.exams.iter().any(|e| e.id == *prereq) {
+                    issues.push(format!("{}: propedeuticità verso un esame inesistente ({})", label, prereq));
+                }
+            }
+            if exam.target_appello.is_some() && exam.target().is_none() {
+                issues.push(format!("{}: appello scelto inesistente", label));
+            }
+            for attempt in &exam.attempts {
+                if !attempt.date.is_empty() && chrono::NaiveDate::parse_from_str(&attempt.date, "%Y-%m-%d").is_err() {
+                    issues.push(format!("{}: data tentativo non valida: {}", label, attempt.date));
+                }
+                match (attempt.outcome, attempt.grade) {
+                    (AttemptOutcome::Scheduled, _) if attempt.date.is_empty() => {
+                        issues.push(format!("{}: appello prenotato senza data", label));
+                    }
+                    (AttemptOutcome::Passed, None) if exam.activity == Activity::Exam => {
+                        issues.push(format!("{}: tentativo del {} senza voto", label, attempt.date));
+                    }
+                    (AttemptOutcome::Rejected, None) => {
+                        issues.push(format!("{}: tentativo del {} senza voto", label, attempt.date));
+                    }
+                    (AttemptOutcome::Scheduled | AttemptOutcome::Failed | AttemptOutcome::Withdrawn, Some(_)) => {
+                        issues.push(format!("{}: voto presente su un tentativo non superato ({})", label, attempt.date));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        issues
+    }
+
+    /// Values that don't fit the plan but that career.json files written by
+    /// older versions contain (exams without CFU, semester 0, a year past
+    /// the course length): reported, never blocking a save.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for exam in &self.exams {
+            let label = if exam.name.is_empty() { exam.id.as_str() } else { exam.name.as_str() };
+            if exam.cfu == 0 {
+                warnings.push(format!("{}: i CFU devono essere maggiori di zero", label));
+            }
+            if self.years > 0 && !(1..=self.years).contains(&exam.year) {
+                warnings.push(format!("{}: anno {} fuori dal corso ({} anni)", label, exam.year, self.years));
+            }
+            if !(1..=2).contains(&exam.sem) {
+                warnings.push(format!("{}: semestre {} non valido", label, exam.sem));
+            }
+        }
+        warnings
+    }
+
+    /// Upcoming appelli of pending exams (date ≥ `today`), by date, with the
+    /// registration state on `today`.
+    pub fn upcoming_appelli(&self, today: &str) -> Vec<Value> {
+        let mut out: Vec<(&str, Value)> = Vec::new();
+        for exam in self.exams.iter().filter(|e| !e.is_passed()) {
+            for appello in exam.appelli.iter().filter(|a| a.date.as_str() >= today) {
+                let days_to_deadline = chrono::NaiveDate::parse_from_str(&appello.registration_deadline, "%Y-%m-%d")
+                    .ok()
+                    .zip(chrono::NaiveDate::parse_from_str(today, "%Y-%m-%d").ok())
+                    .map(|(deadline, today)| (deadline - today).num_days());
+                out.push((appello.date.as_str(), serde_json::json!({
+                    "examId": exam.id,
+                    "examName": exam.name,
+                    "cfu": exam.cfu,
+                    "appelloId": appello.id,
+                    "date": appello.date,
+                    "time": appello.time,
+                    "registrationOpens": appello.registration_opens,
+                    "registrationDeadline": appello.registration_deadline,
+                    "registration": appello.registration_state(today),
+                    "daysToDeadline": days_to_deadline,
+                    "registered": appello.registered,
+                    "selected": exam.target_appello.as_deref() == Some(appello.id.as_str()),
+                })));
+            }
+        }
+        out.sort_by(|a, b| a.0.cmp(b.0));
+        out.into_iter().map(|(_, v)| v).collect()
+    }
+}
+
+fn find_exam<'a>(career: &'a mut Value, exam_id: &str) -> Result<&'a mut Value, String> {
+    career.get_mut("exams")
+        .and_then(|e| e.as_array_mut())
+        .and_then(|exams| exams.iter_mut().find(|e| e.get("id").and_then(|i| i.as_str()) == Some(exam_id)))
+        .ok_or_else(|| format!("Esame non trovato: {}", exam_id))
+}
+
+fn has_appello(exam: &Value, appello_id: &str) -> bool {
+    exam.get("appelli")
+        .and_then(|a| a.as_array())
+        .is_some_and(|list| list.iter().any(|a| a.get("id").and_then(|i| i.as_str()) == Some(appello_id)))
+}
+
+/// Makes `appello_id` the target appello of `exam_id` (`None` clears it).
+/// The booking is recorded as a scheduled attempt tagged with the appello
+/// id, replacing the one of the previous target.
+pub fn select_appello(career: &mut Value, exam_id: &str, appello_id: Option<&str>) -> Result<(), String> {
+    let exam = find_exam(career, exam_id)?;
+    if exam.get("status").and_then(|s| s.as_str()) == Some("passed") {
+        return Err("Esame già superato".to_string());
+    }
+    if let Some(id) = appello_id {
+        if !has_appello(exam, id) {
+            return Err(format!("Appello non trovato: {}", id));
+        }
+    }
+
+    let previous = exam.get("targetAppello").and_then(|t| t.as_str()).map(str::to_string);
+    if !exam.get("attempts").is_some_and(|a| a.is_array()) {
+        exam["attempts"] = Value::Array(legacy_attempts(exam));
+    }
+    let date = appello_id.and_then(|id| {
+        exam["appelli"].as_array()?.iter()
+            .find(|a| a.get("id").and_then(|i| i.as_str()) == Some(id))?
+            .get("date").cloned()
+    });
+    let attempts = exam["attempts"].as_array_mut().expect("attempts is an array");
+    attempts.retain(|a| {
+        let booked = a.get("outcome").and_then(|o| o.as_str()) == Some("scheduled");
+        let tag = a.get("appello").and_then(|t| t.as_str());
+        // Drop the previous target's booking; a new target also replaces
+        // untagged bookings (the single `examDate` of older files)
+        !(booked && match tag {
+            Some(tag) => Some(tag) == previous.as_deref(),
+            None => appello_id.is_some(),
+        })
+    });
+    if let (Some(id), Some(date)) = (appello_id, date) {
+        attempts.push(serde_json::json!({ "date": date, "outcome": "scheduled", "appello": id }));
+    }
+    attempts.sort_by(|a, b| {
+        let date = |v: &Value| v.get("date").and_then(|d| d.as_str()).unwrap_or("").to_string();
+        date(a).cmp(&date(b))
+    });
+    exam["targetAppello"] = appello_id.map(Value::from).unwrap_or(Value::Null);
+    // Re-derived by `migrate` from the bookings left, if any
+    exam["examDate"] = Value::from("");
+    migrate(career);
+    Ok(())
+}
+
+/// Marks the registration to an appello as done (or not).
+pub fn set_appello_registered(career: &mut Value, exam_id: &str, appello_id: &str, registered: bool) -> Result<(), String> {
+    let exam = find_exam(career, exam_id)?;
+    let appello = exam.get_mut("appelli")
+        .and_then(|a| a.as_array_mut())
+        .and_then(|list| list.iter_mut().find(|a| a.get("id").and_then(|i| i.as_str()) == Some(appello_id)))
+        .ok_or_else(|| format!("Appello non trovato: {}", appello_id))?;
+    appello["registered"] = Value::Bool(registered);
+    Ok(())
+}
+
+/// Normalizes a grade field in place; returns whether it changed.
+fn normalize_grade(holder: &mut Value) -> bool {
+    let grade = match holder.get("grade") {
+        Some(Value::String(s)) if s.trim().is_empty() => {
+            holder["grade"] = Value::Null;
+            return true;
+        }
+        Some(g) if !g.is_null() => g.clone(),
+        _ => return false,
+    };
+    match Grade::parse(&grade) {
+        Ok(parsed) if parsed.to_value() != grade => {
+            holder["grade"] = parsed.to_value();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Builds `attempts` from the single-attempt fields of older files.
+fn legacy_attempts(exam: &Value) -> Vec<Value> {
+    let field = |k: &str| exam.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let mut attempts = Vec::new();
+    if exam.get("status").and_then(|s| s.as_str()) == Some("passed") {
+        attempts.push(serde_json::json!({
+            "date": field("passDate"),
+            "outcome": "passed",
+            "grade": exam.get("grade").cloned().unwrap_or(Value::Null),
+        }));
+    } else if !field("examDate").is_empty() {
+        attempts.push(serde_json::json!({ "date": field("examDate"), "outcome": "scheduled" }));
+    }
+    attempts
+}
+
+/// Summary fields (`status`, `grade`, `passDate`, `examDate`) as implied by
+/// the attempt history.
+fn summary_from_attempts(attempts: &[Value]) -> [(&'static str, Value); 4] {
+    let outcome = |a: &Value| a.get("outcome").and_then(|o| o.as_str()).unwrap_or("scheduled").to_string();
+    let date = |a: &Value| a.get("date").and_then(|d| d.as_str()).unwrap_or("").to_string();
+    let passed = attempts.iter().find(|a| outcome(a) == "passed");
+    let next = attempts.iter()
+        .filter(|a| outcome(a) == "scheduled")
+        .map(date)
+        .filter(|d| !d.is_empty())
+        .min();
+    match passed {
+        Some(a) => [
+            ("status", Value::from("passed")),
+            ("grade", a.get("grade").cloned().unwrap_or(Value::Null)),
+            ("passDate", Value::from(date(a))),
+            ("examDate", Value::from("")),
+        ],
+        None => [
+            ("status", Value::from("pending")),
+            ("grade", Value::Null),
+            ("passDate", Value::from("")),
+            ("examDate", Value::from(next.unwrap_or_default())),
+        ],
+    }
+}
+
+/// Rewrites legacy values in place: grade `31` becomes "30L", numeric
+/// strings become numbers and empty strings become `null`; exams without an
+/// attempt history get one from their single-attempt fields, and the summary
+/// fields are re-derived from `attempts`.  Returns whether anything changed.
+pub fn migrate(career: &mut Value) -> bool {
+    let mut changed = false;
+    if let Some(exams) = career.get_mut("exams").and_then(|e| e.as_array_mut()) {
+        for exam in exams.iter_mut() {
+            if !exam.is_object() { continue; }
+            changed |= normalize_grade(exam);
+            let target_date = exam.get("targetAppello")
+                .and_then(|t| t.as_str())
+                .and_then(|id| exam.get("appelli")?.as_array()?.iter().find(|a| a.get("id").and_then(|i| i.as_str()) == Some(id)))
+                .and_then(|a| a.get("date"))
+                .cloned();
+            if !exam.get("attempts").is_some_and(|a| a.is_array()) {
+                let attempts = legacy_attempts(exam);
+                // With a target the history is started even when empty: the
+                // `examDate` derived below is not a booking to import again
+                if !attempts.is_empty() || target_date.is_some() {
+                    exam["attempts"] = Value::Array(attempts);
+                    changed = true;
+                }
+            }
+            if let Some(attempts) = exam.get_mut("attempts").and_then(|a| a.as_array_mut()) {
+                for attempt in attempts.iter_mut().filter(|a| a.is_object()) {
+                    changed |= normalize_grade(attempt);
+                }
+            }
+            let attempts = exam.get("attempts").and_then(|a| a.as_array()).map(Vec::as_slice).unwrap_or(&[]);
+            if attempts.is_empty() && target_date.is_none() { continue; }
+            let mut summary = summary_from_attempts(attempts);
+            if let (Some(date), Value::String(status)) = (target_date, &summary[0].1) {
+                if status == "pending" { summary[3].1 = date; }
+            }
+            for (key, value) in summary {
+                if exam.get(key) != Some(&value) {
+                    exam[key] = value;
+                    changed = true;
+                }
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn career(exams: Value) -> Value {
+        json!({ "course": "Informatica", "years": 3, "exams": exams })
+    }
+
+    #[test]
+    fn legacy_grade_31_becomes_lode() {
+        let mut data = career(json!([
+            { "id": "a", "name": "Analisi", "cfu": 9, "status": "passed", "grade": 31, "passDate": "2024-02-01" },
+            { "id": "b", "name": "Fisica", "cfu": 6, "status": "passed", "grade": "28", "passDate": "2024-06-20" },
+            { "id": "c", "name": "Chimica", "cfu": 6, "status": "pending", "grade": "" },
+        ]));
+        assert!(migrate(&mut data));
+        assert_eq!(data["exams"][0]["grade"], "30L");
+        assert_eq!(data["exams"][0]["attempts"], json!([{ "date": "2024-02-01", "outcome": "passed", "grade": "30L" }]));
+        assert_eq!(data["exams"][1]["grade"], 28);
+        assert_eq!(data["exams"][2]["grade"], Value::Null);
+        let parsed = Career::from_value(&data).unwrap();
+        assert_eq!(parsed.exams[0].grade, Some(Grade::Lode));
+        assert!(parsed.validate().is_empty());
+    }
+
+    #[test]
+    fn legacy_fields_become_attempts() {
+        let passed = json!({ "status": "passed", "grade": 27, "passDate": "2024-01-15", "examDate": "2024-01-15" });
+        assert_eq!(legacy_attempts(&passed), vec![json!({ "date": "2024-01-15", "outcome": "passed", "grade": 27 })]);
+        let booked = json!({ "status": "pending", "examDate": "2024-06-10" });
+        assert_eq!(legacy_attempts(&booked), vec![json!({ "date": "2024-06-10", "outcome": "scheduled" })]);
+        assert!(legacy_attempts(&json!({ "status": "pending", "examDate": "" })).is_empty());
+    }
+
+    #[test]
+    fn summary_follows_the_attempt_history() {
+        let attempts = [
+            json!({ "date": "2024-01-10", "outcome": "failed" }),
+            json!({ "date": "2024-06-10", "outcome": "scheduled" }),
+            json!({ "date": "2024-02-01", "outcome": "scheduled" }),
+        ];
+        assert_eq!(summary_from_attempts(&attempts), [
+            ("status", json!("pending")),
+            ("grade", Value::Null),
+            ("passDate", json!("")),
+            ("examDate", json!("2024-02-01")),
+        ]);
+        let attempts = [
+            json!({ "date": "2024-01-10", "outcome": "rejected", "grade": 19 }),
+            json!({ "date": "2024-02-01", "outcome": "passed", "grade": 25 }),
+        ];
+        assert_eq!(summary_from_attempts(&attempts), [
+            ("status", json!("passed")),
+            ("grade", json!(25)),
+            ("passDate", json!("2024-02-01")),
+            ("examDate", json!("")),
+        ]);
+
+        // The summary saved in the file is overwritten by the history
+        let mut data = career(json!([{
+            "id": "a", "name": "Analisi", "cfu": 9, "status": "pending", "examDate": "2023-12-01",
+            "attempts": [{ "date": "2024-02-01", "outcome": "passed", "grade": "30 e lode" }],
+        }]));
+        assert!(migrate(&mut data));
+        assert_eq!(data["exams"][0]["status"], "passed");
+        assert_eq!(data["exams"][0]["grade"], "30L");
+        assert_eq!(data["exams"][0]["passDate"], "2024-02-01");
+        assert_eq!(data["exams"][0]["examDate"], "");
+    }
+
+    #[test]
+    fn idoneo_and_convalidato() {
+        for (text, grade) in [("Idonea", Grade::Idoneo), ("idoneità", Grade::Idoneo), ("riconosciuto", Grade::Convalidato), ("Convalidato", Grade::Convalidato)] {
+            assert_eq!(Grade::parse(&json!(text)), Ok(grade));
+        }
+        assert!(Grade::parse(&json!(17)).is_err());
+        assert!(Grade::parse(&json!("buono")).is_err());
+
+        let mut data = career(json!([
+            { "id": "a", "name": "Inglese", "cfu": 3, "status": "passed", "grade": "Idonea", "passDate": "2024-02-01" },
+            { "id": "b", "name": "Analisi", "cfu": 9, "status": "passed", "grade": "riconosciuto" },
+            { "id": "c", "name": "Tirocinio", "cfu": 6, "activity": "internship", "status": "passed", "passDate": "2024-07-01" },
+            { "id": "d", "name": "Fisica", "cfu": 6, "status": "passed", "passDate": "2024-07-01" },
+        ]));
+        migrate(&mut data);
+        assert_eq!(data["exams"][0]["grade"], "idoneo");
+        assert_eq!(data["exams"][1]["grade"], "convalidato");
+        // Only a regular exam needs a grade to be passed
+        assert_eq!(Career::from_value(&data).unwrap().validate(), vec![
+            "Fisica: esame superato senza voto",
+            "Fisica: tentativo del 2024-07-01 senza voto",
+        ]);
+    }
+
+    #[test]
+    fn legacy_plan_values_are_warnings_not_errors() {
+        let data = career(json!([
+            { "id": "a", "name": "Analisi", "cfu": 0, "year": 1, "sem": 1 },
+            { "id": "b", "name": "Fisica", "cfu": 6, "year": 4, "sem": 0 },
+        ]));
+        let parsed = Career::from_value(&data).unwrap();
+        assert!(parsed.validate().is_empty());
+        assert_eq!(parsed.warnings(), vec![
+            "Analisi: i CFU devono essere maggiori di zero",
+            "Fisica: anno 4 fuori dal corso (3 anni)",
+            "Fisica: semestre 0 non valido",
+        ]);
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let mut data = career(json!([
+            { "id": "a", "name": "Analisi", "cfu": 9, "status": "passed", "grade": 31, "passDate": "2024-02-01" },
+            { "id": "b", "name": "Fisica", "cfu": 6, "status": "pending", "examDate": "2024-06-10", "grade": "" },
+            { "id": "c", "name": "Chimica", "cfu": 6, "targetAppello": "x",
+              "appelli": [{ "id": "x", "date": "2024-09-01" }] },
+            { "id": "d", "name": "Inglese", "cfu": 3 },
+        ]));
+        assert!(migrate(&mut data));
+        let once = data.clone();
+        assert!(!migrate(&mut data));
+        assert_eq!(data, once);
+        assert_eq!(data["exams"][2]["examDate"], "2024-09-01");
+        assert_eq!(data["exams"][2]["attempts"], json!([]));
+        assert!(data["exams"][3].get("attempts").is_none());
+    }
+
+    #[test]
+    fn select_appello_books_the_target_and_replaces_the_previous_booking() {
+        let mut data = career(json!([{
+            "id": "a", "name": "Analisi", "cfu": 9, "status": "pending", "examDate": "2024-05-01",
+            "appelli": [{ "id": "x", "date": "2024-06-10" }, { "id": "y", "date": "2024-07-01" }],
+        }]));
+        migrate(&mut data);
+
+        // The untagged booking of the old `examDate` is replaced
+        select_appello(&mut data, "a", Some("x")).unwrap();
+        assert_eq!(data["exams"][0]["attempts"], json!([{ "date": "2024-06-10", "outcome": "scheduled", "appello": "x" }]));
+        assert_eq!(data["exams"][0]["examDate"], "2024-06-10");
+
+        select_appello(&mut data, "a", Some("y")).unwrap();
+        assert_eq!(data["exams"][0]["attempts"], json!([{ "date": "2024-07-01", "outcome": "scheduled", "appello": "y" }]));
+        assert_eq!(data["exams"][0]["targetAppello"], "y");
+
+        select_appello(&mut data, "a", None).unwrap();
+        assert_eq!(data["exams"][0]["attempts"], json!([]));
+        assert_eq!(data["exams"][0]["targetAppello"], Value::Null);
+        assert_eq!(data["exams"][0]["examDate"], "");
+
+        assert_eq!(select_appello(&mut data, "a", Some("z")), Err("Appello non trovato: z".to_string()));
+        assert_eq!(select_appello(&mut data, "b", None), Err("Esame non trovato: b".to_string()));
+        data["exams"][0]["status"] = json!("passed");
+        assert_eq!(select_appello(&mut data, "a", Some("x")), Err("Esame già superato".to_string()));
+    }
+}
@@ -0,0 +1,226 @@
+// ===== Careers =====
+//
+// A profile can hold several careers (e.g. a triennale and then a
+// magistrale).  career.json is always the active one, so every reader of it
+// (stats, widget, notifications, briefings) follows the active career
+// unchanged; the others are kept whole in careers.json, an array of career
+// objects with the date they were archived (`archivedAt`).  Careers get an
+// `id` when created or archived, and at startup if they were saved without
+// one.
+//
+// Exams passed in one career can be recognized in another (riconoscimento
+// CFU): they are copied as passed with the original grade and date and keep
+// a `recognized` reference to where they come from.
+
+use serde_json::Value;
+
+/// Whether `career` is the placeholder of "no career yet" (`null`, `{}` or a
+/// reset career without a type).
+pub fn is_empty(career: &Value) -> bool {
+    career.get("type").and_then(|t| t.as_str()).unwrap_or("").is_empty()
+        && !career.get("exams").and_then(|e| e.as_array()).is_some_and(|e| !e.is_empty())
+}
+
+/// Gives `career` an id if it has none; returns whether it changed.
+pub fn ensure_id(career: &mut Value) -> bool {
+    let missing = career.get("id").and_then(|i| i.as_str()).unwrap_or("").is_empty();
+    if missing {
+        if let Some(obj) = career.as_object_mut() {
+            obj.insert("id".to_string(), Value::from(uuid::Uuid::new_v4().to_string()));
+            return true;
+        }
+    }
+    false
+}
+
+pub fn id(career: &Value) -> &str {
+    career.get("id").and_then(|i| i.as_str()).unwrap_or("")
+}
+
+/// Course name, falling back to the type ("Magistrale") for careers created
+/// without one.
+pub fn label(career: &Value) -> String {
+    let field = |k: &str| career.get(k).and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+    let course = field("course");
+    if !course.is_empty() { return course; }
+    let kind = field("type");
+    if kind.is_empty() { "Carriera".to_string() } else { kind }
+}
+
+/// Entry for `list_careers`: identity plus `stats` (from
+/// `stats::career_stats`, `Null` when the career can't be read).
+pub fn summary(career: &Value, stats: &Value, active: bool) -> Value {
+    let field = |k: &str| career.get(k).cloned().unwrap_or(Value::Null);
+    serde_json::json!({
+        "id": id(career),
+        "label": label(career),
+        "university": field("university"),
+        "course": field("course"),
+        "type": field("type"),
+        "active": active,
+        "archivedAt": field("archivedAt"),
+        "cfuEarned": stats["cfuEarned"],
+        "cfuTotal": stats["cfuTotal"],
+        "examsPassed": stats["examsPassed"],
+        "examsTotal": stats["examsTotal"],
+        "weightedAverage": stats["weightedAverage"],
+    })
+}
+
+/// Copies the passed exams `exam_ids` of `source` into `target` as
+/// recognized, and returns how many were recognized.
+///
+/// An exam of `target` with the same name (case-insensitive) is marked
+/// passed instead of being duplicated.  New exams go to year 1, semester 1
+/// and keep their `activity`.  They get the source id, or a free "<id>-ric",
+/// "<id>-ric2"… when `target` already uses it.  Grades are kept; exams
+/// passed without one (tirocinio) are recognized as "convalidato".
+pub fn recognize(target: &mut Value, source: &Value, exam_ids: &[String]) -> Result<usize, String> {
+    let source_exams = source.get("exams").and_then(|e| e.as_array()).map(Vec::as_slice).unwrap_or(&[]);
+    if !target.get("exams").is_some_and(|e| e.is_array()) {
+        target["exams"] = Value::Array(vec![]);
+    }
+    let from = serde_json::json!({ "careerId": id(source), "career": label(source) });
+    let mut count = 0;
+    for exam_id in exam_ids {
+        let exam = source_exams.iter()
+            .find(|e| e.get("id").and_then(|i| i.as_str()) == Some(exam_id.as_str()))
+            .ok_or_else(|| format!("Esame {} non trovato nella carriera {}", exam_id, label(source)))?;
+        let name = exam.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
+        if exam.get("status").and_then(|s| s.as_str()) != Some("passed") {
+            return Err(format!("{}: solo gli esami superati possono essere riconosciuti", name));
+        }
+        let grade = match exam.get("grade") {
+            Some(g) if !g.is_null() => g.clone(),
+            _ => Value::from("convalidato"),
+        };
+        let attempt = serde_json::json!({
+            "date": exam.get("passDate").cloned().unwrap_or(Value::from("")),
+            "outcome": "passed",
+            "grade": grade,
+        });
+        let mut recognized = from.clone();
+        recognized["examId"] = Value::from(exam_id.as_str());
+
+        let exams = target["exams"].as_array_mut().expect("exams is an array");
+        let existing = exams.iter_mut().find(|e| {
+            e.get("name").and_then(|n| n.as_str()).is_some_and(|n| n.trim().eq_ignore_ascii_case(name.trim()))
+        });
+        match existing {
+            Some(e) => {
+                e["attempts"] = Value::Array(vec![attempt]);
+                e["examDate"] = Value::from("");
+                e["recognized"] = recognized;
+            }
+            None => {
+                let taken = |id: &str| exams.iter().any(|e| e.get("id").and_then(|i| i.as_str()) == Some(id));
+                let mut new_id = exam_id.clone();
+                let mut n = 1;
+                while taken(&new_id) {
+                    new_id = if n == 1 { format!("{}-ric", exam_id) } else { format!("{}-ric{}", exam_id, n) };
+                    n += 1;
+                }
+                let mut new_exam = serde_json::json!({
+                    "id": new_id, "name": name, "cfu": exam.get("cfu").cloned().unwrap_or(Value::from(0)),
+                    "year": 1, "sem": 1, "status": "pending", "grade": null, "passDate": "", "examDate": "",
+                    "progress": 0, "pdfs": [], "attempts": [attempt], "recognized": recognized,
+                });
+                if let Some(activity) = exam.get("activity").filter(|a| !a.is_null()) {
+                    new_exam["activity"] = activity.clone();
+                }
+                exams.push(new_exam);
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source() -> Value {
+        json!({ "id": "tri", "course": "Informatica", "type": "triennale", "exams": [
+            { "id": "an", "name": "Analisi I", "cfu": 9, "status": "passed", "grade": "30L", "passDate": "2024-02-01" },
+            { "id": "ing", "name": "Inglese", "cfu": 3, "status": "passed", "grade": null, "passDate": "2024-06-10" },
+            { "id": "tir", "name": "Tirocinio", "cfu": 6, "activity": "internship", "status": "passed", "passDate": "2025-03-01" },
+            { "id": "fi", "name": "Fisica", "cfu": 6, "status": "pending" },
+        ]})
+    }
+
+    fn ids(career: &Value) -> Vec<&str> {
+        career["exams"].as_array().unwrap().iter().map(|e| e["id"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn recognized_exams_get_a_free_id() {
+        let mut target = json!({ "id": "mag", "type": "magistrale", "exams": [
+            { "id": "an", "name": "Analisi avanzata", "cfu": 6 },
+            { "id": "an-ric", "name": "Algebra", "cfu": 6 },
+            { "id": "ing-ric", "name": "Statistica", "cfu": 6 },
+        ]});
+        let count = recognize(&mut target, &source(), &["an".to_string(), "ing".to_string(), "tir".to_string()]).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(ids(&target), ["an", "an-ric", "ing-ric", "an-ric2", "ing", "tir"]);
+
+        let analisi = &target["exams"][3];
+        assert_eq!(analisi["name"], "Analisi I");
+        assert_eq!((analisi["year"].clone(), analisi["sem"].clone()), (json!(1), json!(1)));
+        assert_eq!(analisi["attempts"], json!([{ "date": "2024-02-01", "outcome": "passed", "grade": "30L" }]));
+        assert_eq!(analisi["recognized"], json!({ "careerId": "tri", "career": "Informatica", "examId": "an" }));
+        // Passed without a grade: convalidato, activity kept
+        assert_eq!(target["exams"][4]["attempts"][0]["grade"], "convalidato");
+        assert_eq!(target["exams"][5]["activity"], "internship");
+        assert!(target["exams"][4].get("activity").is_none());
+
+        // The summary fields follow the attempts once migrated
+        crate::career::migrate(&mut target);
+        assert_eq!(target["exams"][3]["status"], "passed");
+        assert_eq!(target["exams"][3]["passDate"], "2024-02-01");
+    }
+
+    #[test]
+    fn exam_with_the_same_name_is_marked_passed() {
+        let mut target = json!({ "exams": [
+            { "id": "x1", "name": " analisi i ", "cfu": 12, "year": 2, "examDate": "2025-01-10",
+              "attempts": [{ "date": "2025-01-10", "outcome": "scheduled" }] },
+        ]});
+        assert_eq!(recognize(&mut target, &source(), &["an".to_string()]), Ok(1));
+        assert_eq!(ids(&target), ["x1"]);
+        let exam = &target["exams"][0];
+        assert_eq!(exam["attempts"], json!([{ "date": "2024-02-01", "outcome": "passed", "grade": "30L" }]));
+        assert_eq!(exam["examDate"], "");
+        assert_eq!((exam["cfu"].clone(), exam["year"].clone()), (json!(12), json!(2)));
+        assert_eq!(exam["recognized"]["examId"], "an");
+    }
+
+    #[test]
+    fn only_known_passed_exams_are_recognized() {
+        let mut target = json!({});
+        assert_eq!(
+            recognize(&mut target, &source(), &["fi".to_string()]),
+            Err("Fisica: solo gli esami superati possono essere riconosciuti".to_string()),
+        );
+        assert_eq!(
+            recognize(&mut target, &source(), &["zz".to_string()]),
+            Err("Esame zz non trovato nella carriera Informatica".to_string()),
+        );
+        assert_eq!(recognize(&mut target, &source(), &[]), Ok(0));
+        assert_eq!(target["exams"], json!([]));
+    }
+
+    #[test]
+    fn empty_careers_and_ids() {
+        assert!(is_empty(&Value::Null));
+        assert!(is_empty(&json!({ "type": "", "exams": [] })));
+        assert!(!is_empty(&json!({ "exams": [{ "id": "a" }] })));
+        let mut career = json!({ "type": "magistrale" });
+        assert!(ensure_id(&mut career));
+        assert!(!id(&career).is_empty());
+        assert!(!ensure_id(&mut career));
+        assert_eq!(label(&career), "magistrale");
+    }
+}
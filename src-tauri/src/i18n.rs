@@ -0,0 +1,190 @@
+// ===== Localization =====
+//
+// Message catalog for every user-facing string produced by the backend
+// (notifications, tray menu).  The frontend keeps its own strings.
+//
+// Templates use `{name}` placeholders.  Plural-sensitive messages are stored
+// as two keys, `<key>.one` and `<key>.other`, and are resolved through `tn()`,
+// which also provides the `{n}` placeholder.  A key missing from a catalog
+// falls back to Italian, then to the key itself.
+
+use serde_json::Value;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    It,
+    En,
+}
+
+impl Lang {
+    pub fn from_code(code: &str) -> Option<Lang> {
+        let code = code.trim().to_ascii_lowercase();
+        if code.starts_with("it") {
+            Some(Lang::It)
+        } else if code.starts_with("en") {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    /// Language chosen in settings.json (`language`: "it", "en" or "auto").
+    /// "auto" follows the OS locale; a missing setting keeps Italian.
+    pub fn from_settings(settings: &Value) -> Lang {
+        match settings.get("language").and_then(|v| v.as_str()) {
+            Some("auto") => tauri_plugin_os::locale()
+                .and_then(|l| Lang::from_code(&l))
+                .unwrap_or(Lang::En),
+            Some(code) => Lang::from_code(code).unwrap_or(Lang::It),
+            None => Lang::It,
+        }
+    }
+}
+
+const IT: &[(&str, &str)] = &[
+    ("title.remind_tomorrow", "StudyPlan — Promemoria domani"),
+    ("title.remind_today", "StudyPlan — Promemoria oggi"),
+    ("title.soon", "StudyPlan — Tra poco"),
+    ("title.morning", "StudyPlan — Riepilogo mattutino"),
+    ("title.afternoon", "StudyPlan — Riepilogo pomeridiano"),
+    ("title.evening", "StudyPlan — Riepilogo serale"),
+    ("title.countdown", "StudyPlan — Countdown esame"),
+    ("title.escalate", "StudyPlan — Esame oggi"),
+    ("title.registration", "StudyPlan — Iscrizione appello"),
+    ("body.remind_tomorrow", "Domani: {title} alle {time}"),
+    ("body.remind_today", "Oggi: {title} alle {time}"),
+    ("body.soon.one", "{title} tra {n} minuto"),
+    ("body.soon.other", "{title} tra {n} minuti"),
+    ("events.one", "{n} impegno"),
+    ("events.other", "{n} impegni"),
+    ("body.evening.both", "Completati {done}/{total}. Domani: {events}."),
+    ("body.evening.today.one", "Completato {done}/{n} impegno di oggi."),
+    ("body.evening.today.other", "Completati {done}/{n} impegni di oggi."),
+    ("body.evening.tomorrow", "{events} in programma per domani."),
+    ("body.countdown.tomorrow", "Domani l'esame di {exam} — preparazione {progress}%."),
+    ("body.countdown.one", "Manca {n} giorno all'esame di {exam} — preparazione {progress}%."),
+    ("body.countdown.other", "Mancano {n} giorni all'esame di {exam} — preparazione {progress}%."),
+    ("body.escalate.one", "{title} alle {time}, tra {n} minuto. Apri StudyPlan per confermare."),
+    ("body.escalate.other", "{title} alle {time}, tra {n} minuti. Apri StudyPlan per confermare."),
+    ("body.registration.today", "Ultimo giorno per iscriversi all'appello di {exam} del {date}."),
+    ("body.registration.one", "Manca {n} giorno alla chiusura delle iscrizioni all'appello di {exam} del {date}."),
+    ("body.registration.other", "Mancano {n} giorni alla chiusura delle iscrizioni all'appello di {exam} del {date}."),
+    ("briefing.morning.header", "{events} in programma per oggi:"),
+    ("briefing.afternoon.header.one", "{n} impegno ancora da completare oggi:"),
+    ("briefing.afternoon.header.other", "{n} impegni ancora da completare oggi:"),
+    ("briefing.item", "• {time} {title}"),
+    ("briefing.item_untimed", "• {title}"),
+    ("briefing.more.one", "…e un altro."),
+    ("briefing.more.other", "…e altri {n}."),
+    ("briefing.exam.today", "Oggi l'esame di {exam}!"),
+    ("briefing.exam.tomorrow", "Domani l'esame di {exam}."),
+    ("briefing.exam.days.one", "Prossimo esame: {exam} tra {n} giorno."),
+    ("briefing.exam.days.other", "Prossimo esame: {exam} tra {n} giorni."),
+    ("briefing.yesterday.one", "Ieri: completato {done}/{n} impegno ({pct}%)."),
+    ("briefing.yesterday.other", "Ieri: completati {done}/{n} impegni ({pct}%)."),
+    ("title.refill", "StudyPlan — Promemoria in scadenza"),
+    ("body.refill", "Apri StudyPlan per continuare a ricevere i promemoria dei prossimi giorni."),
+    ("body.first_launch", "Le notifiche sono attive! Riceverai promemoria per i tuoi impegni."),
+    ("title.test", "StudyPlan — Notifica di prova"),
+    ("body.test", "Se leggi questo messaggio, le notifiche funzionano."),
+    ("tray.open", "Apri StudyPlan"),
+    ("tray.widget", "Widget"),
+    ("tray.quit", "Esci"),
+    ("fallback.event", "Evento"),
+    ("fallback.exam", "Esame"),
+];
+
+const EN: &[(&str, &str)] = &[
+    ("title.remind_tomorrow", "StudyPlan — Reminder for tomorrow"),
+    ("title.remind_today", "StudyPlan — Reminder for today"),
+    ("title.soon", "StudyPlan — Coming up"),
+    ("title.morning", "StudyPlan — Morning briefing"),
+    ("title.afternoon", "StudyPlan — Afternoon briefing"),
+    ("title.evening", "StudyPlan — Evening briefing"),
+    ("title.countdown", "StudyPlan — Exam countdown"),
+    ("title.escalate", "StudyPlan — Exam today"),
+    ("title.registration", "StudyPlan — Exam registration"),
+    ("body.remind_tomorrow", "Tomorrow: {title} at {time}"),
+    ("body.remind_today", "Today: {title} at {time}"),
+    ("body.soon.one", "{title} in {n} minute"),
+    ("body.soon.other", "{title} in {n} minutes"),
+    ("events.one", "{n} event"),
+    ("events.other", "{n} events"),
+    ("body.evening.both", "Completed {done}/{total}. Tomorrow: {events}."),
+    ("body.evening.today.one", "Completed {done}/{n} event today."),
+    ("body.evening.today.other", "Completed {done}/{n} events today."),
+    ("body.evening.tomorrow", "{events} scheduled for tomorrow."),
+    ("body.countdown.tomorrow", "Your {exam} exam is tomorrow — {progress}% prepared."),
+    ("body.countdown.one", "{n} day until your {exam} exam — {progress}% prepared."),
+    ("body.countdown.other", "{n} days until your {exam} exam — {progress}% prepared."),
+    ("body.escalate.one", "{title} at {time}, in {n} minute. Open StudyPlan to confirm."),
+    ("body.escalate.other", "{title} at {time}, in {n} minutes. Open StudyPlan to confirm."),
+    ("body.registration.today", "Last day to register for the {exam} exam on {date}."),
+    ("body.registration.one", "Registration for the {exam} exam on {date} closes in {n} day."),
+    ("body.registration.other", "Registration for the {exam} exam on {date} closes in {n} days."),
+    ("briefing.morning.header", "{events} scheduled for today:"),
+    ("briefing.afternoon.header.one", "{n} event still to complete today:"),
+    ("briefing.afternoon.header.other", "{n} events still to complete today:"),
+    ("briefing.item", "• {time} {title}"),
+    ("briefing.item_untimed", "• {title}"),
+    ("briefing.more.one", "…and one more."),
+    ("briefing.more.other", "…and {n} more."),
+    ("briefing.exam.today", "Your {exam} exam is today!"),
+    ("briefing.exam.tomorrow", "Your {exam} exam is tomorrow."),
+    ("briefing.exam.days.one", "Next exam: {exam} in {n} day."),
+    ("briefing.exam.days.other", "Next exam: {exam} in {n} days."),
+    ("briefing.yesterday.one", "Yesterday: completed {done}/{n} event ({pct}%)."),
+    ("briefing.yesterday.other", "Yesterday: completed {done}/{n} events ({pct}%)."),
+    ("title.refill", "StudyPlan — Reminders running out"),
+    ("body.refill", "Open StudyPlan to keep receiving reminders for the coming days."),
+    ("body.first_launch", "Notifications are on! You'll get reminders for your events."),
+    ("title.test", "StudyPlan — Test notification"),
+    ("body.test", "If you can read this, notifications are working."),
+    ("tray.open", "Open StudyPlan"),
+    ("tray.widget", "Widget"),
+    ("tray.quit", "Quit"),
+    ("fallback.event", "Event"),
+    ("fallback.exam", "Exam"),
+];
+
+fn catalog(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::It => IT,
+        Lang::En => EN,
+    }
+}
+
+fn lookup(lang: Lang, key: &str) -> Option<&'static str> {
+    let find = |cat: &'static [(&'static str, &'static str)]| {
+        cat.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    };
+    find(catalog(lang)).or_else(|| find(IT))
+}
+
+/// Italian and English share the same rule: singular only for exactly one.
+fn plural_suffix(_lang: Lang, n: i64) -> &'static str {
+    if n == 1 { "one" } else { "other" }
+}
+
+/// Plain message without placeholders.
+pub fn t(lang: Lang, key: &str) -> String {
+    lookup(lang, key).map(str::to_string).unwrap_or_else(|| key.to_string())
+}
+
+/// Message with `{name}` placeholders.
+pub fn tf(lang: Lang, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = t(lang, key);
+    for (name, value) in args {
+        out = out.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    out
+}
+
+/// Plural message: picks `<key>.one` / `<key>.other` from `n` and fills `{n}`.
+pub fn tn(lang: Lang, key: &str, n: i64, args: &[(&str, &dyn Display)]) -> String {
+    let full_key = format!("{}.{}", key, plural_suffix(lang, n));
+    let mut all: Vec<(&str, &dyn Display)> = vec![("n", &n)];
+    all.extend_from_slice(args);
+    tf(lang, &full_key, &all)
+}
@@ -1,18 +1,50 @@
+mod attachments;
+mod briefing;
+mod career;
+mod careers;
+mod i18n;
+mod notifications;
+mod pdf;
+mod pdf_index;
+mod prereq;
+mod report;
+mod requirements;
+mod stats;
+mod template;
+mod transcript;
+
 use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{Manager, State, AppHandle, Emitter};
 use tauri_plugin_notification::NotificationExt;
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use tauri_plugin_notification::Schedule;
 use chrono::Datelike;
+use briefing::BriefingKind;
+use i18n::Lang;
+use notifications::{Clock as _, DeliveryStatus, NotificationData, SystemClock, DEFAULT_COUNTDOWN_DAYS};
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+use notifications::{Notification, NotificationSink, Scheduler};
 #[cfg(any(target_os = "android", target_os = "ios"))]
 use chrono::TimeZone as _;
 
 // ===== State =====
 pub struct AppState {
     data_dir: Mutex<PathBuf>,
+    /// Last mobile scheduling plan (horizon, counts); `null` on desktop.
+    notification_plan: Mutex<Value>,
+    /// Scheduler heartbeat, next notification and last delivery outcome.
+    delivery_status: Mutex<DeliveryStatus>,
+    /// Full-text index of pdf-notes/ and its background indexer.
+    pdf_index: Mutex<pdf_index::IndexState>,
+    /// Legacy attachment names renamed at startup → their hash names; applied
+    /// to data saved by a frontend that loaded it before the migration.
+    renamed_attachments: Mutex<std::collections::BTreeMap<String, String>>,
+    /// Wakes the desktop scheduler so it re-reads the JSON state.
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    scheduler_wake: tokio::sync::Notify,
 }
 
 // ===== Helpers =====
@@ -20,7 +52,7 @@ fn get_data_dir(state: &State<AppState>) -> PathBuf {
     state.data_dir.lock().unwrap().clone()
 }
 
-fn read_json(dir: &PathBuf, name: &str, fallback: Value) -> Value {
+fn read_json(dir: &Path, name: &str, fallback: Value) -> Value {
     let path = dir.join(format!("{}.json", name));
     if path.exists() {
         match fs::read_to_string(&path) {
@@ -32,13 +64,30 @@ fn read_json(dir: &PathBuf, name: &str, fallback: Value) -> Value {
     }
 }
 
-fn write_json(dir: &PathBuf, name: &str, data: &Value) {
+fn write_json(dir: &Path, name: &str, data: &Value) {
+    if let Err(e) = try_write_json(dir, name, data) {
+        eprintln!("[StudyPlan] Failed to write {}.json: {}", name, e);
+    }
+}
+
+/// Writes through a temporary file and a rename, so the file on disk is
+/// either the old or the new content, never a partial write.
+fn try_write_json(dir: &Path, name: &str, data: &Value) -> Result<(), String> {
     let path = dir.join(format!("{}.json", name));
     if let Some(parent) = path.parent() {
         let _ = fs::create_dir_all(parent);
     }
-    let content = serde_json::to_string_pretty(data).unwrap_or_default();
-    let _ = fs::write(&path, content);
+    let content = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    let tmp = dir.join(format!("{}.json.tmp", name));
+    fs::write(&tmp, content).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+fn settings_lang(dir: &Path) -> Lang {
+    Lang::from_settings(&read_json(dir, "settings", Value::Null))
 }
 
 // ===== Data Commands =====
@@ -50,8 +99,11 @@ fn load_events(state: State<AppState>) -> Value {
 }
 
 #[tauri::command]
-fn save_events(app: AppHandle, state: State<AppState>, events: Value) -> bool {
+fn save_events(app: AppHandle, state: State<AppState>, mut events: Value) -> bool {
     let dir = get_data_dir(&state);
+    let settings = read_json(&dir, "settings", Value::Null);
+    notifications::apply_default_reminders(&mut events, &settings);
+    attachments::rename_refs(&mut events, &state.renamed_attachments.lock().unwrap());
     write_json(&dir, "events", &events);
     sync_notifications(&app, &dir);
     true
@@ -78,7 +130,13 @@ fn load_settings(state: State<AppState>) -> Value {
         "morningNotif": true,
         "eveningNotif": true,
         "morningTime": "07:30",
-        "eveningTime": "21:00"
+        "eveningTime": "21:00",
+        "language": "it",
+        "briefingMaxItems": briefing::DEFAULT_BRIEFING_MAX_ITEMS,
+        "examCountdownNotif": true,
+        "examCountdownDays": DEFAULT_COUNTDOWN_DAYS,
+        "examCountdownTime": "09:00",
+        "categoryNotif": notifications::default_category_notif_settings()
     });
     read_json(&dir, "settings", default_settings)
 }
@@ -88,20 +146,57 @@ fn save_settings(app: AppHandle, state: State<AppState>, settings: Value) -> boo
     let dir = get_data_dir(&state);
     write_json(&dir, "settings", &settings);
     sync_notifications(&app, &dir);
+    #[cfg(desktop)]
+    refresh_tray_menu(&app, Lang::from_settings(&settings));
     true
 }
 
 #[tauri::command]
 fn load_career(state: State<AppState>) -> Value {
     let dir = get_data_dir(&state);
-    read_json(&dir, "career", Value::Null)
+    let mut data = read_json(&dir, "career", Value::Null);
+    if career::migrate(&mut data) {
+        eprintln!("[StudyPlan] career.json migrated to the current grade format");
+        write_json(&dir, "career", &data);
+    }
+    data
 }
 
+/// Saves career.json after migrating legacy grades; rejects data that does
+/// not fit the career model or whose edit closes a prerequisite cycle.
+/// Returns the exams unlocked by newly passed prerequisites and warnings for
+/// exams passed before their prerequisites, for the cycles already saved and
+/// for the plan values `Career::warnings`
+/// reports that the save introduces (an old career.json does not repeat
+/// the ones it already had on every save).
 #[tauri::command]
-fn save_career(state: State<AppState>, data: Value) -> bool {
+fn save_career(app: AppHandle, state: State<AppState>, mut data: Value) -> Result<Value, String> {
+    career::migrate(&mut data);
+    attachments::rename_refs(&mut data, &state.renamed_attachments.lock().unwrap());
+    let parsed = career::Career::from_value(&data)?;
+    let issues = parsed.validate();
+    if !issues.is_empty() {
+        return Err(issues.join("\n"));
+    }
     let dir = get_data_dir(&state);
+    let mut previous = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut previous);
+    let previous = career::Career::from_value(&previous).unwrap_or_default();
+    let cycles = prereq::check(&previous, &parsed)?;
+    stats::StatsRules::resolve(&parsed, None)?.check()?;
+    let (unlocked, mut warnings) = prereq::on_save(&previous, &parsed);
+    warnings.extend(cycles);
+    let known = previous.warnings();
+    warnings.extend(parsed.warnings().into_iter().filter(|w| !known.contains(w)));
+    for warning in &warnings {
+        eprintln!("[StudyPlan] {}", warning);
+    }
     write_json(&dir, "career", &data);
-    true
+    sync_notifications(&app, &dir);
+    // Attachments dropped from the exams (delete_pdf keeps them while the
+    // saved career still references them)
+    collect_orphans(&app, &dir, true);
+    Ok(serde_json::json!({ "saved": true, "unlocked": unlocked, "warnings": warnings }))
 }
 
 // ===== Platform =====
@@ -244,11 +339,30 @@ fn bio_clear() -> Result<bool, String> {
 
 // ===== PDF =====
 
+/// Path of the attachment `file_name` in pdf-notes/.  `None` unless the
+/// name is one plain file name: `Path::join` keeps `..` and replaces the
+/// base with an absolute path, so the name is checked before joining.
+fn attachment_path(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let plain = !file_name.contains(['/', '\\'])
+        && !file_name.contains("..")
+        && matches!(Path::new(file_name).components().collect::<Vec<_>>().as_slice(), [std::path::Component::Normal(_)]);
+    plain.then(|| dir.join("pdf-notes").join(file_name))
+}
+
+/// Picks a PDF and stores it in pdf-notes/.  Off the main thread: the
+/// dialog, the copy and the page count (in the worker process, which can
+/// take up to its timeout on a malformed file) would freeze the UI.
 #[tauri::command]
-fn pick_pdf(state: State<AppState>, app: AppHandle) -> Result<Option<Value>, String> {
+async fn pick_pdf(app: AppHandle) -> Result<Option<Value>, String> {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    tauri::async_runtime::spawn_blocking(move || pick_and_store_pdf(&app, &dir))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn pick_and_store_pdf(app: &AppHandle, dir: &Path) -> Result<Option<Value>, String> {
     use tauri_plugin_dialog::DialogExt;
     
-    let dir = get_data_dir(&state);
     let pdf_dir = dir.join("pdf-notes");
     let _ = fs::create_dir_all(&pdf_dir);
     
@@ -264,78 +378,480 @@ fn pick_pdf(state: State<AppState>, app: AppHandle) -> Result<Option<Value>, Str
                 .map(|n: &std::ffi::OsStr| n.to_string_lossy().to_string())
                 .unwrap_or_else(|| "document.pdf".to_string());
             
-            let timestamp = chrono::Utc::now().timestamp_millis();
-            let file_name = format!("{}_{}", timestamp, original_name);
+            // Stored by content hash: the same file picked again is reused
+            let (file_name, duplicate) = attachments::store(&pdf_dir, &src)?;
             let dest = pdf_dir.join(&file_name);
+            if duplicate {
+                eprintln!("[StudyPlan] {} already stored as {}", original_name, file_name);
+            }
             
-            fs::copy(&src, &dest).map_err(|e| e.to_string())?;
+            // The attachment is kept even if it can't be parsed; the UI
+            // shows `pagesError` and lets the user enter the pages by hand.
+            let (pages, pages_error) = match pdf::page_count(&dest) {
+                Ok(n) => (Value::from(n), Value::Null),
+                Err(e) => {
+                    eprintln!("[StudyPlan] {}: {}", original_name, e);
+                    (Value::Null, Value::from(e))
+                }
+            };
+            spawn_pdf_indexing(app);
             
             Ok(Some(serde_json::json!({
                 "fileName": file_name,
-                "originalName": original_name
+                "originalName": original_name,
+                "pages": pages,
+                "pagesError": pages_error,
+                "duplicate": duplicate
             })))
         },
         None => Ok(None),
     }
 }
 
+/// Opens an attachment in the system viewer, at `page` when the viewer
+/// accepts one on the command line (see `open_pdf_at_page`).
 #[tauri::command]
-fn open_pdf(state: State<AppState>, file_name: String) -> bool {
+fn open_pdf(state: State<AppState>, file_name: String, page: Option<u32>) -> bool {
     let dir = get_data_dir(&state);
-    let pdf_dir = dir.join("pdf-notes");
-    let path = pdf_dir.join(&file_name);
-    
-    // Security: ensure path doesn't escape pdf_dir
-    if !path.starts_with(&pdf_dir) {
+    let Some(path) = attachment_path(&dir, &file_name) else {
         return false;
-    }
+    };
     
     if path.exists() {
         #[cfg(desktop)]
-        { let _ = open::that(&path); }
+        {
+            let at_page = page.filter(|p| *p > 1).is_some_and(|p| open_pdf_at_page(&path, p));
+            if !at_page {
+                let _ = open::that(&path);
+            }
+        }
+        #[cfg(not(desktop))]
+        let _ = page;
         true
     } else {
         false
     }
 }
 
+/// Launches the default PDF viewer at `page` if it is one that takes a
+/// page argument: Evince, Atril, Xreader, Okular or Zathura on Linux.
+/// Other viewers (and macOS/Windows, whose default viewers have no such
+/// option) return `false` and the file is opened at the first page.
+#[cfg(desktop)]
+fn open_pdf_at_page(path: &Path, page: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let default = std::process::Command::new("xdg-mime")
+            .args(["query", "default", "application/pdf"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_lowercase())
+            .unwrap_or_default();
+        let page = page.to_string();
+        let viewer: Option<(&str, &str)> = if default.contains("evince") {
+            Some(("evince", "--page-index"))
+        } else if default.contains("atril") {
+            Some(("atril", "--page-index"))
+        } else if default.contains("xreader") {
+            Some(("xreader", "--page-index"))
+        } else if default.contains("okular") {
+            Some(("okular", "--page"))
+        } else if default.contains("zathura") {
+            Some(("zathura", "--page"))
+        } else {
+            None
+        };
+        if let Some((cmd, flag)) = viewer {
+            let spawned = std::process::Command::new(cmd).arg(format!("{}={}", flag, page)).arg(path).spawn();
+            return match spawned {
+                Ok(mut child) => {
+                    std::thread::spawn(move || child.wait());
+                    true
+                }
+                Err(_) => false,
+            };
+        }
+    }
+    let _ = (path, page);
+    false
+}
+
+/// Removes an attachment unless a saved career still references it: the
+/// same file can be attached to several exams, and the dialogs call this
+/// before saving the career.  Files kept here are collected by
+/// `collect_orphans` once they are no longer referenced.
 #[tauri::command]
-fn delete_pdf(state: State<AppState>, file_name: String) -> bool {
+fn delete_pdf(app: AppHandle, state: State<AppState>, file_name: String) -> bool {
     let dir = get_data_dir(&state);
-    let pdf_dir = dir.join("pdf-notes");
-    let path = pdf_dir.join(&file_name);
-    
-    if !path.starts_with(&pdf_dir) {
+    let Some(path) = attachment_path(&dir, &file_name) else {
+        return false;
+    };
+    if attachment_refs(&dir).contains_key(&file_name) {
         return false;
     }
     
     if path.exists() {
-        fs::remove_file(&path).is_ok()
+        let removed = fs::remove_file(&path).is_ok();
+        remove_thumbnails(&dir, &file_name);
+        spawn_pdf_indexing(&app);
+        removed
     } else {
         false
     }
 }
 
+/// Page count of an attachment, read in the worker process off the main
+/// thread.
 #[tauri::command]
-fn get_pdf_pages(state: State<AppState>, file_name: String) -> u32 {
-    let dir = get_data_dir(&state);
-    let pdf_dir = dir.join("pdf-notes");
-    let path = pdf_dir.join(&file_name);
+async fn get_pdf_pages(app: AppHandle, file_name: String) -> Result<u32, String> {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    let path = attachment_path(&dir, &file_name)
+        .filter(|p| p.is_file())
+        .ok_or_else(|| format!("File non trovato: {}", file_name))?;
     
-    if !path.starts_with(&pdf_dir) || !path.exists() {
-        return 0;
-    }
+    tauri::async_runtime::spawn_blocking(move || pdf::page_count(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Outline of an attached PDF: `chapters` are the non-overlapping entries
+/// to track study progress on, `outline` every bookmark with its level.
+/// Both are empty for a PDF without bookmarks.  Read in the worker process
+/// off the main thread.
+#[tauri::command]
+async fn get_pdf_outline(app: AppHandle, file_name: String) -> Result<Value, String> {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    let path = attachment_path(&dir, &file_name)
+        .filter(|p| p.is_file())
+        .ok_or_else(|| format!("File non trovato: {}", file_name))?;
     
-    // Simple PDF page count by counting /Type /Page occurrences
-    if let Ok(content) = fs::read(&path) {
-        let content_str = String::from_utf8_lossy(&content);
-        // Count "/Type /Page" but not "/Type /Pages"
-        content_str.matches("/Type /Page\n").count() as u32
-            + content_str.matches("/Type /Page\r").count() as u32
-            + content_str.matches("/Type /Page ").count() as u32
+    let (pages, outline) = tauri::async_runtime::spawn_blocking(move || pdf::outline_of(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+    let chapters = pdf::tracked_chapters(&outline);
+    Ok(serde_json::json!({
+        "pages": pages,
+        "chapters": chapters.iter().map(pdf::Chapter::to_json).collect::<Vec<_>>(),
+        "outline": outline.iter().map(pdf::Chapter::to_json).collect::<Vec<_>>(),
+    }))
+}
+
+/// PNG preview of `page` (default 1) of an attachment, `width` pixels wide
+/// (default 240), cached in pdf-thumbs/: `{ page, width, height, dataUrl }`.
+/// Rendered off the main thread.
+#[tauri::command]
+async fn get_pdf_thumbnail(app: AppHandle, file_name: String, page: Option<u32>, width: Option<u32>) -> Result<Value, String> {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    let path = attachment_path(&dir, &file_name)
+        .filter(|p| p.is_file())
+        .ok_or_else(|| format!("File non trovato: {}", file_name))?;
+
+    // The bundled Pdfium (next to the executable on Windows, among the
+    // resources on macOS) first
+    let mut lib_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(Path::to_path_buf)) {
+        lib_dirs.push(exe_dir);
+    }
+    if let Ok(resources) = app.path().resource_dir() {
+        lib_dirs.push(resources);
+    }
+    lib_dirs.push(dir.clone());
+
+    let page = page.unwrap_or(1).max(1);
+    let cache_dir = dir.join(pdf::THUMBS_DIR);
+    let png = tauri::async_runtime::spawn_blocking(move || {
+        // Legacy names are hashed here; they keep the hash once migrated
+        let hash = match attachments::hash_of(&file_name) {
+            Some(hash) => hash.to_string(),
+            None => attachments::hash_file(&path)?,
+        };
+        pdf::thumbnail(&path, &hash, &cache_dir, page, width.unwrap_or(pdf::THUMB_WIDTH), &lib_dirs)
+    }).await.map_err(|e| e.to_string())??;
+
+    let bytes = fs::read(&png).map_err(|e| e.to_string())?;
+    let (w, h) = image::image_dimensions(&png).unwrap_or((0, 0));
+    use base64::Engine as _;
+    Ok(serde_json::json!({
+        "page": page,
+        "width": w,
+        "height": h,
+        "dataUrl": format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&bytes)),
+    }))
+}
+
+/// Drops the cached previews of a removed attachment.  Legacy names are
+/// skipped: their content lives on under its hash after the migration.
+fn remove_thumbnails(dir: &Path, file_name: &str) {
+    if let Some(hash) = attachments::hash_of(file_name) {
+        pdf::remove_thumbnails(&dir.join(pdf::THUMBS_DIR), hash);
+    }
+}
+
+// ── Full-text search ──
+
+/// Brings the full-text index up to date on a background thread: new and
+/// changed PDFs are extracted, deleted ones dropped.  A call while the
+/// indexer runs makes it go over the directory again when it's done.
+/// Emits `pdf-index-updated` with the progress after each file and once
+/// more when the run is over (only if something changed, so listeners can
+/// search again without starting a loop).
+fn spawn_pdf_indexing(app: &AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        let mut idx = state.pdf_index.lock().unwrap();
+        if idx.running {
+            idx.pending = true;
+            return;
+        }
+        idx.running = true;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let mut changed = false;
+        loop {
+            let dir = state.data_dir.lock().unwrap().clone();
+            changed |= index_pdfs(&app, &dir);
+            let mut idx = state.pdf_index.lock().unwrap();
+            if idx.pending {
+                idx.pending = false;
+                continue;
+            }
+            idx.running = false;
+            idx.progress = (0, 0);
+            break;
+        }
+        if changed {
+            let _ = app.emit("pdf-index-updated", serde_json::json!({ "indexing": false }));
+        }
+    });
+}
+
+/// One pass over pdf-notes/; returns whether the index changed.
+fn index_pdfs(app: &AppHandle, dir: &Path) -> bool {
+    let state = app.state::<AppState>();
+    let pdf_dir = dir.join("pdf-notes");
+    let todo = {
+        let mut idx = state.pdf_index.lock().unwrap();
+        let index = idx.index_for(dir);
+        let (todo, gone) = pdf_index::stale(index, &pdf_dir);
+        if todo.is_empty() && gone.is_empty() {
+            return false;
+        }
+        for name in &gone {
+            index.files.remove(name);
+        }
+        idx.progress = (0, todo.len());
+        todo
+    };
+    eprintln!("[StudyPlan] Indexing {} PDF(s)", todo.len());
+
+    for (i, file) in todo.iter().enumerate() {
+        let entry = pdf_index::extract(&pdf_dir, file);
+        let failed = entry.error.clone();
+        let mut idx = state.pdf_index.lock().unwrap();
+        idx.index_for(dir).files.insert(file.file_name.clone(), entry);
+        idx.progress = (i + 1, todo.len());
+        // Failures are saved at once, so a file that can't be read isn't
+        // tried again at the next launch even if this run is cut short
+        if let Some(e) = failed {
+            eprintln!("[StudyPlan] Not indexed {}: {}", file.file_name, e);
+            if let Err(e) = idx.index_for(dir).save(dir) {
+                eprintln!("[StudyPlan] Failed to save PDF index: {}", e);
+            }
+        }
+        drop(idx);
+        let _ = app.emit("pdf-index-updated", serde_json::json!({
+            "indexing": true,
+            "progress": [i + 1, todo.len()],
+        }));
+    }
+
+    let mut idx = state.pdf_index.lock().unwrap();
+    if let Err(e) = idx.index_for(dir).save(dir) {
+        eprintln!("[StudyPlan] Failed to save PDF index: {}", e);
+    }
+    true
+}
+
+// ── Storage and integrity (see attachments.rs) ──
+
+/// How many exams of the active and archived careers use each attachment.
+fn attachment_refs(dir: &Path) -> std::collections::BTreeMap<String, usize> {
+    let active = read_json(dir, "career", Value::Null);
+    let archive = read_archive(dir);
+    attachments::refcounts(std::iter::once(&active).chain(&archive))
+}
+
+/// Removes the attachments no career references, with their previews;
+/// `grace` keeps the ones just imported.  Returns the removed files and
+/// the bytes freed.
+fn collect_orphans(app: &AppHandle, dir: &Path, grace: bool) -> (Vec<String>, u64) {
+    let (removed, freed) = attachments::collect(&dir.join("pdf-notes"), &attachment_refs(dir), grace, false);
+    if !removed.is_empty() {
+        for name in &removed {
+            remove_thumbnails(dir, name);
+        }
+        eprintln!("[StudyPlan] Removed {} orphaned attachment(s), {} bytes", removed.len(), freed);
+        spawn_pdf_indexing(app);
+    }
+    (removed, freed)
+}
+
+/// Startup maintenance of pdf-notes/, on the blocking pool: legacy files
+/// are migrated, orphans collected, then the full-text index updated.
+fn spawn_attachment_maintenance(app: &AppHandle, dir: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        match migrate_attachments(&app, &dir) {
+            // The old files are unreferenced now and go with the orphans
+            Ok(()) => { collect_orphans(&app, &dir, true); }
+            Err(e) => eprintln!("[StudyPlan] Attachment migration failed, old files kept: {}", e),
+        }
+        spawn_pdf_indexing(&app);
+    });
+}
+
+/// Copies attachments stored by older versions as "<timestamp>_<name>" to
+/// their hash and rewrites the references in the careers and in the study
+/// sessions.  The old files are never removed here: once nothing references
+/// them they are orphans, so a failed rewrite leaves the data as it was.
+fn migrate_attachments(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    let pdf_dir = dir.join("pdf-notes");
+    let renamed = attachments::migrate_legacy(&pdf_dir);
+    if renamed.is_empty() {
+        return Ok(());
+    }
+    *app.state::<AppState>().renamed_attachments.lock().unwrap() = renamed.clone();
+
+    // On the main thread, where the synchronous commands run, so no save
+    // interleaves with the read-modify-write below
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (main_dir, main_renamed) = (dir.to_path_buf(), renamed.clone());
+    app.run_on_main_thread(move || {
+        let result: Result<(), String> = ["career", "careers", "events"].into_iter().try_for_each(|name| {
+            let mut data = read_json(&main_dir, name, Value::Null);
+            if attachments::rename_refs(&mut data, &main_renamed) {
+                try_write_json(&main_dir, name, &data).map_err(|e| format!("{}.json: {}", name, e))?;
+            }
+            Ok(())
+        });
+        let _ = tx.send(result);
+    }).map_err(|e| e.to_string())?;
+    rx.recv().map_err(|e| e.to_string())??;
+    eprintln!("[StudyPlan] Migrated {} attachment(s) to content-addressed names", renamed.len());
+    Ok(())
+}
+
+/// Integrity check of pdf-notes/: every referenced file is hashed again.
+/// Reports `missing` and `altered` files (as `{ fileName, originalName,
+/// examId, examName }`), legacy names that can't be checked (`unchecked`),
+/// `orphans` no career references and how many files are `shared` by
+/// several exams.
+#[tauri::command]
+async fn verify_attachments(app: AppHandle) -> Result<Value, String> {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let refs = attachment_refs(&dir);
+        let report = attachments::verify(&dir.join("pdf-notes"), &refs);
+        if !report.missing.is_empty() || !report.altered.is_empty() {
+            eprintln!("[StudyPlan] Attachments: {} missing, {} altered", report.missing.len(), report.altered.len());
+        }
+        let owners = pdf_owners(&dir);
+        let mut result = report.to_json(&refs);
+        for key in ["missing", "altered", "unchecked"] {
+            let files: Vec<Value> = result[key].as_array().cloned().unwrap_or_default().into_iter()
+                .map(|name| {
+                    let mut file = owners.get(name.as_str().unwrap_or("")).cloned().unwrap_or_else(|| serde_json::json!({}));
+                    file["fileName"] = name;
+                    file
+                })
+                .collect();
+            result[key] = Value::Array(files);
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Garbage collection of pdf-notes/: removes the files no career
+/// references (imported in the last hour excepted, see attachments.rs).
+/// With `dry_run` only lists them.
+#[tauri::command]
+fn collect_attachments(app: AppHandle, state: State<AppState>, dry_run: Option<bool>) -> Value {
+    let dir = get_data_dir(&state);
+    let (removed, freed) = if dry_run.unwrap_or(false) {
+        attachments::collect(&dir.join("pdf-notes"), &attachment_refs(&dir), true, true)
     } else {
-        0
+        collect_orphans(&app, &dir, true)
+    };
+    serde_json::json!({ "removed": removed, "freed": freed })
+}
+
+/// Names and exams of the attachments of all careers, by file name.
+fn pdf_owners(dir: &Path) -> std::collections::HashMap<String, Value> {
+    let active = read_json(dir, "career", Value::Null);
+    let mut owners = std::collections::HashMap::new();
+    for career in std::iter::once(&active).chain(read_archive(dir).iter()) {
+        for exam in career["exams"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+            for pdf in exam["pdfs"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+                let Some(file_name) = pdf["fileName"].as_str() else { continue };
+                owners.entry(file_name.to_string()).or_insert_with(|| serde_json::json!({
+                    "originalName": pdf["originalName"],
+                    "examId": exam["id"],
+                    "examName": exam["name"],
+                }));
+            }
+        }
     }
+    owners
+}
+
+/// Full-text search in the attached PDFs: pages containing every word of
+/// `query`, as `{ fileName, originalName, examId, examName, page, snippet }`.
+/// `indexing` is set while new files are still being extracted, with
+/// `progress` `[done, total]`.
+#[tauri::command]
+fn search_pdfs(app: AppHandle, state: State<AppState>, query: String, limit: Option<usize>) -> Value {
+    let dir = get_data_dir(&state);
+    spawn_pdf_indexing(&app);
+    let limit = limit.unwrap_or(50).min(200);
+
+    let (hits, indexing, progress, files, pages) = {
+        let mut idx = state.pdf_index.lock().unwrap();
+        let (running, progress) = (idx.running, idx.progress);
+        let index = idx.index_for(&dir);
+        (pdf_index::search(index, &query, limit), running, progress, index.files.len(), index.page_count())
+    };
+
+    let owners = pdf_owners(&dir);
+    let pdf_dir = dir.join("pdf-notes");
+    let results: Vec<Value> = hits.into_iter()
+        // Deleted since the last indexing run
+        .filter(|h| pdf_dir.join(&h.file_name).is_file())
+        .map(|h| {
+            let owner = owners.get(&h.file_name);
+            let original = owner.and_then(|o| o["originalName"].as_str()).map(str::to_string)
+                // Legacy name "<timestamp>_<original name>"
+                .unwrap_or_else(|| h.file_name.split_once('_').map_or(h.file_name.clone(), |(_, n)| n.to_string()));
+            serde_json::json!({
+                "fileName": h.file_name,
+                "originalName": original,
+                "examId": owner.map_or(Value::Null, |o| o["examId"].clone()),
+                "examName": owner.map_or(Value::Null, |o| o["examName"].clone()),
+                "page": h.page,
+                "snippet": h.snippet,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "results": results,
+        "indexing": indexing,
+        "progress": [progress.0, progress.1],
+        "files": files,
+        "pages": pages,
+    })
 }
 
 // ===== Widget Data =====
@@ -364,12 +880,40 @@ fn get_widget_today(state: State<AppState>) -> Value {
 #[tauri::command]
 fn get_widget_exams(state: State<AppState>) -> Value {
     let dir = get_data_dir(&state);
-    let career = read_json(&dir, "career", Value::Null);
-    
+    let mut career = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut career);
+    let typed = career::Career::from_value(&career).unwrap_or_default();
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+
     if let Some(exams) = career.get("exams").and_then(|e| e.as_array()) {
         let mut filtered: Vec<Value> = exams.iter()
             .filter(|e| e.get("status").and_then(|s| s.as_str()) != Some("passed"))
             .cloned()
+            .map(|mut e| {
+                // Show the next booked attempt rather than a stale one
+                let id = e.get("id").and_then(|i| i.as_str()).unwrap_or("");
+                if let Some(exam) = typed.exams.iter().find(|x| x.id == id) {
+                    if let Some(obj) = e.as_object_mut() {
+                        if let Some(next) = exam.next_attempt_date(&today) {
+                            obj.insert("examDate".to_string(), Value::from(next));
+                        }
+                        obj.insert("attemptNumber".to_string(), Value::from(exam.attempts_sat() + 1));
+                    }
+                }
+                // Preview: first page still to read of the first notes PDF
+                let preview = e["pdfs"].as_array()
+                    .and_then(|pdfs| pdfs.iter().find(|p| p["type"] == "appunti" && p["fileName"].is_string()))
+                    .map(|p| {
+                        let total = p["pages"]["total"].as_u64().unwrap_or(0);
+                        let read = p["pages"]["read"].as_u64().unwrap_or(0);
+                        let page = if total > 0 { (read + 1).min(total) } else { 1 };
+                        serde_json::json!({ "fileName": p["fileName"], "page": page })
+                    });
+                if let Some(obj) = e.as_object_mut() {
+                    obj.insert("preview".to_string(), preview.unwrap_or(Value::Null));
+                }
+                e
+            })
             .collect();
         filtered.sort_by(|a, b| {
             let da = a.get("examDate").and_then(|d| d.as_str()).unwrap_or("zzzz");
@@ -421,7 +965,564 @@ fn get_widget_week(state: State<AppState>) -> Value {
 #[tauri::command]
 fn get_widget_career(state: State<AppState>) -> Value {
     let dir = get_data_dir(&state);
-    read_json(&dir, "career", Value::Null)
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let summary = career::Career::from_value(&data).and_then(|c| {
+        let rules = stats::StatsRules::resolve(&c, None)?;
+        stats::career_stats(&c, &rules, chrono::Local::now().date_naive())
+    });
+    if let (Ok(s), Some(obj)) = (summary, data.as_object_mut()) {
+        obj.insert("cfuEarned".to_string(), s["cfuEarned"].clone());
+        obj.insert("average".to_string(), s["weightedAverage"].clone());
+        obj.insert("baseScore".to_string(), s["baseScore"].clone());
+    }
+    data
+}
+
+// ===== Career Commands =====
+
+// ── Multiple careers (see careers.rs) ──
+
+/// Archived careers from careers.json.
+fn read_archive(dir: &Path) -> Vec<Value> {
+    read_json(dir, "careers", Value::Array(vec![])).as_array().cloned().unwrap_or_default()
+}
+
+/// Gives an id to the active and archived careers saved without one
+/// (before multiple careers); run once at startup, so the readers never
+/// write.
+fn ensure_career_ids(dir: &Path) {
+    let mut active = read_json(dir, "career", Value::Null);
+    if !careers::is_empty(&active) && careers::ensure_id(&mut active) {
+        write_json(dir, "career", &active);
+    }
+    let mut archive = read_archive(dir);
+    if archive.iter_mut().fold(false, |changed, c| careers::ensure_id(c) | changed) {
+        write_json(dir, "careers", &Value::Array(archive));
+    }
+}
+
+/// Moves `career` into careers.json, replacing an older copy with its id.
+fn archive_career(dir: &Path, mut career: Value) {
+    careers::ensure_id(&mut career);
+    career["archivedAt"] = Value::from(chrono::Local::now().format("%Y-%m-%d").to_string());
+    let mut archive = read_archive(dir);
+    archive.retain(|c| careers::id(c) != careers::id(&career));
+    archive.push(career);
+    write_json(dir, "careers", &Value::Array(archive));
+}
+
+/// Statistics of a career in any state; `Null` when it can't be read.
+fn stats_of(career: &Value) -> Value {
+    let mut data = career.clone();
+    career::migrate(&mut data);
+    career::Career::from_value(&data)
+        .and_then(|c| {
+            let rules = stats::StatsRules::resolve(&c, None)?;
+            stats::career_stats(&c, &rules, chrono::Local::now().date_naive())
+        })
+        .unwrap_or(Value::Null)
+}
+
+/// The active career first, then the archived ones, most recently archived
+/// first, each with CFU and weighted average.
+#[tauri::command]
+fn list_careers(state: State<AppState>) -> Value {
+    career_list(&get_data_dir(&state))
+}
+
+/// Body of `list_careers`; only reads.
+fn career_list(dir: &Path) -> Value {
+    let mut list = Vec::new();
+    let active = read_json(dir, "career", Value::Null);
+    if !careers::is_empty(&active) {
+        list.push(careers::summary(&active, &stats_of(&active), true));
+    }
+    let mut archive = read_archive(dir);
+    archive.sort_by(|a, b| b["archivedAt"].as_str().cmp(&a["archivedAt"].as_str()));
+    list.extend(archive.iter().map(|c| careers::summary(c, &stats_of(c), false)));
+    Value::Array(list)
+}
+
+/// Starts a new career (`data` as for `save_career`) and archives the
+/// current one.  `recognize` lists passed exams of the current career to
+/// carry over as recognized CFU.  Returns the new career.
+#[tauri::command]
+fn create_career(
+    app: AppHandle,
+    state: State<AppState>,
+    mut data: Value,
+    recognize: Option<Vec<String>>,
+) -> Result<Value, String> {
+    let Some(obj) = data.as_object_mut() else {
+        return Err("Dati carriera non validi".to_string());
+    };
+    obj.remove("id");
+    obj.remove("archivedAt");
+    careers::ensure_id(&mut data);
+
+    let dir = get_data_dir(&state);
+    let mut current = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut current);
+    careers::ensure_id(&mut current);
+    let recognized = match recognize.filter(|ids| !ids.is_empty()) {
+        Some(ids) => careers::recognize(&mut data, &current, &ids)?,
+        None => 0,
+    };
+    career::migrate(&mut data);
+    let parsed = career::Career::from_value(&data)?;
+    let issues = parsed.validate();
+    if !issues.is_empty() {
+        return Err(issues.join("\n"));
+    }
+    prereq::check(&career::Career::default(), &parsed)?;
+
+    if !careers::is_empty(&current) {
+        archive_career(&dir, current);
+    }
+    write_json(&dir, "career", &data);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    eprintln!("[StudyPlan] Career created: {} ({} exams recognized)", careers::label(&data), recognized);
+    Ok(data)
+}
+
+/// Makes the archived career `id` the active one; the current one is
+/// archived.  Returns the activated career.
+#[tauri::command]
+fn switch_career(app: AppHandle, state: State<AppState>, id: String) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let chosen = activate_career(&dir, &id)?;
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    eprintln!("[StudyPlan] Active career: {}", careers::label(&chosen));
+    Ok(chosen)
+}
+
+/// Swaps the archived career `id` with the active one in the data files.
+fn activate_career(dir: &Path, id: &str) -> Result<Value, String> {
+    let mut archive = read_archive(dir);
+    let pos = archive.iter().position(|c| careers::id(c) == id)
+        .ok_or_else(|| "Carriera non trovata".to_string())?;
+    let mut chosen = archive.remove(pos);
+    if let Some(obj) = chosen.as_object_mut() {
+        obj.remove("archivedAt");
+    }
+    write_json(dir, "careers", &Value::Array(archive));
+
+    let current = read_json(dir, "career", Value::Null);
+    if !careers::is_empty(&current) {
+        archive_career(dir, current);
+    }
+    career::migrate(&mut chosen);
+    write_json(dir, "career", &chosen);
+    Ok(chosen)
+}
+
+/// Deletes an archived career and its PDF attachments (files still used by
+/// another career are kept).  The active career can't be deleted.
+#[tauri::command]
+fn delete_career(state: State<AppState>, id: String) -> Result<bool, String> {
+    let dir = get_data_dir(&state);
+    let active = read_json(&dir, "career", Value::Null);
+    if careers::id(&active) == id {
+        return Err("La carriera attiva non può essere eliminata: attivane prima un'altra".to_string());
+    }
+    let mut archive = read_archive(&dir);
+    let pos = archive.iter().position(|c| careers::id(c) == id)
+        .ok_or_else(|| "Carriera non trovata".to_string())?;
+    let removed = archive.remove(pos);
+    write_json(&dir, "careers", &Value::Array(archive.clone()));
+
+    let in_use = attachments::refcounts(std::iter::once(&active).chain(&archive));
+    for name in attachments::refcounts([&removed]).into_keys().filter(|f| !in_use.contains_key(f)) {
+        if let Some(path) = attachment_path(&dir, &name) {
+            if fs::remove_file(path).is_ok() {
+                remove_thumbnails(&dir, &name);
+            }
+        }
+    }
+    eprintln!("[StudyPlan] Career deleted: {}", careers::label(&removed));
+    Ok(true)
+}
+
+/// Averages, base di laurea, CFU by year/semester and projected graduation.
+/// `rules` overrides career.json `statsRules` field by field (e.g. to preview
+/// another base formula).
+#[tauri::command]
+fn get_career_stats(state: State<AppState>, rules: Option<Value>) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    let rules = stats::StatsRules::resolve(&career, rules.as_ref())?;
+    stats::career_stats(&career, &rules, chrono::Local::now().date_naive())
+}
+
+/// What-if simulation: `grades` maps pending exam ids to hypothetical grades;
+/// `target` is the base di laurea to reach, answered with the minimum average
+/// needed on the exams left without a grade.
+#[tauri::command]
+fn simulate_career(
+    state: State<AppState>,
+    grades: Option<Value>,
+    target: Option<f64>,
+    rules: Option<Value>,
+) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    let rules = stats::StatsRules::resolve(&career, rules.as_ref())?;
+    let grades = match grades {
+        Some(Value::Object(map)) => map,
+        None | Some(Value::Null) => Default::default(),
+        Some(_) => return Err("grades deve essere un oggetto { idEsame: voto }".to_string()),
+    };
+    stats::simulate(&career, &rules, &grades, target)
+}
+
+/// Imports a degree-program template (JSON or TOML, see
+/// docs/degree-template.md) into career.json; without `path` a file dialog
+/// is shown.  `replace` starts a new career, otherwise the template is merged
+/// into the current one.  `None` when the dialog is cancelled.
+#[tauri::command]
+fn import_degree_template(
+    app: AppHandle,
+    state: State<AppState>,
+    path: Option<String>,
+    replace: Option<bool>,
+) -> Result<Option<Value>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => match app.dialog().file().add_filter("Template corso", &["toml", "json"]).blocking_pick_file() {
+            Some(fp) => fp.into_path().map_err(|e| format!("Path error: {:?}", e))?,
+            None => return Ok(None),
+        },
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("Lettura template fallita: {}", e))?;
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+    let template = template::DegreeTemplate::parse(&text, name.as_deref())?;
+
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let before = career::Career::from_value(&data).unwrap_or_default();
+    let (added, updated) = template.apply(&mut data, replace.unwrap_or(false));
+    career::migrate(&mut data);
+    let parsed = career::Career::from_value(&data)?;
+    let issues = parsed.validate();
+    if !issues.is_empty() {
+        return Err(issues.join("\n"));
+    }
+    prereq::check(&before, &parsed)?;
+    write_json(&dir, "career", &data);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    eprintln!("[StudyPlan] Degree template imported: {} added, {} updated", added, updated);
+    Ok(Some(serde_json::json!({ "added": added, "updated": updated, "career": data })))
+}
+
+/// Writes the structure of the current career (exams, CFU, year, semester,
+/// prerequisites, elective groups; no grades) as a template.  TOML or JSON
+/// follows the file extension; without `path` a save dialog is shown.
+/// Returns the written path, `None` when the dialog is cancelled.
+#[tauri::command]
+fn export_degree_template(app: AppHandle, state: State<AppState>, path: Option<String>) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    let template = template::DegreeTemplate::from_career(&career);
+
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let course = if career.course.is_empty() { "corso".to_string() } else { career.course.clone() };
+            match app.dialog().file()
+                .add_filter("TOML", &["toml"])
+                .add_filter("JSON", &["json"])
+                .set_file_name(format!("{}.toml", course))
+                .blocking_save_file()
+            {
+                Some(fp) => fp.into_path().map_err(|e| format!("Path error: {:?}", e))?,
+                None => return Ok(None),
+            }
+        }
+    };
+    template.write(&path)?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// Imports the libretto exported by the university portal (CSV, XLSX, XLS
+/// or ODS) into career.json; without `path` a file dialog is shown.
+/// `mapping` picks columns by header or number (see
+/// `transcript::ColumnMapping`).  With `dry_run` nothing is saved and the
+/// result is only the preview of the changes.  `None` when the dialog is
+/// cancelled.
+#[tauri::command]
+fn import_transcript(
+    app: AppHandle,
+    state: State<AppState>,
+    path: Option<String>,
+    mapping: Option<Value>,
+    dry_run: Option<bool>,
+) -> Result<Option<Value>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let mapping: transcript::ColumnMapping = match mapping {
+        None | Some(Value::Null) => Default::default(),
+        Some(m) => serde_json::from_value(m).map_err(|e| format!("Mappatura colonne non valida: {}", e))?,
+    };
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => match app.dialog().file().add_filter("Libretto", &["csv", "xlsx", "xls", "ods"]).blocking_pick_file() {
+            Some(fp) => fp.into_path().map_err(|e| format!("Path error: {:?}", e))?,
+            None => return Ok(None),
+        },
+    };
+    let rows = transcript::read_table(&path)?;
+
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let before = career::Career::from_value(&data).unwrap_or_default();
+    let mut result = transcript::merge(&rows, &mapping, &mut data)?;
+    career::migrate(&mut data);
+    let merged = career::Career::from_value(&data)?;
+    let mut issues = merged.validate();
+    if let Err(e) = prereq::check(&before, &merged) {
+        issues.push(e);
+    }
+    let dry_run = dry_run.unwrap_or(false);
+    result["path"] = Value::from(path.to_string_lossy().to_string());
+    result["dryRun"] = Value::from(dry_run);
+    result["issues"] = serde_json::json!(issues);
+    if dry_run {
+        return Ok(Some(result));
+    }
+    if !issues.is_empty() {
+        return Err(issues.join("\n"));
+    }
+    write_json(&dir, "career", &data);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    eprintln!("[StudyPlan] Transcript imported: {} added, {} updated", result["added"], result["updated"]);
+    result["career"] = data;
+    Ok(Some(result))
+}
+
+/// Writes the career report (summary, CFU by year, passed exams, upcoming
+/// exams) as a self-contained HTML page or as PDF.  The format follows
+/// `format` ("html" or "pdf"), else the extension of the path; without
+/// `path` a save dialog is shown.  Returns the written path, `None` when the
+/// dialog is cancelled.
+#[tauri::command]
+fn export_career_report(
+    app: AppHandle,
+    state: State<AppState>,
+    path: Option<String>,
+    format: Option<String>,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    let rules = stats::StatsRules::resolve(&career, None)?;
+    let today = chrono::Local::now().date_naive();
+    let stats = stats::career_stats(&career, &rules, today)?;
+    let report = report::Report::build(&career, &stats, today);
+
+    let format = format.map(|f| f.to_lowercase());
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => {
+            let ext = format.as_deref().unwrap_or("pdf");
+            let mut dialog = app.dialog().file();
+            dialog = match ext {
+                "html" => dialog.add_filter("HTML", &["html"]).add_filter("PDF", &["pdf"]),
+                _ => dialog.add_filter("PDF", &["pdf"]).add_filter("HTML", &["html"]),
+            };
+            match dialog.set_file_name(format!("Carriera - {}.{}", report.course, ext)).blocking_save_file() {
+                Some(fp) => fp.into_path().map_err(|e| format!("Path error: {:?}", e))?,
+                None => return Ok(None),
+            }
+        }
+    };
+    let is_html = match format.as_deref() {
+        Some("html") => true,
+        Some("pdf") => false,
+        Some(other) => return Err(format!("Formato \"{}\" non supportato (html o pdf)", other)),
+        None => path.extension().is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm")),
+    };
+    let bytes = if is_html { report.to_html().into_bytes() } else { report.to_pdf() };
+    fs::write(&path, bytes).map_err(|e| format!("Scrittura report fallita: {}", e))?;
+    eprintln!("[StudyPlan] Career report exported: {:?}", path);
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+/// CFU required, earned and remaining per elective group and for the
+/// compulsory exams, tirocinio and prova finale status, and plan issues.
+#[tauri::command]
+fn get_requirements(state: State<AppState>) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    Ok(requirements::report(&career))
+}
+
+/// Prerequisite graph check: cycles, exams that can't be taken yet, exams
+/// passed without their prerequisites and the eligible-next list.
+#[tauri::command]
+fn get_prerequisites(state: State<AppState>) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    Ok(prereq::report(&career))
+}
+
+/// Upcoming appelli of all pending exams with their registration state.
+#[tauri::command]
+fn list_appelli(state: State<AppState>) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    let career = career::Career::from_value(&data)?;
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    Ok(Value::Array(career.upcoming_appelli(&today)))
+}
+
+/// Chooses the appello an exam will be sat at (`appello_id` null clears
+/// the choice); countdown, registration reminders and widget follow it.
+#[tauri::command]
+fn select_appello(app: AppHandle, state: State<AppState>, exam_id: String, appello_id: Option<String>) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    career::select_appello(&mut data, &exam_id, appello_id.as_deref())?;
+    write_json(&dir, "career", &data);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    Ok(data)
+}
+
+/// Records whether the student has registered for an appello; registered
+/// appelli get no more deadline reminders.
+#[tauri::command]
+fn set_appello_registered(
+    app: AppHandle,
+    state: State<AppState>,
+    exam_id: String,
+    appello_id: String,
+    registered: bool,
+) -> Result<Value, String> {
+    let dir = get_data_dir(&state);
+    let mut data = read_json(&dir, "career", Value::Null);
+    career::migrate(&mut data);
+    career::set_appello_registered(&mut data, &exam_id, &appello_id, registered)?;
+    write_json(&dir, "career", &data);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    Ok(data)
+}
+
+// ===== Notification Commands =====
+
+/// Next delivery of the `kind` briefing ("morning" | "afternoon" | "evening")
+/// with the exact title and body the notification engine would send.
+#[tauri::command]
+fn preview_briefing(state: State<AppState>, kind: String) -> Result<Value, String> {
+    let kind = BriefingKind::parse(&kind).ok_or_else(|| format!("Riepilogo sconosciuto: {}", kind))?;
+    let dir = get_data_dir(&state);
+    let events = read_json(&dir, "events", Value::Array(vec![]));
+    let settings = read_json(&dir, "settings", Value::Null);
+    let career = read_json(&dir, "career", Value::Null);
+    let lang = Lang::from_settings(&settings);
+
+    let now = chrono::Local::now().naive_local();
+    let time = kind.time(&settings);
+    let fire_today = chrono::NaiveTime::parse_from_str(&time, "%H:%M")
+        .map(|t| now.date().and_time(t) > now)
+        .unwrap_or(false);
+    let date = if fire_today { now.date() } else { now.date() + chrono::Duration::days(1) };
+
+    let enabled = kind.enabled(&settings);
+    let built = briefing::build(kind, &events, &settings, &career, date, lang);
+    Ok(serde_json::json!({
+        "kind": kind.key(),
+        "enabled": enabled,
+        "date": date.format("%Y-%m-%d").to_string(),
+        "time": time,
+        "willSend": enabled && built.is_some(),
+        "title": built.as_ref().map(|b| b.title.clone()),
+        "body": built.as_ref().map(|b| b.body.clone()),
+    }))
+}
+
+/// Re-runs the notification planner (mobile) and returns the resulting plan.
+/// Called by the frontend whenever a scheduled notification is delivered.
+#[tauri::command]
+fn refill_notifications(app: AppHandle, state: State<AppState>) -> Value {
+    let dir = get_data_dir(&state);
+    sync_notifications(&app, &dir);
+    state.notification_plan.lock().unwrap().clone()
+}
+
+/// Last planned scheduling window, as reported by the mobile planner.
+#[tauri::command]
+fn get_notification_plan(state: State<AppState>) -> Value {
+    state.notification_plan.lock().unwrap().clone()
+}
+
+/// Diagnostics for "I never get reminders": OS permission, scheduler
+/// heartbeat, next planned notification and last delivery error.
+#[tauri::command]
+fn get_notification_status(app: AppHandle, state: State<AppState>) -> Value {
+    let permission = app.notification().permission_state()
+        .map(|p| p.to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let status = state.delivery_status.lock().unwrap();
+    let mut out = status.to_json();
+    out["permission"] = Value::from(permission);
+    out["schedulerAlive"] = Value::from(scheduler_alive(&status));
+    out
+}
+
+/// Sends a notification right away through the same channel as reminders.
+#[tauri::command]
+fn send_test_notification(app: AppHandle, state: State<AppState>) -> Result<bool, String> {
+    let lang = settings_lang(&get_data_dir(&state));
+    let result = app.notification().builder()
+        .title(i18n::t(lang, "title.test"))
+        .body(i18n::t(lang, "body.test"))
+        .show()
+        .map_err(|e| e.to_string());
+    state.delivery_status.lock().unwrap().record(SystemClock.now(), "sp-test", &result);
+    result.map(|_| true)
+}
+
+/// Stops the escalating exam-day reminder of `event_id`: the acknowledgement
+/// is stored in the event and the remaining repeats are unscheduled.
+#[tauri::command]
+fn acknowledge_reminder(app: AppHandle, state: State<AppState>, event_id: String) -> Result<bool, String> {
+    let dir = get_data_dir(&state);
+    let mut events = read_json(&dir, "events", Value::Array(vec![]));
+    let event = events.as_array_mut()
+        .and_then(|arr| arr.iter_mut().find(|e| e.get("id").and_then(|i| i.as_str()) == Some(event_id.as_str())))
+        .ok_or_else(|| format!("Evento non trovato: {}", event_id))?;
+    notifications::acknowledge(event, SystemClock.now())?;
+    write_json(&dir, "events", &events);
+    sync_notifications(&app, &dir);
+    let _ = app.emit("data-changed", ());
+    Ok(true)
 }
 
 // ===== HYBRID NOTIFICATION ARCHITECTURE (v2.1) =====
@@ -430,12 +1531,13 @@ fn get_widget_career(state: State<AppState>) -> Value {
 //   notifications even if the app is killed.
 //
 // DESKTOP (macOS/Windows/Linux): tauri-plugin-notification (via notify-rust)
-//   IGNORES Schedule::At and fires immediately.  Instead we run a single async
-//   Tokio cron job that wakes once per minute, checks the JSON state, and fires
-//   notifications in real-time.  Zero threads, zero sleeps, zero CPU waste.
+//   IGNORES Schedule::At and fires immediately.  Instead a single async Tokio
+//   task keeps an in-memory queue of upcoming notifications and sleeps until
+//   the next one is due.  The JSON state is only re-read when it changes
+//   (save_events / save_settings / save_career) or when the app resumes.
 //
-//   On macOS the App Nap hack (NSProcessInfo.beginActivityWithOptions) prevents
-//   the OS from freezing the async timer when the window is hidden.
+//   On macOS an NSProcessInfo activity is held for the last minutes before a
+//   delivery, so App Nap cannot postpone the timer when the window is hidden.
 
 /// Deterministic i32 notification ID from a seed string.
 /// Uses FNV-1a hash to produce a stable, positive, non-zero i32.
@@ -450,365 +1552,234 @@ fn notif_id(seed: &str) -> i32 {
 }
 
 // ── MOBILE: Native AOT scheduling ─────────────────────────────────────────
+//
+// The OS only keeps a limited number of pending notifications, so the planner
+// first collects every candidate, then schedules the most important ones:
+//   0 — exam reminders (category `esame`) and the exam countdown series
+//   1 — other per-event reminders
+//   2 — today's briefings
+//   3 — morning briefings for the following days
+// One slot is reserved for a "refill" prompt before the planned window runs
+// out.  The planner re-runs on save, on startup, when the app resumes and when
+// the frontend reports a delivered notification (`refill_notifications`).
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn to_offset_datetime(dt: &chrono::DateTime<chrono::Local>) -> Option<time::OffsetDateTime> {
+    let offset = time::UtcOffset::from_whole_seconds(dt.offset().local_minus_utc()).ok()?;
+    time::OffsetDateTime::from_unix_timestamp(dt.timestamp()).ok().map(|t| t.to_offset(offset))
+}
+
+/// Local instant for a wall-clock time.  Times repeated by a DST fall-back
+/// resolve to their first occurrence; times skipped by a spring-forward fire
+/// one hour later.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn local_instant(at: chrono::NaiveDateTime) -> Option<chrono::DateTime<chrono::Local>> {
+    chrono::Local.from_local_datetime(&at).earliest()
+        .or_else(|| chrono::Local.from_local_datetime(&(at + chrono::Duration::hours(1))).earliest())
+}
+
 #[cfg(any(target_os = "android", target_os = "ios"))]
 fn sync_notifications(app: &AppHandle, data_dir: &std::path::Path) {
     let _ = app.notification().cancel_all();
     eprintln!("[StudyPlan] Cancelled all pending notifications (mobile)");
 
-    let dir = data_dir.to_path_buf();
-    let events = read_json(&dir, "events", Value::Array(vec![]));
-    let settings = read_json(&dir, "settings", serde_json::json!({
-        "morningNotif": true, "afternoonNotif": true, "eveningNotif": true,
-        "morningTime": "07:30", "afternoonTime": "14:00", "eveningTime": "21:00"
-    }));
-
-    let now = chrono::Local::now();
-    let today = now.format("%Y-%m-%d").to_string();
-    let tomorrow = (now + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
-    const MAX_SCHEDULED: u32 = 60;
-    let horizon = now + chrono::Duration::days(14);
-    let mut scheduled_count = 0u32;
-
-    let to_schedule_time = |date_str: &str, time_str: &str| -> Option<time::OffsetDateTime> {
-        if time_str.len() < 5 { return None; }
-        let dt_str = format!("{} {}", date_str, time_str);
-        let ndt = chrono::NaiveDateTime::parse_from_str(&dt_str, "%Y-%m-%d %H:%M").ok()?;
-        let local_dt = chrono::Local.from_local_datetime(&ndt).single()?;
-        if local_dt <= now || local_dt > horizon { return None; }
-        let ts = local_dt.timestamp();
-        let offset_secs = local_dt.offset().local_minus_utc();
-        let offset = time::UtcOffset::from_whole_seconds(offset_secs).ok()?;
-        time::OffsetDateTime::from_unix_timestamp(ts).ok().map(|t| t.to_offset(offset))
-    };
-
-    // Per-event reminders
-    if let Value::Array(ref arr) = events {
-        for event in arr {
-            if scheduled_count >= MAX_SCHEDULED { break; }
-            let reminders_obj = match event.get("reminders") {
-                Some(r) if r.is_object() => r, _ => continue,
-            };
-            let event_date = event.get("date").and_then(|d| d.as_str()).unwrap_or("");
-            let title_text = event.get("title").and_then(|t| t.as_str()).unwrap_or("Evento");
-            let time_start = event.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
-
-            if let Some(db) = reminders_obj.get("dayBefore") {
-                let enabled = db.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
-                let rem_time = db.get("time").and_then(|v| v.as_str()).unwrap_or("");
-                if enabled {
-                    if let Ok(edate) = chrono::NaiveDate::parse_from_str(event_date, "%Y-%m-%d") {
-                        let day_before = (edate - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
-                        if let Some(fire_at) = to_schedule_time(&day_before, rem_time) {
-                            let id = notif_id(&format!("sp-db-{}-{}", event_date, rem_time));
-                            let _ = app.notification().builder().id(id)
-                                .title("StudyPlan — Promemoria domani")
-                                .body(&format!("Domani: {} alle {}", title_text, time_start))
-                                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                                .show();
-                            scheduled_count += 1;
-                        }
-                    }
-                }
-            }
-            if let Some(sd) = reminders_obj.get("sameDay") {
-                let enabled = sd.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
-                let rem_time = sd.get("time").and_then(|v| v.as_str()).unwrap_or("");
-                if enabled {
-                    if let Some(fire_at) = to_schedule_time(event_date, rem_time) {
-                        let id = notif_id(&format!("sp-sd-{}-{}", event_date, rem_time));
-                        let _ = app.notification().builder().id(id)
-                            .title("StudyPlan — Promemoria oggi")
-                            .body(&format!("Oggi: {} alle {}", title_text, time_start))
-                            .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                            .show();
-                        scheduled_count += 1;
-                    }
-                }
-            }
-            if let Some(custom_mins) = reminders_obj.get("customRemindTime").and_then(|v| v.as_i64()) {
-                if custom_mins > 0 && !time_start.is_empty() {
-                    let dt_str = format!("{} {}", event_date, time_start);
-                    if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(&dt_str, "%Y-%m-%d %H:%M") {
-                        let remind_ndt = ndt - chrono::Duration::minutes(custom_mins);
-                        let remind_date = remind_ndt.format("%Y-%m-%d").to_string();
-                        let remind_time = remind_ndt.format("%H:%M").to_string();
-                        if let Some(fire_at) = to_schedule_time(&remind_date, &remind_time) {
-                            let id = notif_id(&format!("sp-cr-{}-{}-{}", event_date, time_start, custom_mins));
-                            let _ = app.notification().builder().id(id)
-                                .title("StudyPlan — Tra poco")
-                                .body(&format!("{} tra {} minuti", title_text, custom_mins))
-                                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                                .show();
-                            scheduled_count += 1;
-                        }
-                    }
-                }
+    let data = NotificationData::load(data_dir);
+    let now = SystemClock.now();
+    let plan = notifications::plan_ahead(&data, now);
+
+    let state = app.state::<AppState>();
+    let mut status = state.delivery_status.lock().unwrap();
+    for p in &plan.scheduled {
+        if let Some(fire_at) = local_instant(p.at).as_ref().and_then(to_offset_datetime) {
+            let result = app.notification().builder().id(notif_id(&p.notification.key))
+                .title(&p.notification.title)
+                .body(&p.notification.body)
+                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
+                .show()
+                .map_err(|e| e.to_string());
+            if result.is_err() {
+                status.record(now, &p.notification.key, &result);
             }
         }
     }
+    status.scheduler_ran_at = Some(now);
+    status.next = plan.scheduled.first().cloned();
+    drop(status);
+
+    let fmt = |t: &chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M").to_string();
+    let summary = serde_json::json!({
+        "plannedAt": fmt(&now),
+        "scheduled": plan.scheduled.len(),
+        "dropped": plan.dropped,
+        "limit": notifications::MAX_SCHEDULED,
+        "horizonEnd": fmt(&plan.horizon_end),
+        "coveredUntil": fmt(&plan.covered_until),
+        "lastPlanned": plan.scheduled.iter().map(|p| p.at).max().map(|t| fmt(&t)),
+    });
+    *state.notification_plan.lock().unwrap() = summary.clone();
+    let _ = app.emit("notification-plan-updated", summary);
 
-    // Global briefings
-    let morning_enabled = settings.get("morningNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-    let afternoon_enabled = settings.get("afternoonNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-    let evening_enabled = settings.get("eveningNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-    let morning_time = settings.get("morningTime").and_then(|v| v.as_str()).unwrap_or("07:30");
-    let afternoon_time = settings.get("afternoonTime").and_then(|v| v.as_str()).unwrap_or("14:00");
-    let evening_time = settings.get("eveningTime").and_then(|v| v.as_str()).unwrap_or("21:00");
-
-    let today_count = if let Value::Array(ref arr) = events {
-        arr.iter().filter(|e| e.get("date").and_then(|d| d.as_str()) == Some(today.as_str())).count()
-    } else { 0 };
-    let today_pending = if let Value::Array(ref arr) = events {
-        arr.iter().filter(|e| {
-            e.get("date").and_then(|d| d.as_str()) == Some(today.as_str())
-            && !e.get("completed").and_then(|c| c.as_bool()).unwrap_or(false)
-        }).count()
-    } else { 0 };
-    let today_completed = today_count.saturating_sub(today_pending);
-    let tomorrow_count = if let Value::Array(ref arr) = events {
-        arr.iter().filter(|e| e.get("date").and_then(|d| d.as_str()) == Some(tomorrow.as_str())).count()
-    } else { 0 };
-
-    if scheduled_count < MAX_SCHEDULED && morning_enabled && today_pending > 0 {
-        if let Some(fire_at) = to_schedule_time(&today, morning_time) {
-            let id = notif_id(&format!("sp-morning-{}", today));
-            let _ = app.notification().builder().id(id)
-                .title("StudyPlan — Riepilogo mattutino")
-                .body(&format!("{} impegni in programma per oggi.", today_pending))
-                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                .show();
-            scheduled_count += 1;
-        }
-    }
-    if scheduled_count < MAX_SCHEDULED && afternoon_enabled && today_pending > 0 {
-        if let Some(fire_at) = to_schedule_time(&today, afternoon_time) {
-            let id = notif_id(&format!("sp-afternoon-{}", today));
-            let _ = app.notification().builder().id(id)
-                .title("StudyPlan — Riepilogo pomeridiano")
-                .body(&format!("{} impegni ancora da completare oggi.", today_pending))
-                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                .show();
-            scheduled_count += 1;
+    eprintln!("[StudyPlan] Mobile AOT sync: {}/{} notifications scheduled, {} dropped",
+        plan.scheduled.len(), notifications::MAX_SCHEDULED, plan.dropped);
+}
+
+// ── DESKTOP: the async scheduler owns the queue; just ask it to rebuild ──────
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn sync_notifications(app: &AppHandle, _data_dir: &std::path::Path) {
+    app.state::<AppState>().scheduler_wake.notify_one();
+}
+
+// ── DESKTOP: Async scheduler — sleeps until the next notification is due ────
+
+/// Upper bound on a single sleep: the monotonic timer does not follow wall
+/// clock changes (manual adjustments, time spent suspended).
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// How long before a delivery the App Nap activity is taken.
+#[cfg(target_os = "macos")]
+const APP_NAP_LEAD: std::time::Duration = std::time::Duration::from_secs(2 * 60);
+
+/// Delivers through the OS notification center on the main thread.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+struct TauriSink {
+    app: AppHandle,
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+impl NotificationSink for TauriSink {
+    fn deliver(&self, notification: &Notification) -> Result<(), String> {
+        let app_c = self.app.clone();
+        let (key, title, body) = (notification.key.clone(), notification.title.clone(), notification.body.clone());
+        let dispatched = self.app.run_on_main_thread(move || {
+            let result = app_c.notification().builder()
+                .title(&title)
+                .body(&body).show()
+                .map_err(|e| e.to_string());
+            app_c.state::<AppState>().delivery_status.lock().unwrap().record(SystemClock.now(), &key, &result);
+        }).map_err(|e| e.to_string());
+        if dispatched.is_err() {
+            self.app.state::<AppState>().delivery_status.lock().unwrap()
+                .record(SystemClock.now(), &notification.key, &dispatched);
         }
+        dispatched?;
+        eprintln!("[StudyPlan Cron] ✓ fired: {}", notification.key);
+        Ok(())
     }
-    if scheduled_count < MAX_SCHEDULED && evening_enabled && (today_count > 0 || tomorrow_count > 0) {
-        if let Some(fire_at) = to_schedule_time(&today, evening_time) {
-            let id = notif_id(&format!("sp-evening-{}", today));
-            let body = if today_count > 0 && tomorrow_count > 0 {
-                format!("Completati {}/{}. Domani: {} impegni.", today_completed, today_count, tomorrow_count)
-            } else if today_count > 0 {
-                format!("Completati {}/{} impegni di oggi.", today_completed, today_count)
-            } else {
-                format!("{} impegni in programma per domani.", tomorrow_count)
-            };
-            let _ = app.notification().builder().id(id)
-                .title("StudyPlan — Riepilogo serale")
-                .body(&body)
-                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                .show();
-            scheduled_count += 1;
+}
+
+/// NSProcessInfo activity that keeps App Nap from throttling our timers
+/// while it is alive.
+#[cfg(target_os = "macos")]
+struct AppNapActivity(*mut objc::runtime::Object);
+
+// The activity token is only handed back to NSProcessInfo, which is thread-safe.
+#[cfg(target_os = "macos")]
+unsafe impl Send for AppNapActivity {}
+
+#[cfg(target_os = "macos")]
+impl AppNapActivity {
+    /// NSActivityUserInitiatedAllowingIdleSystemSleep
+    const OPTIONS: u64 = 0x00FF_FFFF;
+
+    fn begin() -> Self {
+        use objc::{msg_send, sel, sel_impl, class};
+        use cocoa::foundation::NSString as NSStringTrait;
+        unsafe {
+            let info: *mut objc::runtime::Object = msg_send![class!(NSProcessInfo), processInfo];
+            let reason = cocoa::foundation::NSString::alloc(cocoa::base::nil)
+                .init_str("StudyPlan is about to deliver a scheduled notification");
+            let activity: *mut objc::runtime::Object = msg_send![info,
+                beginActivityWithOptions: Self::OPTIONS
+                reason: reason
+            ];
+            let activity: *mut objc::runtime::Object = msg_send![activity, retain];
+            let _: () = msg_send![reason, release];
+            AppNapActivity(activity)
         }
     }
-    if scheduled_count < MAX_SCHEDULED && morning_enabled && tomorrow_count > 0 {
-        if let Some(fire_at) = to_schedule_time(&tomorrow, morning_time) {
-            let id = notif_id(&format!("sp-morning-{}", tomorrow));
-            let _ = app.notification().builder().id(id)
-                .title("StudyPlan — Riepilogo mattutino")
-                .body(&format!("{} impegni in programma per oggi.", tomorrow_count))
-                .schedule(Schedule::At { date: fire_at, repeating: false, allow_while_idle: true })
-                .show();
-            scheduled_count += 1;
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for AppNapActivity {
+    fn drop(&mut self) {
+        use objc::{msg_send, sel, sel_impl, class};
+        unsafe {
+            let info: *mut objc::runtime::Object = msg_send![class!(NSProcessInfo), processInfo];
+            let _: () = msg_send![info, endActivity: self.0];
+            let _: () = msg_send![self.0, release];
         }
     }
+}
 
-    eprintln!("[StudyPlan] Mobile AOT sync: {}/{} notifications scheduled", scheduled_count, MAX_SCHEDULED);
+/// Time left until the local wall-clock time `at`.  A time skipped by a DST
+/// spring-forward is reached as soon as the clock has jumped past it.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn duration_until(at: chrono::NaiveDateTime) -> std::time::Duration {
+    use chrono::TimeZone as _;
+    let now = chrono::Local::now();
+    let left = match chrono::Local.from_local_datetime(&at).earliest() {
+        Some(instant) => instant - now,
+        None => at - now.naive_local(),
+    };
+    // One extra second so the wake-up lands inside the due minute
+    left.to_std().unwrap_or_default() + std::time::Duration::from_secs(1)
 }
 
-// ── DESKTOP: stub — scheduling is handled by the async cron job ────────────
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
-fn sync_notifications(_app: &AppHandle, _data_dir: &std::path::Path) {
-    // No-op on desktop.  The desktop_cron_job() handles everything.
+fn load_notification_data(app: &AppHandle) -> NotificationData {
+    let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+    NotificationData::load(&dir)
 }
 
-// ── DESKTOP: Async Cron Job — wakes every 60s, fires matching notifications ──
+/// Heartbeat and next queued notification for `get_notification_status`.
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
-async fn desktop_cron_job(app: AppHandle) {
-    use tauri_plugin_notification::NotificationExt;
+fn report_scheduler(app: &AppHandle, scheduler: &Scheduler) {
+    let state = app.state::<AppState>();
+    let mut status = state.delivery_status.lock().unwrap();
+    status.scheduler_ran_at = Some(SystemClock.now());
+    status.next = scheduler.peek().cloned();
+}
 
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
-    let mut last_processed_minute = String::new();
+/// The desktop scheduler wakes at least every `MAX_SLEEP`; a heartbeat older
+/// than that (plus some slack) means the task died or is being starved.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn scheduler_alive(status: &DeliveryStatus) -> bool {
+    let slack = chrono::Duration::from_std(MAX_SLEEP).unwrap_or_default() + chrono::Duration::minutes(2);
+    status.scheduler_ran_at.is_some_and(|ran| (SystemClock.now() - ran).abs() <= slack)
+}
 
-    eprintln!("[StudyPlan Cron] Desktop cron job started — checking every 60s");
+/// On mobile the OS delivers; the planner only has to have run.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn scheduler_alive(status: &DeliveryStatus) -> bool {
+    status.scheduler_ran_at.is_some()
+}
 
-    loop {
-        interval.tick().await;
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+async fn desktop_scheduler(app: AppHandle) {
+    let sink = TauriSink { app: app.clone() };
+    let mut scheduler = Scheduler::new(load_notification_data(&app));
+    scheduler.run_due(&SystemClock, &sink);
+    report_scheduler(&app, &scheduler);
 
-        let now = chrono::Local::now();
-        let current_minute = now.format("%Y-%m-%d %H:%M").to_string();
-        if current_minute == last_processed_minute { continue; }
-        last_processed_minute = current_minute.clone();
+    eprintln!("[StudyPlan Cron] Desktop scheduler started");
 
-        let data_dir = {
-            let state = app.state::<AppState>();
-            let dir = state.data_dir.lock().unwrap().clone();
-            dir
+    loop {
+        let wait = scheduler.next_wake().map(duration_until).unwrap_or(MAX_SLEEP).min(MAX_SLEEP);
+        #[cfg(target_os = "macos")]
+        let (wait, _activity) = if wait > APP_NAP_LEAD {
+            (wait - APP_NAP_LEAD, None)
+        } else {
+            (wait, Some(AppNapActivity::begin()))
         };
 
-        let events = read_json(&data_dir, "events", Value::Array(vec![]));
-        let settings = read_json(&data_dir, "settings", serde_json::json!({
-            "morningNotif": true, "afternoonNotif": true, "eveningNotif": true,
-            "morningTime": "07:30", "afternoonTime": "14:00", "eveningTime": "21:00"
-        }));
-
-        let today = now.format("%Y-%m-%d").to_string();
-        let tomorrow = (now + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
-
-        // ── Check per-event reminders ──
-        if let Value::Array(ref arr) = events {
-            for event in arr {
-                let reminders_obj = match event.get("reminders") {
-                    Some(r) if r.is_object() => r, _ => continue,
-                };
-                let event_date = event.get("date").and_then(|d| d.as_str()).unwrap_or("");
-                let title_text = event.get("title").and_then(|t| t.as_str()).unwrap_or("Evento");
-                let time_start = event.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
-
-                // dayBefore reminder
-                if let Some(db) = reminders_obj.get("dayBefore") {
-                    let enabled = db.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
-                    let rem_time = db.get("time").and_then(|v| v.as_str()).unwrap_or("");
-                    if enabled {
-                        if let Ok(edate) = chrono::NaiveDate::parse_from_str(event_date, "%Y-%m-%d") {
-                            let day_before = (edate - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
-                            let fire_key = format!("{} {}", day_before, rem_time);
-                            if fire_key == current_minute {
-                                let app_c = app.clone();
-                                let body = format!("Domani: {} alle {}", title_text, time_start);
-                                let _ = app.run_on_main_thread(move || {
-                                    let _ = app_c.notification().builder()
-                                        .title("StudyPlan — Promemoria domani")
-                                        .body(&body).show();
-                                });
-                                eprintln!("[StudyPlan Cron] ✓ dayBefore fired: {}", fire_key);
-                            }
-                        }
-                    }
-                }
-
-                // sameDay reminder
-                if let Some(sd) = reminders_obj.get("sameDay") {
-                    let enabled = sd.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
-                    let rem_time = sd.get("time").and_then(|v| v.as_str()).unwrap_or("");
-                    if enabled {
-                        let fire_key = format!("{} {}", event_date, rem_time);
-                        if fire_key == current_minute {
-                            let app_c = app.clone();
-                            let body = format!("Oggi: {} alle {}", title_text, time_start);
-                            let _ = app.run_on_main_thread(move || {
-                                let _ = app_c.notification().builder()
-                                    .title("StudyPlan — Promemoria oggi")
-                                    .body(&body).show();
-                            });
-                            eprintln!("[StudyPlan Cron] ✓ sameDay fired: {}", fire_key);
-                        }
-                    }
-                }
-
-                // customRemindTime reminder
-                if let Some(custom_mins) = reminders_obj.get("customRemindTime").and_then(|v| v.as_i64()) {
-                    if custom_mins > 0 && !time_start.is_empty() {
-                        let dt_str = format!("{} {}", event_date, time_start);
-                        if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(&dt_str, "%Y-%m-%d %H:%M") {
-                            let remind_ndt = ndt - chrono::Duration::minutes(custom_mins);
-                            let fire_key = remind_ndt.format("%Y-%m-%d %H:%M").to_string();
-                            if fire_key == current_minute {
-                                let app_c = app.clone();
-                                let body = format!("{} tra {} minuti", title_text, custom_mins);
-                                let _ = app.run_on_main_thread(move || {
-                                    let _ = app_c.notification().builder()
-                                        .title("StudyPlan — Tra poco")
-                                        .body(&body).show();
-                                });
-                                eprintln!("[StudyPlan Cron] ✓ customRemind fired: {}", fire_key);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // ── Check global briefings ──
-        let morning_enabled = settings.get("morningNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-        let afternoon_enabled = settings.get("afternoonNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-        let evening_enabled = settings.get("eveningNotif").and_then(|v| v.as_bool()).unwrap_or(true);
-        let morning_time = settings.get("morningTime").and_then(|v| v.as_str()).unwrap_or("07:30");
-        let afternoon_time = settings.get("afternoonTime").and_then(|v| v.as_str()).unwrap_or("14:00");
-        let evening_time = settings.get("eveningTime").and_then(|v| v.as_str()).unwrap_or("21:00");
-
-        let today_count = if let Value::Array(ref arr) = events {
-            arr.iter().filter(|e| e.get("date").and_then(|d| d.as_str()) == Some(today.as_str())).count()
-        } else { 0 };
-        let today_pending = if let Value::Array(ref arr) = events {
-            arr.iter().filter(|e| {
-                e.get("date").and_then(|d| d.as_str()) == Some(today.as_str())
-                && !e.get("completed").and_then(|c| c.as_bool()).unwrap_or(false)
-            }).count()
-        } else { 0 };
-        let today_completed = today_count.saturating_sub(today_pending);
-        let tomorrow_count = if let Value::Array(ref arr) = events {
-            arr.iter().filter(|e| e.get("date").and_then(|d| d.as_str()) == Some(tomorrow.as_str())).count()
-        } else { 0 };
-
-        // Morning briefing
-        if morning_enabled && today_pending > 0 {
-            let key = format!("{} {}", today, morning_time);
-            if key == current_minute {
-                let app_c = app.clone();
-                let body = format!("{} impegni in programma per oggi.", today_pending);
-                let _ = app.run_on_main_thread(move || {
-                    let _ = app_c.notification().builder()
-                        .title("StudyPlan — Riepilogo mattutino")
-                        .body(&body).show();
-                });
-                eprintln!("[StudyPlan Cron] ✓ Morning briefing fired");
-            }
-        }
-
-        // Afternoon briefing
-        if afternoon_enabled && today_pending > 0 {
-            let key = format!("{} {}", today, afternoon_time);
-            if key == current_minute {
-                let app_c = app.clone();
-                let body = format!("{} impegni ancora da completare oggi.", today_pending);
-                let _ = app.run_on_main_thread(move || {
-                    let _ = app_c.notification().builder()
-                        .title("StudyPlan — Riepilogo pomeridiano")
-                        .body(&body).show();
-                });
-                eprintln!("[StudyPlan Cron] ✓ Afternoon briefing fired");
-            }
-        }
-
-        // Evening briefing
-        if evening_enabled && (today_count > 0 || tomorrow_count > 0) {
-            let key = format!("{} {}", today, evening_time);
-            if key == current_minute {
-                let app_c = app.clone();
-                let body = if today_count > 0 && tomorrow_count > 0 {
-                    format!("Completati {}/{}. Domani: {} impegni.", today_completed, today_count, tomorrow_count)
-                } else if today_count > 0 {
-                    format!("Completati {}/{} impegni di oggi.", today_completed, today_count)
-                } else {
-                    format!("{} impegni in programma per domani.", tomorrow_count)
-                };
-                let _ = app.run_on_main_thread(move || {
-                    let _ = app_c.notification().builder()
-                        .title("StudyPlan — Riepilogo serale")
-                        .body(&body).show();
-                });
-                eprintln!("[StudyPlan Cron] ✓ Evening briefing fired");
-            }
+        let state = app.state::<AppState>();
+        if tokio::time::timeout(wait, state.scheduler_wake.notified()).await.is_ok() {
+            scheduler.reload(load_notification_data(&app));
+            eprintln!("[StudyPlan Cron] Queue rebuilt");
         }
+        scheduler.run_due(&SystemClock, &sink);
+        report_scheduler(&app, &scheduler);
     }
 }
 
@@ -872,8 +1843,39 @@ fn toggle_widget(app: AppHandle) {
     }
 }
 
+// ===== Tray (Desktop only) =====
+
+#[cfg(desktop)]
+const TRAY_ID: &str = "main";
+
+#[cfg(desktop)]
+fn build_tray_menu(app: &AppHandle, lang: Lang) -> tauri::Result<tauri::menu::Menu<tauri::Wry>> {
+    use tauri::menu::{MenuBuilder, MenuItemBuilder};
+
+    let show_app = MenuItemBuilder::with_id("show", i18n::t(lang, "tray.open")).build(app)?;
+    let show_widget = MenuItemBuilder::with_id("widget", i18n::t(lang, "tray.widget")).build(app)?;
+    let quit = MenuItemBuilder::with_id("quit", i18n::t(lang, "tray.quit")).build(app)?;
+    MenuBuilder::new(app).items(&[&show_app, &show_widget, &quit]).build()
+}
+
+/// Rebuild the tray menu so it follows a `language` change in settings.
+#[cfg(desktop)]
+fn refresh_tray_menu(app: &AppHandle, lang: Lang) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Ok(menu) = build_tray_menu(app, lang) {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+}
+
 // ===== Run =====
 
+/// Runs a PDF parsing job when the process was started as a worker (see
+/// pdf.rs); `main` exits with the returned code instead of starting the app.
+pub fn pdf_worker() -> Option<i32> {
+    pdf::worker_main(std::env::args())
+}
+
 pub fn run() {
     let data_dir = dirs::data_dir()
         .unwrap_or_else(|| PathBuf::from("."))
@@ -893,6 +1895,12 @@ pub fn run() {
         .plugin(tauri_plugin_os::init())
         .manage(AppState {
             data_dir: Mutex::new(data_dir),
+            notification_plan: Mutex::new(Value::Null),
+            delivery_status: Mutex::new(DeliveryStatus::default()),
+            pdf_index: Mutex::new(pdf_index::IndexState::default()),
+            renamed_attachments: Mutex::new(std::collections::BTreeMap::new()),
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            scheduler_wake: tokio::sync::Notify::new(),
         })
         .setup(move |app| {
             if cfg!(debug_assertions) {
@@ -935,16 +1943,19 @@ pub fn run() {
                 }
 
                 if !marker.exists() {
+                    let lang = settings_lang(&data_dir);
                     let _ = app.notification()
                         .builder()
                         .title("StudyPlan")
-                        .body("Le notifiche sono attive! Riceverai promemoria per i tuoi impegni.")
+                        .body(i18n::t(lang, "body.first_launch"))
                         .show();
                     let _ = fs::write(&marker, "1");
                 }
             }
             
             // Privacy blur: emit events on window focus/blur
+            // Focus also wakes the scheduler: desktop has no Resumed event,
+            // and coming back after a suspend usually means focusing the app
             // Intercept close button → hide to tray instead of terminating
             #[cfg(desktop)]
             if let Some(window) = app.get_webview_window("main") {
@@ -957,6 +1968,8 @@ pub fn run() {
                         }
                         tauri::WindowEvent::Focused(true) => {
                             let _ = w.emit("app-blur", false);
+                            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+                            w.state::<AppState>().scheduler_wake.notify_one();
                         }
                         tauri::WindowEvent::CloseRequested { api, .. } => {
                             // Hide instead of close — keeps tray alive
@@ -971,20 +1984,16 @@ pub fn run() {
             // Tray menu (Desktop only)
             #[cfg(desktop)]
             {
-                use tauri::menu::{MenuBuilder, MenuItemBuilder};
                 use tauri::tray::TrayIconBuilder;
 
-                let show_app = MenuItemBuilder::with_id("show", "Apri StudyPlan").build(app)?;
-                let show_widget = MenuItemBuilder::with_id("widget", "Widget").build(app)?;
-                let quit = MenuItemBuilder::with_id("quit", "Esci").build(app)?;
-                let menu = MenuBuilder::new(app).items(&[&show_app, &show_widget, &quit]).build()?;
+                let menu = build_tray_menu(app.handle(), settings_lang(&setup_data_dir))?;
 
                 let png_data = include_bytes!("../icons/tray-icon.png");
                 let img = image::load_from_memory(png_data).expect("decode tray icon").to_rgba8();
                 let (w, h) = img.dimensions();
                 let icon = tauri::image::Image::new_owned(img.into_raw(), w, h);
 
-                TrayIconBuilder::new()
+                TrayIconBuilder::with_id(TRAY_ID)
                     .icon(icon)
                     .icon_as_template(false)
                     .menu(&menu)
@@ -1014,32 +2023,21 @@ pub fn run() {
                     .build(app)?;
             }
             
-            // Start AOT notification sync (mobile: real scheduling, desktop: no-op stub)
-            sync_notifications(&app.handle(), &setup_data_dir);
+            ensure_career_ids(&setup_data_dir);
 
-            // ── DESKTOP: App Nap prevention + async cron job ──────────────────
-            #[cfg(target_os = "macos")]
-            {
-                use objc::{msg_send, sel, sel_impl, class};
-                use cocoa::foundation::NSString as NSStringTrait;
-                unsafe {
-                    let info: *mut objc::runtime::Object = msg_send![class!(NSProcessInfo), processInfo];
-                    let reason = cocoa::foundation::NSString::alloc(cocoa::base::nil)
-                        .init_str("StudyPlan notification cron job must not be suspended");
-                    let _activity: *mut objc::runtime::Object = msg_send![info,
-                        beginActivityWithOptions: 0x00FFFFFFu64
-                        reason: reason
-                    ];
-                    eprintln!("[StudyPlan] macOS App Nap disabled via NSProcessInfo ✓");
-                }
-            }
+            // Content-addressed attachments (rename legacy files, drop
+            // orphans), then bring the PDF full-text index up to date
+            spawn_attachment_maintenance(app.handle(), setup_data_dir.clone());
+
+            // Start AOT notification sync (mobile: real scheduling, desktop: queue rebuild)
+            sync_notifications(app.handle(), &setup_data_dir);
 
-            // Launch the desktop cron job (single async task, zero threads)
+            // Launch the desktop scheduler (single async task, zero threads)
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             {
                 let app_handle = app.handle().clone();
                 tauri::async_runtime::spawn(async move {
-                    desktop_cron_job(app_handle).await;
+                    desktop_scheduler(app_handle).await;
                 });
             }
 
@@ -1079,11 +2077,39 @@ pub fn run() {
             open_pdf,
             delete_pdf,
             get_pdf_pages,
+            get_pdf_outline,
+            search_pdfs,
+            get_pdf_thumbnail,
+            verify_attachments,
+            collect_attachments,
             // Widget data
             get_widget_today,
             get_widget_exams,
             get_widget_week,
             get_widget_career,
+            // Career
+            list_careers,
+            create_career,
+            switch_career,
+            delete_career,
+            get_career_stats,
+            simulate_career,
+            get_prerequisites,
+            get_requirements,
+            import_degree_template,
+            export_degree_template,
+            import_transcript,
+            export_career_report,
+            list_appelli,
+            select_appello,
+            set_appello_registered,
+            // Notifications
+            preview_briefing,
+            refill_notifications,
+            get_notification_plan,
+            acknowledge_reminder,
+            get_notification_status,
+            send_test_notification,
             // Window
             window_minimize,
             window_maximize,
@@ -1094,9 +2120,16 @@ pub fn run() {
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app, event| {
+            // App back in the foreground → top up the scheduled notifications
+            // (mobile) or rebuild the desktop queue (see also the focus handler)
+            if let tauri::RunEvent::Resumed = event {
+                let dir = app.state::<AppState>().data_dir.lock().unwrap().clone();
+                sync_notifications(app, &dir);
+            }
             // macOS: click sull'icona nel Dock quando la finestra è nascosta → riaprila
             #[cfg(target_os = "macos")]
             if let tauri::RunEvent::Reopen { .. } = event {
+                app.state::<AppState>().scheduler_wake.notify_one();
                 if let Some(w) = app.get_webview_window("main") {
                     let _ = w.show();
                     let _ = w.set_focus();
@@ -1108,3 +2141,76 @@ pub fn run() {
             }
         });
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn career(id: Option<&str>, course: &str) -> Value {
+        let mut career = json!({ "course": course, "type": "triennale", "exams": [
+            { "id": "an", "name": "Analisi", "cfu": 9, "status": "passed", "grade": 27, "passDate": "2024-02-01" },
+        ]});
+        if let Some(id) = id {
+            career["id"] = json!(id);
+        }
+        career
+    }
+
+    #[test]
+    fn list_careers_does_not_write() {
+        let dir = tempfile::tempdir().unwrap();
+        write_json(dir.path(), "career", &career(None, "Informatica"));
+        write_json(dir.path(), "careers", &json!([career(None, "Fisica")]));
+        let files = || ["career.json", "careers.json"].map(|f| fs::read(dir.path().join(f)).unwrap());
+        let before = files();
+
+        let list = career_list(dir.path());
+        assert_eq!(list.as_array().unwrap().len(), 2);
+        assert_eq!(list[0]["active"], true);
+        assert_eq!(list[0]["cfuEarned"], 9);
+        assert_eq!(list[1]["label"], "Fisica");
+        assert_eq!(files(), before);
+
+        // Ids are given once, at startup
+        ensure_career_ids(dir.path());
+        assert!(!careers::id(&read_json(dir.path(), "career", Value::Null)).is_empty());
+        assert!(read_archive(dir.path()).iter().all(|c| !careers::id(c).is_empty()));
+        let after = files();
+        ensure_career_ids(dir.path());
+        assert_eq!(files(), after);
+    }
+
+    #[test]
+    fn switching_archives_the_active_career() {
+        let dir = tempfile::tempdir().unwrap();
+        write_json(dir.path(), "career", &career(Some("tri"), "Informatica"));
+        archive_career(dir.path(), career(Some("mag"), "Data Science"));
+        assert!(read_archive(dir.path())[0]["archivedAt"].is_string());
+
+        let chosen = activate_career(dir.path(), "mag").unwrap();
+        assert!(chosen.get("archivedAt").is_none());
+        // Migrated on the way in
+        assert_eq!(chosen["exams"][0]["attempts"][0]["outcome"], "passed");
+        assert_eq!(read_json(dir.path(), "career", Value::Null), chosen);
+        let archive = read_archive(dir.path());
+        assert_eq!(archive.iter().map(careers::id).collect::<Vec<_>>(), ["tri"]);
+        assert!(archive[0]["archivedAt"].is_string());
+
+        // And back: one copy of each career
+        activate_career(dir.path(), "tri").unwrap();
+        assert_eq!(careers::id(&read_json(dir.path(), "career", Value::Null)), "tri");
+        assert_eq!(read_archive(dir.path()).iter().map(careers::id).collect::<Vec<_>>(), ["mag"]);
+        assert_eq!(activate_career(dir.path(), "tri"), Err("Carriera non trovata".to_string()));
+    }
+
+    #[test]
+    fn empty_active_career_is_not_archived() {
+        let dir = tempfile::tempdir().unwrap();
+        write_json(dir.path(), "career", &json!({}));
+        archive_career(dir.path(), career(Some("tri"), "Informatica"));
+        activate_career(dir.path(), "tri").unwrap();
+        assert!(read_archive(dir.path()).is_empty());
+    }
+}
@@ -0,0 +1,998 @@
+// ===== Notification Engine =====
+//
+// Pure scheduling logic shared by the desktop cron job and the mobile AOT
+// planner.  Nothing in here talks to Tauri: the current time comes from a
+// `Clock` and delivered notifications go to a `NotificationSink`, so the
+// whole engine can be driven step by step in tests.
+//
+//   plan()        — every notification whose fire time falls in (from, to]
+//   plan_ahead()  — mobile: prioritised selection for the next HORIZON_DAYS
+//   Scheduler     — desktop: in-memory queue, fires what became due
+
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
+
+use crate::briefing::{self, BriefingKind};
+use crate::career::{Appello, CareerExam};
+use crate::i18n::{self, Lang};
+use crate::read_json;
+
+// ── Clock & sink ──────────────────────────────────────────────────────────
+
+/// Source of the current local wall-clock time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Notification {
+    pub key: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Where fired notifications end up (OS notification center, memory, ...).
+pub trait NotificationSink: Send + Sync {
+    fn deliver(&self, notification: &Notification) -> Result<(), String>;
+}
+
+// ── Input data ────────────────────────────────────────────────────────────
+
+/// Snapshot of the JSON files the engine reads.
+pub struct NotificationData {
+    pub events: Value,
+    pub settings: Value,
+    pub career: Value,
+}
+
+impl NotificationData {
+    pub fn load(dir: &Path) -> Self {
+        NotificationData {
+            events: read_json(dir, "events", Value::Array(vec![])),
+            settings: read_json(dir, "settings", Value::Null),
+            career: read_json(dir, "career", Value::Null),
+        }
+    }
+
+    fn lang(&self) -> Lang {
+        Lang::from_settings(&self.settings)
+    }
+}
+
+// ── Per-category preferences (settings.categoryNotif) ─────────────────────
+//
+// settings.categoryNotif.<category> = {
+//   "notify": bool,      — per-event reminders fire at all
+//   "briefing": bool,    — events count toward morning/afternoon/evening briefings
+//   "reminders": {...}   — default `reminders` object for new events
+// }
+// Missing entries fall back to the defaults below; breaks (`pausa`) are
+// excluded from briefings unless the user opts in.  Exams also get
+// `reminders.repeatMinutes` (see "Escalating exam-day reminders").
+
+const EVENT_CATEGORIES: [&str; 6] = ["lezione", "studio", "esame", "progetto", "personale", "pausa"];
+
+pub struct CategoryPrefs {
+    pub notify: bool,
+    pub briefing: bool,
+}
+
+pub fn event_category(event: &Value) -> &str {
+    event.get("category").and_then(|c| c.as_str()).unwrap_or("lezione")
+}
+
+fn default_category_notif(category: &str) -> Value {
+    let mut prefs = serde_json::json!({
+        "notify": true,
+        "briefing": category != "pausa",
+        "reminders": {
+            "dayBefore": { "enabled": category == "esame", "time": "20:00" },
+            "sameDay": { "enabled": category == "esame", "time": "07:00" },
+            "customRemindTime": 0
+        }
+    });
+    if category == "esame" {
+        prefs["reminders"]["repeatMinutes"] = Value::from(DEFAULT_REPEAT_MINUTES);
+    }
+    prefs
+}
+
+pub fn default_category_notif_settings() -> Value {
+    let map: serde_json::Map<String, Value> = EVENT_CATEGORIES.iter()
+        .map(|c| (c.to_string(), default_category_notif(c)))
+        .collect();
+    Value::Object(map)
+}
+
+fn category_notif(settings: &Value, category: &str) -> Value {
+    settings.get("categoryNotif")
+        .and_then(|c| c.get(category))
+        .cloned()
+        .unwrap_or_else(|| default_category_notif(category))
+}
+
+pub fn category_prefs(settings: &Value, category: &str) -> CategoryPrefs {
+    let prefs = category_notif(settings, category);
+    let defaults = default_category_notif(category);
+    let flag = |key: &str| prefs.get(key)
+        .or_else(|| defaults.get(key))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    CategoryPrefs { notify: flag("notify"), briefing: flag("briefing") }
+}
+
+/// Events on `date` that count toward the daily briefings.
+pub fn briefing_events<'a>(events: &'a Value, settings: &Value, date: &str) -> Vec<&'a Value> {
+    match events.as_array() {
+        Some(arr) => arr.iter()
+            .filter(|e| e.get("date").and_then(|d| d.as_str()) == Some(date))
+            .filter(|e| category_prefs(settings, event_category(e)).briefing)
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Fills in the category's default `reminders` for events saved without one.
+pub fn apply_default_reminders(events: &mut Value, settings: &Value) {
+    if let Some(arr) = events.as_array_mut() {
+        for event in arr.iter_mut() {
+            if event.get("reminders").is_some() { continue; }
+            let defaults = category_notif(settings, event_category(event));
+            if let (Some(obj), Some(reminders)) = (event.as_object_mut(), defaults.get("reminders")) {
+                obj.insert("reminders".to_string(), reminders.clone());
+            }
+        }
+    }
+}
+
+// ── Delivery status ───────────────────────────────────────────────────────
+
+/// Delivery health reported by `get_notification_status`, next to the
+/// permission state.
+#[derive(Default)]
+pub struct DeliveryStatus {
+    /// Last time the scheduler processed its queue (desktop) or the planner
+    /// ran (mobile).
+    pub scheduler_ran_at: Option<NaiveDateTime>,
+    pub next: Option<PlannedNotification>,
+    pub last_delivered: Option<(NaiveDateTime, String)>,
+    pub last_error: Option<(NaiveDateTime, String)>,
+}
+
+impl DeliveryStatus {
+    pub fn record(&mut self, at: NaiveDateTime, key: &str, result: &Result<(), String>) {
+        match result {
+            Ok(()) => self.last_delivered = Some((at, key.to_string())),
+            Err(e) => self.last_error = Some((at, format!("{}: {}", key, e))),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let fmt = |t: &NaiveDateTime| t.format("%Y-%m-%d %H:%M").to_string();
+        serde_json::json!({
+            "schedulerRanAt": self.scheduler_ran_at.as_ref().map(fmt),
+            "next": self.next.as_ref().map(|p| serde_json::json!({
+                "at": fmt(&p.at),
+                "key": p.notification.key,
+                "title": p.notification.title,
+                "body": p.notification.body,
+            })),
+            "lastDelivered": self.last_delivered.as_ref().map(|(at, key)| serde_json::json!({ "at": fmt(at), "key": key })),
+            "lastError": self.last_error.as_ref().map(|(at, msg)| serde_json::json!({ "at": fmt(at), "message": msg })),
+        })
+    }
+}
+
+// ── Candidate notifications ───────────────────────────────────────────────
+
+/// Priorities used when the mobile slot limit is hit (lower goes first).
+const PRIORITY_EXAM: u8 = 0;
+const PRIORITY_REMINDER: u8 = 1;
+const PRIORITY_BRIEFING_TODAY: u8 = 2;
+const PRIORITY_BRIEFING_AHEAD: u8 = 3;
+const PRIORITY_REFILL: u8 = 4;
+
+/// Ordered by fire time, then priority.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PlannedNotification {
+    pub at: NaiveDateTime,
+    pub priority: u8,
+    pub notification: Notification,
+}
+
+fn parse_at(date: &str, time: &str) -> Option<NaiveDateTime> {
+    if time.len() < 5 { return None; }
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").ok()
+}
+
+fn planned(at: NaiveDateTime, priority: u8, key: String, title: String, body: String) -> PlannedNotification {
+    PlannedNotification { at, priority, notification: Notification { key, title, body } }
+}
+
+/// dayBefore / sameDay / customRemindTime reminders of every event.
+fn event_reminders(data: &NotificationData, lang: Lang) -> Vec<PlannedNotification> {
+    let mut out = Vec::new();
+    let events = match data.events.as_array() {
+        Some(arr) => arr,
+        None => return out,
+    };
+    let fallback_title = i18n::t(lang, "fallback.event");
+
+    for event in events {
+        let category = event_category(event);
+        if !category_prefs(&data.settings, category).notify { continue; }
+        let reminders_obj = match event.get("reminders") {
+            Some(r) if r.is_object() => r, _ => continue,
+        };
+        let priority = if category == "esame" { PRIORITY_EXAM } else { PRIORITY_REMINDER };
+        let event_date = event.get("date").and_then(|d| d.as_str()).unwrap_or("");
+        let title_text = event.get("title").and_then(|t| t.as_str()).unwrap_or(&fallback_title);
+        let event_key = event.get("id").and_then(|i| i.as_str()).unwrap_or(title_text);
+        let time_start = event.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
+        // Earliest reminder on the day of the event, where exam repeats start
+        let mut first_on_day: Option<NaiveDateTime> = None;
+
+        if let Some(db) = reminders_obj.get("dayBefore") {
+            let enabled = db.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
+            let rem_time = db.get("time").and_then(|v| v.as_str()).unwrap_or("");
+            if enabled {
+                if let Ok(edate) = NaiveDate::parse_from_str(event_date, "%Y-%m-%d") {
+                    let day_before = (edate - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+                    if let Some(at) = parse_at(&day_before, rem_time) {
+                        out.push(planned(at, priority,
+                            format!("sp-db-{}-{}-{}", event_key, event_date, rem_time),
+                            i18n::t(lang, "title.remind_tomorrow"),
+                            i18n::tf(lang, "body.remind_tomorrow", &[("title", &title_text), ("time", &time_start)])));
+                    }
+                }
+            }
+        }
+
+        if let Some(sd) = reminders_obj.get("sameDay") {
+            let enabled = sd.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
+            let rem_time = sd.get("time").and_then(|v| v.as_str()).unwrap_or("");
+            if enabled {
+                if let Some(at) = parse_at(event_date, rem_time) {
+                    out.push(planned(at, priority,
+                        format!("sp-sd-{}-{}-{}", event_key, event_date, rem_time),
+                        i18n::t(lang, "title.remind_today"),
+                        i18n::tf(lang, "body.remind_today", &[("title", &title_text), ("time", &time_start)])));
+                    first_on_day = Some(at);
+                }
+            }
+        }
+
+        if let Some(custom_mins) = reminders_obj.get("customRemindTime").and_then(|v| v.as_i64()) {
+            if custom_mins > 0 {
+                if let Some(start) = parse_at(event_date, time_start) {
+                    let at = start - chrono::Duration::minutes(custom_mins);
+                    if at.date() == start.date() && first_on_day.map_or(true, |f| at < f) {
+                        first_on_day = Some(at);
+                    }
+                    out.push(planned(at, priority,
+                        format!("sp-cr-{}-{}-{}-{}", event_key, event_date, time_start, custom_mins),
+                        i18n::t(lang, "title.soon"),
+                        i18n::tn(lang, "body.soon", custom_mins, &[("title", &title_text)])));
+                }
+            }
+        }
+
+        if let (true, Some(first)) = (category == "esame", first_on_day) {
+            out.extend(escalation(event, &data.settings, first, event_key, title_text, lang));
+        }
+    }
+    out
+}
+
+// ── Escalating exam-day reminders ──────────────────────────────────────────
+//
+// For `esame` events the first reminder on the day of the exam (sameDay or
+// customRemindTime) repeats every `reminders.repeatMinutes` minutes (0 =
+// off; events without the field use the esame default of
+// settings.categoryNotif) until the event starts or the user acknowledges
+// it.  The acknowledgement is stored in the event as `reminderAckAt`
+// ("YYYY-MM-DDTHH:MM:SS", see `acknowledge_reminder`) and only counts on
+// the day of the event, so moving the exam re-arms it.
+
+const DEFAULT_REPEAT_MINUTES: i64 = 15;
+/// Shorter intervals are raised to this.
+const MIN_REPEAT_MINUTES: i64 = 5;
+
+/// Repeats of the exam-day reminder fired at `first`.
+fn escalation(event: &Value, settings: &Value, first: NaiveDateTime, event_key: &str, title_text: &str, lang: Lang) -> Vec<PlannedNotification> {
+    let repeat_of = |reminders: Option<&Value>| reminders.and_then(|r| r.get("repeatMinutes")).and_then(|v| v.as_i64());
+    let repeat = repeat_of(event.get("reminders"))
+        .or_else(|| repeat_of(category_notif(settings, "esame").get("reminders")))
+        .unwrap_or(DEFAULT_REPEAT_MINUTES);
+    if repeat <= 0 { return vec![]; }
+    let time_start = event.get("timeStart").and_then(|t| t.as_str()).unwrap_or("");
+    let start = match parse_at(&first.format("%Y-%m-%d").to_string(), time_start) {
+        Some(start) => start, None => return vec![],
+    };
+    let acked_at = event.get("reminderAckAt")
+        .and_then(|a| a.as_str())
+        .and_then(|a| NaiveDateTime::parse_from_str(a, "%Y-%m-%dT%H:%M:%S").ok())
+        .filter(|a| a.date() == first.date());
+    let step = chrono::Duration::minutes(repeat.max(MIN_REPEAT_MINUTES));
+
+    let mut out = Vec::new();
+    let mut at = first + step;
+    while at < start && acked_at.map_or(true, |ack| at <= ack) {
+        let minutes_left = (start - at).num_minutes();
+        out.push(planned(at, PRIORITY_REMINDER,
+            format!("sp-rp-{}-{}", event_key, at.format("%Y-%m-%d-%H:%M")),
+            i18n::t(lang, "title.escalate"),
+            i18n::tn(lang, "body.escalate", minutes_left, &[("title", &title_text), ("time", &time_start)])));
+        at += step;
+    }
+    out
+}
+
+/// Marks the escalating reminder of `event` as seen at `now`.  Only exams
+/// have one.
+pub fn acknowledge(event: &mut Value, now: NaiveDateTime) -> Result<(), String> {
+    if event_category(event) != "esame" {
+        return Err("Solo i promemoria degli esami si ripetono".to_string());
+    }
+    match event.as_object_mut() {
+        Some(obj) => {
+            obj.insert("reminderAckAt".to_string(), Value::from(now.format("%Y-%m-%dT%H:%M:%S").to_string()));
+            Ok(())
+        }
+        None => Err("Evento non valido".to_string()),
+    }
+}
+
+// ── Exam countdown series ─────────────────────────────────────────────────
+//
+// Every exam in career.json with a planned date (target appello, booked
+// attempt or `examDate`, see `CareerExam::planned_date`) and
+// `status != "passed"` gets one notification per configured offset
+// (settings.examCountdownDays), fired at settings.examCountdownTime on the
+// day `date - offset`.
+
+pub const DEFAULT_COUNTDOWN_DAYS: [i64; 5] = [30, 14, 7, 3, 1];
+
+fn exam_countdown(data: &NotificationData, lang: Lang) -> Vec<PlannedNotification> {
+    let settings = &data.settings;
+    let enabled = settings.get("examCountdownNotif").and_then(|v| v.as_bool()).unwrap_or(true);
+    if !enabled { return vec![]; }
+    let time = settings.get("examCountdownTime").and_then(|v| v.as_str()).unwrap_or("09:00");
+    let days: Vec<i64> = match settings.get("examCountdownDays").and_then(|v| v.as_array()) {
+        Some(arr) => arr.iter().filter_map(|d| d.as_i64()).filter(|d| *d > 0).collect(),
+        None => DEFAULT_COUNTDOWN_DAYS.to_vec(),
+    };
+
+    let exams = match data.career.get("exams").and_then(|e| e.as_array()) {
+        Some(arr) => arr,
+        None => return vec![],
+    };
+
+    let fallback_name = i18n::t(lang, "fallback.exam");
+    let mut out = Vec::new();
+    for exam in exams {
+        let Ok(typed) = serde_json::from_value::<CareerExam>(exam.clone()) else { continue };
+        if typed.is_passed() { continue; }
+        let exam_date = typed.planned_date().unwrap_or("");
+        let edate = match NaiveDate::parse_from_str(exam_date, "%Y-%m-%d") {
+            Ok(d) => d, Err(_) => continue,
+        };
+        let name = if typed.name.is_empty() { fallback_name.as_str() } else { typed.name.as_str() };
+        let exam_key = if typed.id.is_empty() { name } else { typed.id.as_str() };
+        let progress = exam.get("progress").and_then(|p| p.as_i64()).unwrap_or(0);
+
+        for &offset in &days {
+            let fire_date = (edate - chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+            let at = match parse_at(&fire_date, time) {
+                Some(at) => at, None => continue,
+            };
+            let body = if offset == 1 {
+                i18n::tf(lang, "body.countdown.tomorrow", &[("exam", &name), ("progress", &progress)])
+            } else {
+                i18n::tn(lang, "body.countdown", offset, &[("exam", &name), ("progress", &progress)])
+            };
+            out.push(planned(at, PRIORITY_EXAM,
+                format!("sp-cd-{}-{}-{}", exam_key, exam_date, offset),
+                i18n::t(lang, "title.countdown"),
+                body));
+        }
+    }
+    out
+}
+
+// ── Appello registration deadlines ────────────────────────────────────────
+//
+// For every pending exam, the target appello — or, until one is chosen,
+// every upcoming candidate — that is not marked `registered` gets a reminder
+// settings.registrationReminderDays days before its registrationDeadline
+// (0 = the deadline day itself), at settings.examCountdownTime.  Disabled
+// with settings.registrationNotif = false.
+
+pub const DEFAULT_REGISTRATION_DAYS: [i64; 3] = [3, 1, 0];
+
+fn registration_reminders(data: &NotificationData, lang: Lang) -> Vec<PlannedNotification> {
+    let settings = &data.settings;
+    let enabled = settings.get("registrationNotif").and_then(|v| v.as_bool()).unwrap_or(true);
+    if !enabled { return vec![]; }
+    let time = settings.get("examCountdownTime").and_then(|v| v.as_str()).unwrap_or("09:00");
+    let days: Vec<i64> = match settings.get("registrationReminderDays").and_then(|v| v.as_array()) {
+        Some(arr) => arr.iter().filter_map(|d| d.as_i64()).filter(|d| *d >= 0).collect(),
+        None => DEFAULT_REGISTRATION_DAYS.to_vec(),
+    };
+
+    let exams = match data.career.get("exams").and_then(|e| e.as_array()) {
+        Some(arr) => arr,
+        None => return vec![],
+    };
+
+    let fallback_name = i18n::t(lang, "fallback.exam");
+    let mut out = Vec::new();
+    for exam in exams {
+        let Ok(typed) = serde_json::from_value::<CareerExam>(exam.clone()) else { continue };
+        if typed.is_passed() { continue; }
+        let name = if typed.name.is_empty() { fallback_name.as_str() } else { typed.name.as_str() };
+        let candidates: Vec<&Appello> = match typed.target() {
+            Some(target) => vec![target],
+            None => typed.appelli.iter().collect(),
+        };
+        for appello in candidates.into_iter().filter(|a| !a.registered) {
+            let deadline = match NaiveDate::parse_from_str(&appello.registration_deadline, "%Y-%m-%d") {
+                Ok(d) => d, Err(_) => continue,
+            };
+            let date = NaiveDate::parse_from_str(&appello.date, "%Y-%m-%d")
+                .map(|d| d.format("%d/%m").to_string())
+                .unwrap_or_else(|_| appello.date.clone());
+            for &offset in &days {
+                let fire_date = (deadline - chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+                let at = match parse_at(&fire_date, time) {
+                    Some(at) => at, None => continue,
+                };
+                let body = if offset == 0 {
+                    i18n::tf(lang, "body.registration.today", &[("exam", &name), ("date", &date)])
+                } else {
+                    i18n::tn(lang, "body.registration", offset, &[("exam", &name), ("date", &date)])
+                };
+                out.push(planned(at, PRIORITY_EXAM,
+                    format!("sp-rg-{}-{}-{}", typed.id, appello.id, offset),
+                    i18n::t(lang, "title.registration"),
+                    body));
+            }
+        }
+    }
+    out
+}
+
+// ── Planning ──────────────────────────────────────────────────────────────
+
+/// Every notification whose fire time falls in `(from, to]`, sorted by time.
+///
+/// `ahead` is set when planning for later delivery (mobile): from the day
+/// after `from` on, only the morning briefing is planned and it leaves out
+/// the previous day's completion rate, which is not known yet.
+pub fn plan(data: &NotificationData, from: NaiveDateTime, to: NaiveDateTime, ahead: bool) -> Vec<PlannedNotification> {
+    let lang = data.lang();
+    let in_window = |at: &NaiveDateTime| *at > from && *at <= to;
+
+    let mut out: Vec<PlannedNotification> = event_reminders(data, lang).into_iter()
+        .chain(exam_countdown(data, lang))
+        .chain(registration_reminders(data, lang))
+        .filter(|p| in_window(&p.at))
+        .collect();
+
+    let first_day = from.date();
+    let mut date = first_day;
+    while date <= to.date() {
+        let later_day = date > first_day;
+        let day = date.format("%Y-%m-%d").to_string();
+        for kind in BriefingKind::ALL {
+            if ahead && later_day && kind != BriefingKind::Morning { continue; }
+            if !kind.enabled(&data.settings) { continue; }
+            let at = match parse_at(&day, &kind.time(&data.settings)) {
+                Some(at) if in_window(&at) => at,
+                _ => continue,
+            };
+            let built = if ahead && later_day {
+                briefing::build_planned(kind, &data.events, &data.settings, &data.career, date, lang)
+            } else {
+                briefing::build(kind, &data.events, &data.settings, &data.career, date, lang)
+            };
+            if let Some(b) = built {
+                let priority = if later_day { PRIORITY_BRIEFING_AHEAD } else { PRIORITY_BRIEFING_TODAY };
+                out.push(planned(at, priority, format!("sp-{}-{}", kind.key(), day), b.title, b.body));
+            }
+        }
+        date += chrono::Duration::days(1);
+    }
+
+    out.sort_by(|a, b| a.at.cmp(&b.at).then(a.priority.cmp(&b.priority)));
+    out
+}
+
+/// Maximum number of notifications the mobile OS keeps pending for us.
+pub const MAX_SCHEDULED: usize = 60;
+pub const HORIZON_DAYS: i64 = 14;
+
+/// Result of the mobile planner.
+#[cfg_attr(not(any(target_os = "android", target_os = "ios")), allow(dead_code))]
+pub struct AheadPlan {
+    pub scheduled: Vec<PlannedNotification>,
+    pub dropped: usize,
+    pub horizon_end: NaiveDateTime,
+    /// Everything due before this moment made it into `scheduled`.
+    pub covered_until: NaiveDateTime,
+}
+
+/// Mobile planner: the `MAX_SCHEDULED` most important notifications of the
+/// next `HORIZON_DAYS`, exam reminders first.  When something is left out
+/// (slot limit or horizon), the last slot prompts the user to open the app
+/// the day before coverage runs out.
+#[cfg_attr(not(any(target_os = "android", target_os = "ios")), allow(dead_code))]
+pub fn plan_ahead(data: &NotificationData, now: NaiveDateTime) -> AheadPlan {
+    let horizon_end = now + chrono::Duration::days(HORIZON_DAYS);
+    let mut candidates = plan(data, now, horizon_end, true);
+    let beyond_horizon = plan(data, horizon_end, horizon_end + chrono::Duration::days(HORIZON_DAYS), true)
+        .iter()
+        .any(|p| p.priority <= PRIORITY_REMINDER);
+
+    candidates.sort_by(|a, b| a.priority.cmp(&b.priority).then(a.at.cmp(&b.at)));
+    let mut dropped = Vec::new();
+    if candidates.len() > MAX_SCHEDULED - 1 {
+        dropped = candidates.split_off(MAX_SCHEDULED - 1);
+    }
+    let covered_until = dropped.iter().map(|p| p.at).min().unwrap_or(horizon_end);
+
+    if !dropped.is_empty() || beyond_horizon {
+        let prompt_day = (covered_until - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+        if let Some(at) = parse_at(&prompt_day, &BriefingKind::Morning.time(&data.settings)) {
+            if at > now {
+                let lang = data.lang();
+                candidates.push(planned(at, PRIORITY_REFILL,
+                    format!("sp-refill-{}", prompt_day),
+                    i18n::t(lang, "title.refill"),
+                    i18n::t(lang, "body.refill")));
+            }
+        }
+    }
+
+    candidates.sort_by_key(|p| p.at);
+    AheadPlan { scheduled: candidates, dropped: dropped.len(), horizon_end, covered_until }
+}
+
+// ── Desktop scheduler ─────────────────────────────────────────────────────
+
+/// After a suspend or a long stall, reminders older than this are dropped
+/// instead of being delivered in a burst.  Covers the one-hour DST jump.
+const MAX_CATCH_UP_MINUTES: i64 = 90;
+/// The queue covers this far ahead and is topped up when half of it is used.
+const QUEUE_WINDOW_HOURS: i64 = 48;
+
+/// In-memory queue of upcoming notifications, ordered by fire time.
+///
+/// The data is read once and replaced through `reload()` whenever it changes
+/// on disk, so waking up costs no I/O.  Delivery works on the interval
+/// `(last run, now]` rather than on "the current minute": a late wake-up
+/// (timer drift, the DST spring-forward gap) still fires, and when the wall
+/// clock goes back (DST fall-back) nothing fires twice.
+#[cfg_attr(any(target_os = "android", target_os = "ios"), allow(dead_code))]
+pub struct Scheduler {
+    data: NotificationData,
+    queue: BinaryHeap<Reverse<PlannedNotification>>,
+    /// Latest minute already processed.
+    last_run: Option<NaiveDateTime>,
+    /// End of the window currently in the queue; `None` after a reload.
+    planned_until: Option<NaiveDateTime>,
+}
+
+#[cfg_attr(any(target_os = "android", target_os = "ios"), allow(dead_code))]
+impl Scheduler {
+    pub fn new(data: NotificationData) -> Self {
+        Scheduler { data, queue: BinaryHeap::new(), last_run: None, planned_until: None }
+    }
+
+    /// Swaps in fresh data; the queue is rebuilt on the next `run_due()`.
+    /// Notifications already delivered are not repeated.
+    pub fn reload(&mut self, data: NotificationData) {
+        self.data = data;
+        self.queue.clear();
+        self.planned_until = None;
+    }
+
+    /// Next queued notification.
+    pub fn peek(&self) -> Option<&PlannedNotification> {
+        self.queue.peek().map(|Reverse(p)| p)
+    }
+
+    /// Fire time of the next queued notification.
+    pub fn next_wake(&self) -> Option<NaiveDateTime> {
+        self.peek().map(|p| p.at)
+    }
+
+    /// Delivers everything due by now; returns what was handed to the sink.
+    pub fn run_due(&mut self, clock: &dyn Clock, sink: &dyn NotificationSink) -> Vec<Notification> {
+        let now = clock.now().with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or_else(|| clock.now());
+        // Clock moved back by more than a DST shift (set by hand): start over
+        if self.last_run.is_some_and(|last| now < last - chrono::Duration::hours(2)) {
+            self.last_run = None;
+            self.queue.clear();
+            self.planned_until = None;
+        }
+        let from = match self.last_run {
+            Some(last) => last.max(now - chrono::Duration::minutes(MAX_CATCH_UP_MINUTES)),
+            None => now - chrono::Duration::minutes(1),
+        };
+        self.last_run = Some(self.last_run.map_or(now, |last| last.max(now)));
+
+        let window = chrono::Duration::hours(QUEUE_WINDOW_HOURS);
+        if !self.planned_until.is_some_and(|until| until >= now + window / 2) {
+            let start = self.planned_until.unwrap_or(from);
+            let end = now + window;
+            self.queue.extend(plan(&self.data, start, end, false).into_iter().map(Reverse));
+            self.planned_until = Some(end);
+        }
+
+        let mut fired = Vec::new();
+        while self.next_wake().is_some_and(|at| at <= now) {
+            let Reverse(p) = self.queue.pop().unwrap();
+            if p.at <= from { continue; }
+            match sink.deliver(&p.notification) {
+                Ok(()) => fired.push(p.notification),
+                Err(e) => eprintln!("[StudyPlan Cron] ✗ {} not delivered: {}", p.notification.key, e),
+            }
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Weekday};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// Clock that only moves when told to.
+    struct ManualClock(Mutex<NaiveDateTime>);
+
+    impl ManualClock {
+        fn new(now: NaiveDateTime) -> Self {
+            ManualClock(Mutex::new(now))
+        }
+
+        fn set(&self, now: NaiveDateTime) {
+            *self.0.lock().unwrap() = now;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// Sink that records every delivery, tagged with the clock time it happened at.
+    struct MemorySink<'a> {
+        clock: &'a dyn Clock,
+        delivered: Mutex<Vec<(NaiveDateTime, Notification)>>,
+    }
+
+    impl<'a> MemorySink<'a> {
+        fn new(clock: &'a dyn Clock) -> Self {
+            MemorySink { clock, delivered: Mutex::new(Vec::new()) }
+        }
+
+        fn delivered(&self) -> Vec<(NaiveDateTime, Notification)> {
+            self.delivered.lock().unwrap().clone()
+        }
+    }
+
+    impl NotificationSink for MemorySink<'_> {
+        fn deliver(&self, notification: &Notification) -> Result<(), String> {
+            self.delivered.lock().unwrap().push((self.clock.now(), notification.clone()));
+            Ok(())
+        }
+    }
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    /// Europe/Rome wall time for a UTC instant (CET/CEST, EU rules: the
+    /// switch happens at 01:00 UTC on the last Sunday of March / October).
+    fn rome_local(utc: NaiveDateTime) -> NaiveDateTime {
+        let last_sunday = |month: u32| {
+            let mut d = NaiveDate::from_ymd_opt(utc.year(), month, 31).unwrap();
+            while d.weekday() != Weekday::Sun { d -= chrono::Duration::days(1); }
+            d.and_hms_opt(1, 0, 0).unwrap()
+        };
+        let summer = utc >= last_sunday(3) && utc < last_sunday(10);
+        utc + chrono::Duration::hours(if summer { 2 } else { 1 })
+    }
+
+    /// Drives the scheduler like the desktop loop does between `[from, to)`
+    /// UTC: it wakes at the next queued fire time, or after
+    /// `MAX_SLEEP_MINUTES` at the latest.  Returns what the sink received, as
+    /// ("local time", key) pairs.
+    fn simulate(data: NotificationData, from_utc: &str, to_utc: &str) -> Vec<(String, String)> {
+        const MAX_SLEEP_MINUTES: i64 = 5;
+        let mut utc = dt(from_utc);
+        let end = dt(to_utc);
+        let clock = ManualClock::new(rome_local(utc));
+        let sink = MemorySink::new(&clock);
+        let mut scheduler = Scheduler::new(data);
+        let mut next_check = utc;
+        while utc < end {
+            let local = rome_local(utc);
+            if utc >= next_check || scheduler.next_wake().is_some_and(|at| at <= local) {
+                clock.set(local);
+                scheduler.run_due(&clock, &sink);
+                next_check = utc + chrono::Duration::minutes(MAX_SLEEP_MINUTES);
+            }
+            utc += chrono::Duration::minutes(1);
+        }
+        sink.delivered().into_iter()
+            .map(|(at, n)| (at.format("%Y-%m-%d %H:%M").to_string(), n.key))
+            .collect()
+    }
+
+    fn quiet_settings() -> Value {
+        json!({ "morningNotif": false, "afternoonNotif": false, "eveningNotif": false, "examCountdownNotif": false })
+    }
+
+    fn data(events: Value, settings: Value, career: Value) -> NotificationData {
+        NotificationData { events, settings, career }
+    }
+
+    fn event(id: &str, date: &str, start: &str, reminders: Value) -> Value {
+        json!({ "id": id, "title": id, "date": date, "timeStart": start, "category": "lezione", "reminders": reminders })
+    }
+
+    #[test]
+    fn fires_each_reminder_once_over_several_days() {
+        let events = json!([
+            event("analisi", "2026-05-12", "10:00", json!({
+                "dayBefore": { "enabled": true, "time": "20:00" },
+                "sameDay": { "enabled": true, "time": "07:00" },
+                "customRemindTime": 30
+            })),
+            event("fisica", "2026-05-13", "09:00", json!({ "sameDay": { "enabled": true, "time": "08:00" } })),
+        ]);
+        let fired = simulate(data(events, quiet_settings(), Value::Null), "2026-05-10 22:00", "2026-05-13 22:00");
+        assert_eq!(fired, vec![
+            ("2026-05-11 20:00".to_string(), "sp-db-analisi-2026-05-12-20:00".to_string()),
+            ("2026-05-12 07:00".to_string(), "sp-sd-analisi-2026-05-12-07:00".to_string()),
+            ("2026-05-12 09:30".to_string(), "sp-cr-analisi-2026-05-12-10:00-30".to_string()),
+            ("2026-05-13 08:00".to_string(), "sp-sd-fisica-2026-05-13-08:00".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn custom_reminder_crosses_midnight() {
+        let events = json!([event("notturno", "2026-05-13", "00:10", json!({ "customRemindTime": 20 }))]);
+        let fired = simulate(data(events, quiet_settings(), Value::Null), "2026-05-12 20:00", "2026-05-12 23:00");
+        assert_eq!(fired, vec![("2026-05-12 23:50".to_string(), "sp-cr-notturno-2026-05-13-00:10-20".to_string())]);
+    }
+
+    #[test]
+    fn late_wake_after_midnight_still_fires() {
+        let events = json!([event("mezzanotte", "2026-05-13", "09:00", json!({ "sameDay": { "enabled": true, "time": "00:00" } }))]);
+        let clock = ManualClock::new(dt("2026-05-12 23:59"));
+        let sink = MemorySink::new(&clock);
+        let mut scheduler = Scheduler::new(data(events, quiet_settings(), Value::Null));
+        scheduler.run_due(&clock, &sink);
+        assert_eq!(scheduler.next_wake(), Some(dt("2026-05-13 00:00")));
+        clock.set(dt("2026-05-13 00:01"));
+        scheduler.run_due(&clock, &sink);
+        let keys: Vec<String> = sink.delivered().into_iter().map(|(_, n)| n.key).collect();
+        assert_eq!(keys, vec!["sp-sd-mezzanotte-2026-05-13-00:00".to_string()]);
+    }
+
+    #[test]
+    fn spring_forward_gap_fires_at_three() {
+        // 2026-03-29: Rome jumps from 02:00 to 03:00, 02:30 never happens
+        let events = json!([event("gap", "2026-03-29", "10:00", json!({ "sameDay": { "enabled": true, "time": "02:30" } }))]);
+        let fired = simulate(data(events, quiet_settings(), Value::Null), "2026-03-28 23:00", "2026-03-29 03:00");
+        assert_eq!(fired, vec![("2026-03-29 03:00".to_string(), "sp-sd-gap-2026-03-29-02:30".to_string())]);
+    }
+
+    #[test]
+    fn fall_back_repeated_hour_fires_once() {
+        // 2026-10-25: Rome goes from 03:00 back to 02:00, 02:30 happens twice
+        let events = json!([event("doppio", "2026-10-25", "10:00", json!({ "sameDay": { "enabled": true, "time": "02:30" } }))]);
+        let fired = simulate(data(events, quiet_settings(), Value::Null), "2026-10-24 22:00", "2026-10-25 04:00");
+        assert_eq!(fired, vec![("2026-10-25 02:30".to_string(), "sp-sd-doppio-2026-10-25-02:30".to_string())]);
+    }
+
+    #[test]
+    fn briefings_follow_settings_and_categories() {
+        let events = json!([
+            event("lezione", "2026-05-12", "09:00", json!({})),
+            { "id": "pausa", "title": "Caffè", "date": "2026-05-13", "timeStart": "10:00", "category": "pausa" },
+        ]);
+        let settings = json!({
+            "morningNotif": true, "afternoonNotif": false, "eveningNotif": true,
+            "morningTime": "07:30", "eveningTime": "21:00", "examCountdownNotif": false
+        });
+        let fired = simulate(data(events, settings, Value::Null), "2026-05-11 17:00", "2026-05-13 22:00");
+        // The break on the 13th does not count, so there is no evening or
+        // morning briefing for it.
+        assert_eq!(fired, vec![
+            ("2026-05-11 21:00".to_string(), "sp-evening-2026-05-11".to_string()),
+            ("2026-05-12 07:30".to_string(), "sp-morning-2026-05-12".to_string()),
+            ("2026-05-12 21:00".to_string(), "sp-evening-2026-05-12".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn long_suspend_drops_stale_reminders() {
+        let events = json!([
+            event("stale", "2026-05-12", "09:00", json!({ "sameDay": { "enabled": true, "time": "08:00" } })),
+            event("recent", "2026-05-12", "12:00", json!({ "sameDay": { "enabled": true, "time": "11:00" } })),
+        ]);
+        let clock = ManualClock::new(dt("2026-05-12 06:00"));
+        let sink = MemorySink::new(&clock);
+        let mut scheduler = Scheduler::new(data(events, quiet_settings(), Value::Null));
+        scheduler.run_due(&clock, &sink);
+        clock.set(dt("2026-05-12 12:00"));
+        scheduler.run_due(&clock, &sink);
+        let keys: Vec<String> = sink.delivered().into_iter().map(|(_, n)| n.key).collect();
+        assert_eq!(keys, vec!["sp-sd-recent-2026-05-12-11:00".to_string()]);
+    }
+
+    #[test]
+    fn reload_picks_up_changes_without_repeating() {
+        let first = event("prima", "2026-05-12", "10:00", json!({ "sameDay": { "enabled": true, "time": "08:00" } }));
+        let clock = ManualClock::new(dt("2026-05-12 07:00"));
+        let sink = MemorySink::new(&clock);
+        let mut scheduler = Scheduler::new(data(json!([first.clone()]), quiet_settings(), Value::Null));
+        scheduler.run_due(&clock, &sink);
+        clock.set(dt("2026-05-12 08:00"));
+        scheduler.run_due(&clock, &sink);
+
+        // Saved at 08:30: a new event, and the first one is still there
+        let second = event("seconda", "2026-05-12", "12:00", json!({ "customRemindTime": 60 }));
+        clock.set(dt("2026-05-12 08:30"));
+        scheduler.reload(data(json!([first, second]), quiet_settings(), Value::Null));
+        scheduler.run_due(&clock, &sink);
+        assert_eq!(scheduler.next_wake(), Some(dt("2026-05-12 11:00")));
+        clock.set(dt("2026-05-12 11:00"));
+        scheduler.run_due(&clock, &sink);
+
+        let keys: Vec<String> = sink.delivered().into_iter().map(|(_, n)| n.key).collect();
+        assert_eq!(keys, vec![
+            "sp-sd-prima-2026-05-12-08:00".to_string(),
+            "sp-cr-seconda-2026-05-12-12:00-60".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn exam_reminder_repeats_until_acknowledged() {
+        let exam = json!({
+            "id": "orale", "title": "Orale", "date": "2026-05-12", "timeStart": "09:00", "category": "esame",
+            "reminders": { "sameDay": { "enabled": true, "time": "08:00" }, "repeatMinutes": 20 }
+        });
+        let clock = ManualClock::new(dt("2026-05-12 07:00"));
+        let sink = MemorySink::new(&clock);
+        let mut scheduler = Scheduler::new(data(json!([exam.clone()]), quiet_settings(), Value::Null));
+        for time in ["07:00", "08:00", "08:20"] {
+            clock.set(dt(&format!("2026-05-12 {}", time)));
+            scheduler.run_due(&clock, &sink);
+        }
+
+        let mut exam = exam;
+        assert!(acknowledge(&mut exam, dt("2026-05-12 08:25")).is_ok());
+        scheduler.reload(data(json!([exam]), quiet_settings(), Value::Null));
+        for time in ["08:25", "08:40", "09:00"] {
+            clock.set(dt(&format!("2026-05-12 {}", time)));
+            scheduler.run_due(&clock, &sink);
+        }
+
+        let keys: Vec<String> = sink.delivered().into_iter().map(|(_, n)| n.key).collect();
+        assert_eq!(keys, vec![
+            "sp-sd-orale-2026-05-12-08:00".to_string(),
+            "sp-rp-orale-2026-05-12-08:20".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn exam_reminder_repeats_stop_when_the_exam_starts() {
+        let exam = json!({
+            "id": "scritto", "title": "Scritto", "date": "2026-05-12", "timeStart": "09:00", "category": "esame",
+            "reminderAckAt": "2026-05-11T21:00:00",
+            "reminders": { "sameDay": { "enabled": true, "time": "08:00" }, "repeatMinutes": 20 }
+        });
+        let fired = simulate(data(json!([exam]), quiet_settings(), Value::Null), "2026-05-12 05:00", "2026-05-12 09:00");
+        // An acknowledgement from another day does not count
+        assert_eq!(fired, vec![
+            ("2026-05-12 08:00".to_string(), "sp-sd-scritto-2026-05-12-08:00".to_string()),
+            ("2026-05-12 08:20".to_string(), "sp-rp-scritto-2026-05-12-08:20".to_string()),
+            ("2026-05-12 08:40".to_string(), "sp-rp-scritto-2026-05-12-08:40".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn exam_repeats_follow_the_reminder_that_fires_with_the_default_interval() {
+        // Saved by the event dialog: no sameDay, no repeatMinutes
+        let exam = json!({
+            "id": "lab", "title": "Lab", "date": "2026-05-12", "timeStart": "10:00", "category": "esame",
+            "reminders": { "dayBefore": { "enabled": true, "time": "20:00" }, "customRemindTime": 45 }
+        });
+        let fired = simulate(data(json!([exam]), quiet_settings(), Value::Null), "2026-05-11 17:00", "2026-05-12 10:00");
+        assert_eq!(fired, vec![
+            ("2026-05-11 20:00".to_string(), "sp-db-lab-2026-05-12-20:00".to_string()),
+            ("2026-05-12 09:15".to_string(), "sp-cr-lab-2026-05-12-10:00-45".to_string()),
+            ("2026-05-12 09:30".to_string(), "sp-rp-lab-2026-05-12-09:30".to_string()),
+            ("2026-05-12 09:45".to_string(), "sp-rp-lab-2026-05-12-09:45".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn only_exam_reminders_can_be_acknowledged() {
+        let mut lesson = event("lezione", "2026-05-12", "09:00", json!({ "sameDay": { "enabled": true, "time": "08:00" } }));
+        assert!(acknowledge(&mut lesson, dt("2026-05-12 08:05")).is_err());
+        assert!(lesson.get("reminderAckAt").is_none());
+    }
+
+    #[test]
+    fn mobile_plan_puts_exam_reminders_first() {
+        let mut events = Vec::new();
+        for day in 0..40 {
+            let date = (NaiveDate::from_ymd_opt(2026, 5, 11).unwrap() + chrono::Duration::days(day % 10))
+                .format("%Y-%m-%d").to_string();
+            events.push(event(&format!("e{}", day), &date, "10:00", json!({ "customRemindTime": 10 + day })));
+            events.push(event(&format!("s{}", day), &date, "11:00", json!({ "sameDay": { "enabled": true, "time": "08:00" } })));
+        }
+        events.push(json!({
+            "id": "scritto", "title": "Scritto", "date": "2026-05-20", "timeStart": "09:00", "category": "esame",
+            "reminders": { "dayBefore": { "enabled": true, "time": "20:00" } }
+        }));
+        let d = data(Value::Array(events), quiet_settings(), Value::Null);
+        let plan = plan_ahead(&d, dt("2026-05-10 12:00"));
+
+        assert_eq!(plan.scheduled.len(), MAX_SCHEDULED);
+        assert!(plan.dropped > 0);
+        assert!(plan.scheduled.iter().any(|p| p.notification.key == "sp-db-scritto-2026-05-20-20:00"));
+        assert!(plan.scheduled.iter().any(|p| p.notification.key.starts_with("sp-refill-")));
+        assert!(plan.covered_until < plan.horizon_end);
+    }
+
+    #[test]
+    fn appelli_drive_registration_reminders_and_countdown() {
+        let career = |target: Value, registered: bool| json!({ "exams": [{
+            "id": "fisica", "name": "Fisica", "cfu": 9, "status": "pending", "targetAppello": target,
+            "appelli": [
+                { "id": "gen", "date": "2026-06-10", "registrationDeadline": "2026-06-05", "registered": registered },
+                { "id": "lug", "date": "2026-07-08", "registrationDeadline": "2026-07-01" }
+            ]
+        }]});
+        let settings = json!({
+            "morningNotif": false, "afternoonNotif": false, "eveningNotif": false,
+            "examCountdownDays": [7], "registrationReminderDays": [1, 0]
+        });
+        let keys = |career: Value| -> Vec<String> {
+            plan(&data(json!([]), settings.clone(), career), dt("2026-06-01 00:00"), dt("2026-07-31 00:00"), false)
+                .into_iter().map(|p| p.notification.key).collect()
+        };
+
+        // No target yet: every candidate's deadline, no countdown
+        assert_eq!(keys(career(Value::Null, false)), [
+            "sp-rg-fisica-gen-1", "sp-rg-fisica-gen-0", "sp-rg-fisica-lug-1", "sp-rg-fisica-lug-0",
+        ]);
+        // Target chosen: only its deadline, and the countdown follows its date
+        assert_eq!(keys(career(json!("lug"), false)), [
+            "sp-rg-fisica-lug-1", "sp-cd-fisica-2026-07-08-7", "sp-rg-fisica-lug-0",
+        ]);
+        // Registered: the countdown stays, the deadline reminders stop
+        assert_eq!(keys(career(json!("gen"), true)), ["sp-cd-fisica-2026-06-10-7"]);
+    }
+}
@@ -0,0 +1,657 @@
+// ===== PDF Attachments =====
+//
+// Reading of the PDFs attached to exams (pdf-notes/).  Files are parsed with
+// lopdf, so the page count comes from the page tree rather than from
+// scanning the bytes: compressed object streams, incremental updates and
+// linearized files are counted correctly.  Any failure is returned as an
+// Italian message ready for the UI.
+//
+// lopdf can panic or loop forever on malformed files, and release builds
+// abort on panic, so on desktop the parsing runs in a child process (see
+// "Worker process" below): a bad attachment costs the child, not the app.
+//
+// The outline (bookmarks) gives the chapters of a PDF: every entry starts at
+// the page its destination points to and ends where the next entry of the
+// same or a higher level begins, so study progress can be tracked chapter by
+// chapter.
+//
+// Page previews are rasterised with Pdfium, which the release bundles for
+// each target (scripts/fetch-pdfium.sh), also in the worker process.
+// Rendered pages are cached as PNG in pdf-thumbs/ under the content hash of
+// the PDF, so a cached page never goes stale.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde_json::Value;
+
+/// Guards against malformed outlines (cycles, absurd nesting).
+const MAX_OUTLINE_ENTRIES: usize = 5000;
+const MAX_OUTLINE_DEPTH: u32 = 32;
+
+/// Parses the PDF at `path`.  Files protected only by an owner password
+/// (printing/copy restrictions) open with the empty user password; files
+/// that need a password to be opened are reported as protected.  May panic
+/// on malformed files: call it only through `Job::run`.
+fn open(path: &Path) -> Result<Document, String> {
+    let mut doc = Document::load(path).map_err(|e| match e {
+        lopdf::Error::IO(e) => format!("Impossibile leggere il PDF: {}", e),
+        e => format!("PDF danneggiato o non leggibile: {}", e),
+    })?;
+    if doc.is_encrypted() && doc.decrypt("").is_err() {
+        return Err("PDF protetto da password".to_string());
+    }
+    Ok(doc)
+}
+
+/// Number of pages of the PDF at `path`.  A document whose page tree can't
+/// be walked (no pages found) is an error, never `0`.
+pub fn page_count(path: &Path) -> Result<u32, String> {
+    let pages = run_job(Job::Pages, path)?;
+    pages.as_u64().map(|n| n as u32).ok_or_else(|| "PDF danneggiato o non leggibile".to_string())
+}
+
+/// Page count and outline of the PDF at `path` (see `outline`).
+pub fn outline_of(path: &Path) -> Result<(u32, Vec<Chapter>), String> {
+    let result = run_job(Job::Outline, path)?;
+    let chapters = result["outline"].as_array().map(Vec::as_slice).unwrap_or(&[]).iter()
+        .filter_map(Chapter::from_json)
+        .collect();
+    Ok((result["pages"].as_u64().unwrap_or(0) as u32, chapters))
+}
+
+/// Text of every page of the PDF at `path` (see `extract_texts`).
+pub fn page_texts(path: &Path) -> Result<Vec<String>, String> {
+    let texts = run_job(Job::Texts, path)?;
+    Ok(texts.as_array().map(Vec::as_slice).unwrap_or(&[]).iter()
+        .map(|t| t.as_str().unwrap_or("").to_string())
+        .collect())
+}
+
+fn count_pages(doc: &Document) -> Result<u32, String> {
+    match doc.get_pages().len() {
+        0 if doc.is_encrypted() => Err("PDF cifrato in un formato non supportato".to_string()),
+        0 => Err("PDF danneggiato: nessuna pagina trovata".to_string()),
+        n => Ok(n as u32),
+    }
+}
+
+// ── Worker process ──
+//
+// On desktop every job runs in the app's own executable started with
+// `WORKER_ARG` (see `worker_main`), which prints the result as JSON.  A
+// child that crashes or exceeds `WORKER_TIMEOUT` means the file is
+// unreadable.  Mobile apps can't start processes: there a file is parsed
+// in-process, and only if `looks_valid` accepts its structure.
+
+pub const WORKER_ARG: &str = "--pdf-worker";
+#[cfg(desktop)]
+const WORKER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// What is read from a PDF.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Job {
+    /// Page count.
+    Pages,
+    /// `{ pages, outline }`.
+    Outline,
+    /// Text of each page.
+    Texts,
+    /// Page `page` rendered `width` pixels wide into the PNG `out`:
+    /// `{ width, height }`.  Pdfium is looked for in `lib_dirs` first.
+    Render { page: u32, width: u32, out: PathBuf, lib_dirs: Vec<PathBuf> },
+}
+
+impl Job {
+    #[cfg_attr(not(desktop), allow(dead_code))]
+    fn name(&self) -> &'static str {
+        match self {
+            Job::Pages => "pages",
+            Job::Outline => "outline",
+            Job::Texts => "texts",
+            Job::Render { .. } => "render",
+        }
+    }
+
+    /// Arguments after the PDF path on the worker command line.
+    #[cfg_attr(not(desktop), allow(dead_code))]
+    fn args(&self) -> Vec<std::ffi::OsString> {
+        match self {
+            Job::Render { page, width, out, lib_dirs } => {
+                let mut args = vec![page.to_string().into(), width.to_string().into(), out.clone().into_os_string()];
+                args.extend(lib_dirs.iter().map(|d| d.clone().into_os_string()));
+                args
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse(name: &str, mut args: impl Iterator<Item = String>) -> Option<Job> {
+        match name {
+            "pages" => Some(Job::Pages),
+            "outline" => Some(Job::Outline),
+            "texts" => Some(Job::Texts),
+            "render" => Some(Job::Render {
+                page: args.next()?.parse().ok()?,
+                width: args.next()?.parse().ok()?,
+                out: PathBuf::from(args.next()?),
+                lib_dirs: args.map(PathBuf::from).collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Reads `path` in this process.
+    fn run(&self, path: &Path) -> Result<Value, String> {
+        let doc = match self {
+            Job::Render { page, width, out, lib_dirs } => return render_png(path, *page, *width, out, lib_dirs),
+            _ => open(path)?,
+        };
+        match self {
+            Job::Pages => count_pages(&doc).map(Value::from),
+            Job::Outline => Ok(serde_json::json!({
+                "pages": doc.get_pages().len(),
+                "outline": outline(&doc).iter().map(Chapter::to_json).collect::<Vec<_>>(),
+            })),
+            Job::Texts => Ok(Value::from(extract_texts(&doc))),
+            Job::Render { .. } => unreachable!("rendered above"),
+        }
+    }
+}
+
+/// Runs `job` on `path` where a malformed file can't bring the app down.
+fn run_job(job: Job, path: &Path) -> Result<Value, String> {
+    #[cfg(desktop)]
+    {
+        in_worker(&job, path)
+    }
+    #[cfg(not(desktop))]
+    {
+        looks_valid(path)?;
+        job.run(path)
+    }
+}
+
+/// Entry point of the worker process: `<exe> --pdf-worker <job> <path>
+/// [<job args>]`.  Returns `None` when the process wasn't started as a
+/// worker.
+pub fn worker_main(mut args: impl Iterator<Item = String>) -> Option<i32> {
+    if args.nth(1).as_deref() != Some(WORKER_ARG) {
+        return None;
+    }
+    let (name, path) = (args.next(), args.next());
+    let result = match (name.and_then(|n| Job::parse(&n, args)), path) {
+        (Some(job), Some(path)) => job.run(Path::new(&path)),
+        _ => Err("Richiesta non valida".to_string()),
+    };
+    let out = match result {
+        Ok(value) => serde_json::json!({ "ok": value }),
+        Err(e) => serde_json::json!({ "error": e }),
+    };
+    println!("{}", out);
+    Some(0)
+}
+
+#[cfg(desktop)]
+fn in_worker(job: &Job, path: &Path) -> Result<Value, String> {
+    use std::io::Read as _;
+    use std::process::{Command, Stdio};
+    use std::time::Instant;
+
+    let exe = std::env::current_exe().map_err(|e| format!("Impossibile leggere il PDF: {}", e))?;
+    let mut child = Command::new(exe)
+        .arg(WORKER_ARG).arg(job.name()).arg(path).args(job.args())
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Impossibile leggere il PDF: {}", e))?;
+    // Drained while waiting, so a large result can't fill the pipe
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let reader = std::thread::spawn(move || {
+        let mut out = String::new();
+        let _ = stdout.read_to_string(&mut out);
+        out
+    });
+
+    let deadline = Instant::now() + WORKER_TIMEOUT;
+    let finished = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.success(),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(std::time::Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break false;
+            }
+        }
+    };
+    let out = reader.join().unwrap_or_default();
+    let reply: Value = serde_json::from_str(out.trim()).unwrap_or(Value::Null);
+    if !finished || reply.is_null() {
+        eprintln!("[StudyPlan] PDF worker failed on {}", path.display());
+        return Err("PDF danneggiato o non leggibile".to_string());
+    }
+    match reply["error"].as_str() {
+        Some(e) => Err(e.to_string()),
+        None => Ok(reply["ok"].clone()),
+    }
+}
+
+/// Cheap structural check before parsing in-process: a `%PDF-` header near
+/// the start and a `startxref` / `%%EOF` trailer near the end.  It keeps
+/// out files that aren't PDFs or are truncated, not every malformed one.
+#[cfg_attr(desktop, allow(dead_code))]
+fn looks_valid(path: &Path) -> Result<(), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Impossibile leggere il PDF: {}", e))?;
+    let contains = |hay: &[u8], needle: &[u8]| hay.windows(needle.len()).any(|w| w == needle);
+    let head = &bytes[..bytes.len().min(1024)];
+    let tail = &bytes[bytes.len().saturating_sub(2048)..];
+    if !contains(head, b"%PDF-") {
+        return Err("Il file non è un PDF".to_string());
+    }
+    if !contains(tail, b"startxref") || !contains(tail, b"%%EOF") {
+        return Err("PDF danneggiato o incompleto".to_string());
+    }
+    Ok(())
+}
+
+// ── Outline ──
+
+/// An outline entry with the pages it covers (1-based, inclusive).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    /// 0 for top-level entries.
+    pub level: u32,
+    pub start_page: u32,
+    pub end_page: u32,
+}
+
+impl Chapter {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "title": self.title,
+            "level": self.level,
+            "startPage": self.start_page,
+            "endPage": self.end_page,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Option<Chapter> {
+        Some(Chapter {
+            title: value["title"].as_str()?.to_string(),
+            level: value["level"].as_u64()? as u32,
+            start_page: value["startPage"].as_u64()? as u32,
+            end_page: value["endPage"].as_u64()? as u32,
+        })
+    }
+}
+
+/// The outline of `doc` in reading order.  Entries whose destination can't
+/// be resolved to a page (external links, actions other than GoTo) are
+/// left out; their children are kept.  A PDF without bookmarks gives an
+/// empty list.
+fn outline(doc: &Document) -> Vec<Chapter> {
+    let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(n, id)| (id, n)).collect();
+    let total = pages.len() as u32;
+    let first = doc.catalog().ok()
+        .and_then(|c| doc.get_dict_in_dict(c, b"Outlines").ok())
+        .and_then(|o| o.get(b"First").ok())
+        .and_then(|f| f.as_reference().ok());
+
+    let mut entries = Vec::new();
+    walk(doc, first, 0, &pages, &mut HashSet::new(), &mut entries);
+
+    let mut chapters: Vec<Chapter> = entries.iter().enumerate().map(|(i, (title, level, start))| {
+        // Ends before the next entry that isn't nested in this one; entries
+        // pointing backwards (out-of-order bookmarks) are skipped.
+        let end = entries[i + 1..].iter()
+            .filter(|(_, l, _)| l <= level)
+            .find(|(_, _, s)| s >= start)
+            .map(|(_, _, s)| if s > start { s - 1 } else { *start })
+            .unwrap_or(total);
+        Chapter { title: title.clone(), level: *level, start_page: *start, end_page: end }
+    }).collect();
+    chapters.retain(|c| c.start_page <= c.end_page);
+    chapters
+}
+
+/// The entries to track progress on: the top level of the outline, or the
+/// level below when the top is a single wrapper (the book title, "Indice")
+/// around the real chapters.  Chapters never overlap.
+pub fn tracked_chapters(outline: &[Chapter]) -> Vec<Chapter> {
+    let mut level = 0;
+    loop {
+        let at_level = outline.iter().filter(|c| c.level == level).count();
+        let deeper = outline.iter().any(|c| c.level > level);
+        if at_level == 1 && deeper {
+            level += 1;
+        } else {
+            break;
+        }
+    }
+    outline.iter().filter(|c| c.level == level).cloned().collect()
+}
+
+fn walk(
+    doc: &Document,
+    mut node: Option<ObjectId>,
+    level: u32,
+    pages: &HashMap<ObjectId, u32>,
+    seen: &mut HashSet<ObjectId>,
+    out: &mut Vec<(String, u32, u32)>,
+) {
+    if level > MAX_OUTLINE_DEPTH { return; }
+    while let Some(id) = node {
+        if seen.len() >= MAX_OUTLINE_ENTRIES || !seen.insert(id) { return; }
+        let Ok(item) = doc.get_dictionary(id) else { return };
+        let title = item.get(b"Title").ok()
+            .and_then(|t| doc.dereference(t).ok())
+            .and_then(|(_, t)| lopdf::decode_text_string(t).ok())
+            .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        if let Some(page) = target_page(doc, item, pages) {
+            if !title.is_empty() {
+                out.push((title, level, page));
+            }
+        }
+        let child = item.get(b"First").ok().and_then(|f| f.as_reference().ok());
+        walk(doc, child, level + 1, pages, seen, out);
+        node = item.get(b"Next").ok().and_then(|n| n.as_reference().ok());
+    }
+}
+
+/// Page number an outline item jumps to, from `/Dest` or a GoTo `/A`.
+fn target_page(doc: &Document, item: &Dictionary, pages: &HashMap<ObjectId, u32>) -> Option<u32> {
+    let dest = match item.get(b"Dest") {
+        Ok(d) => d,
+        Err(_) => {
+            let action = deref(doc, item.get(b"A").ok()?)?.as_dict().ok()?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" { return None; }
+            action.get(b"D").ok()?
+        }
+    };
+    let dest = resolve_dest(doc, deref(doc, dest)?)?;
+    // [page /XYZ left top zoom], page being a reference to the page object
+    let page = deref_id(doc, dest.as_array().ok()?.first()?)?;
+    pages.get(&page).copied()
+}
+
+/// Named destinations (`/Dests` in the catalog, or the `/Names` tree) are
+/// looked up; explicit destinations are returned as they are.
+fn resolve_dest<'a>(doc: &'a Document, dest: &'a Object) -> Option<&'a Object> {
+    let name = match dest {
+        Object::Name(n) => n.as_slice(),
+        Object::String(s, _) => s.as_slice(),
+        _ => return Some(dest),
+    };
+    let catalog = doc.catalog().ok()?;
+    let found = doc.get_dict_in_dict(catalog, b"Dests").ok()
+        .and_then(|d| d.get(name).ok())
+        .or_else(|| {
+            let names = doc.get_dict_in_dict(catalog, b"Names").ok()?;
+            let tree = doc.get_dict_in_dict(names, b"Dests").ok()?;
+            name_tree_get(doc, tree, name, 0)
+        })?;
+    // A named destination is either the array itself or a dictionary
+    // holding it under /D.
+    let found = deref(doc, found)?;
+    match found.as_dict() {
+        Ok(d) => deref(doc, d.get(b"D").ok()?),
+        Err(_) => Some(found),
+    }
+}
+
+fn name_tree_get<'a>(doc: &'a Document, node: &'a Dictionary, key: &[u8], depth: u32) -> Option<&'a Object> {
+    if depth > MAX_OUTLINE_DEPTH { return None; }
+    if let Some(names) = node.get(b"Names").ok().and_then(|n| deref(doc, n)).and_then(|n| n.as_array().ok()) {
+        let hit = names.chunks(2).find(|pair| deref(doc, &pair[0]).and_then(|k| k.as_str().ok()) == Some(key));
+        if let Some([_, value]) = hit {
+            return Some(value);
+        }
+    }
+    let kids = deref(doc, node.get(b"Kids").ok()?)?.as_array().ok()?;
+    kids.iter().find_map(|kid| {
+        let kid = deref(doc, kid)?.as_dict().ok()?;
+        name_tree_get(doc, kid, key, depth + 1)
+    })
+}
+
+fn deref<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Object> {
+    doc.dereference(obj).ok().map(|(_, o)| o)
+}
+
+/// Id of the object `obj` refers to (the last one of a reference chain).
+fn deref_id(doc: &Document, obj: &Object) -> Option<ObjectId> {
+    doc.dereference(obj).ok().and_then(|(id, _)| id)
+}
+
+// ── Text ──
+
+/// Text of every page of `doc` (index 0 is page 1) with whitespace
+/// collapsed.  Runs in fonts lopdf can't decode are dropped; a page whose
+/// content can't be read at all gives an empty string.
+fn extract_texts(doc: &Document) -> Vec<String> {
+    let count = doc.get_pages().len() as u32;
+    (1..=count).map(|n| {
+        let chunks: Vec<String> = doc.extract_text_chunks(&[n]).into_iter().filter_map(Result::ok).collect();
+        chunks.join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+    }).collect()
+}
+
+// ── Thumbnails ──
+
+pub const THUMBS_DIR: &str = "pdf-thumbs";
+pub const THUMB_WIDTH: u32 = 240;
+const THUMB_WIDTH_RANGE: (u32, u32) = (64, 1600);
+
+/// Pdfium is initialised once per render; concurrent renders in one process
+/// (mobile) would race on its global state.
+static PDFIUM: Mutex<()> = Mutex::new(());
+
+/// PNG of page `page` (1-based) of `pdf`, whose content hash is `hash`,
+/// `width` pixels wide: from the cache in `cache_dir`, or rendered and
+/// cached.
+pub fn thumbnail(pdf: &Path, hash: &str, cache_dir: &Path, page: u32, width: u32, lib_dirs: &[PathBuf]) -> Result<PathBuf, String> {
+    let width = width.clamp(THUMB_WIDTH_RANGE.0, THUMB_WIDTH_RANGE.1);
+    let cached = cache_dir.join(format!("{}.p{}.w{}.png", hash, page, width));
+    if cached.is_file() {
+        return Ok(cached);
+    }
+    std::fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+    run_job(Job::Render { page, width, out: cached.clone(), lib_dirs: lib_dirs.to_vec() }, pdf)?;
+    Ok(cached)
+}
+
+/// Drops the cached pages of the PDF with content hash `hash`.
+pub fn remove_thumbnails(cache_dir: &Path, hash: &str) {
+    let prefix = format!("{}.p", hash);
+    for entry in std::fs::read_dir(cache_dir).into_iter().flatten().flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Renders page `page` of `pdf` into the PNG `out`, through a temporary
+/// file so the cache never holds a partial image.
+fn render_png(pdf: &Path, page: u32, width: u32, out: &Path, lib_dirs: &[PathBuf]) -> Result<Value, String> {
+    let img = render_page(pdf, page, width, lib_dirs)?;
+    let tmp = out.with_extension("png.tmp");
+    img.save_with_format(&tmp, image::ImageFormat::Png).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, out).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        e.to_string()
+    })?;
+    Ok(serde_json::json!({ "width": img.width(), "height": img.height() }))
+}
+
+/// Rasterises page `page` of `pdf` to `width` pixels (height follows the
+/// page, at most twice the width), white background.
+fn render_page(pdf: &Path, page: u32, width: u32, lib_dirs: &[PathBuf]) -> Result<image::RgbaImage, String> {
+    use pdfium_render::prelude::*;
+
+    let _guard = PDFIUM.lock().unwrap_or_else(|e| e.into_inner());
+    let bindings = lib_dirs.iter()
+        .find_map(|d| Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(d)).ok())
+        .map_or_else(Pdfium::bind_to_system_library, Ok)
+        .map_err(|_| "Anteprime non disponibili: libreria Pdfium non trovata".to_string())?;
+    let pdfium = Pdfium::new(bindings);
+
+    let doc = pdfium.load_pdf_from_file(pdf, None).map_err(|e| match e {
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError) => {
+            "PDF protetto da password".to_string()
+        }
+        e => format!("PDF danneggiato o non leggibile: {:?}", e),
+    })?;
+    let pages = doc.pages();
+    let index = page.checked_sub(1)
+        .and_then(|i| PdfPageIndex::try_from(i).ok())
+        .filter(|i| *i < pages.len())
+        .ok_or_else(|| format!("Pagina {} inesistente: il PDF ha {} pagine", page, pages.len()))?;
+    let config = PdfRenderConfig::new()
+        .set_target_width(width as Pixels)
+        .set_maximum_height((width * 2) as Pixels)
+        .render_form_data(true);
+    let pdf_page = pages.get(index).map_err(|e| format!("Pagina {} non leggibile: {:?}", page, e))?;
+    let bitmap = pdf_page.render_with_config(&config)
+        .map_err(|e| format!("Impossibile disegnare la pagina {}: {:?}", page, e))?;
+    image::RgbaImage::from_raw(bitmap.width() as u32, bitmap.height() as u32, bitmap.as_rgba_bytes())
+        .ok_or_else(|| format!("Impossibile disegnare la pagina {}", page))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    /// A document of `pages` empty pages; `outline` builds the outline
+    /// items given the page ids and returns the first top-level item.
+    fn document(pages: u32, outline: impl FnOnce(&mut Document, &[ObjectId]) -> Option<ObjectId>) -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let page_ids: Vec<ObjectId> = (0..pages)
+            .map(|_| doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()] }))
+            .collect();
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>(),
+            "Count" => pages,
+        }));
+        let first = outline(&mut doc, &page_ids);
+        let mut catalog = dictionary! { "Type" => "Catalog", "Pages" => pages_id };
+        if let Some(first) = first {
+            catalog.set("Outlines", dictionary! { "Type" => "Outlines", "First" => first });
+        }
+        let catalog_id = doc.add_object(catalog);
+        doc.trailer.set("Root", catalog_id);
+        doc
+    }
+
+    fn dest(page: ObjectId) -> Object {
+        Object::Array(vec![Object::Reference(page), Object::Name(b"Fit".to_vec())])
+    }
+
+    /// Links `items` (title, destination, children) as siblings and
+    /// returns the id of the first.
+    fn items(doc: &mut Document, items: Vec<(&str, Object, Vec<ObjectId>)>) -> Option<ObjectId> {
+        let ids: Vec<ObjectId> = items.iter().map(|_| doc.new_object_id()).collect();
+        for (i, (title, target, children)) in items.into_iter().enumerate() {
+            let mut item = dictionary! { "Title" => Object::string_literal(title) };
+            match target {
+                Object::Dictionary(action) => item.set("A", action),
+                Object::Null => {}
+                target => item.set("Dest", target),
+            }
+            if let Some(first) = children.first() {
+                item.set("First", *first);
+            }
+            if let Some(next) = ids.get(i + 1) {
+                item.set("Next", *next);
+            }
+            doc.objects.insert(ids[i], Object::Dictionary(item));
+        }
+        ids.first().copied()
+    }
+
+    fn chapter(title: &str, level: u32, start_page: u32, end_page: u32) -> Chapter {
+        Chapter { title: title.to_string(), level, start_page, end_page }
+    }
+
+    #[test]
+    fn outline_entries_end_where_the_next_one_at_the_same_level_starts() {
+        let doc = document(10, |doc, pages| {
+            let sections = items(doc, vec![
+                ("1.1", dest(pages[1]), vec![]),
+                ("1.2", dest(pages[2]), vec![]),
+            ]).unwrap();
+            // Cap 2 through a GoTo action, Cap 3 through a named destination
+            let goto = Object::Dictionary(dictionary! { "S" => "GoTo", "D" => dest(pages[3]) });
+            let chapters = items(doc, vec![
+                ("Cap 1", dest(pages[0]), vec![sections]),
+                ("Cap 2", goto, vec![]),
+                ("Link esterno", Object::Null, vec![]),
+                ("Cap 3", Object::Name(b"cap3".to_vec()), vec![]),
+            ]).unwrap();
+            items(doc, vec![("Libro", dest(pages[0]), vec![chapters])])
+        });
+        // Named destinations in the catalog's /Dests
+        let mut doc = doc;
+        let page7 = doc.get_pages()[&7];
+        let dests = doc.add_object(dictionary! { "cap3" => dest(page7) });
+        let catalog = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+        doc.get_dictionary_mut(catalog).unwrap().set("Dests", dests);
+
+        let outline = outline(&doc);
+        assert_eq!(outline, vec![
+            chapter("Libro", 0, 1, 10),
+            chapter("Cap 1", 1, 1, 3),
+            chapter("1.1", 2, 2, 2),
+            chapter("1.2", 2, 3, 3),
+            chapter("Cap 2", 1, 4, 6),
+            chapter("Cap 3", 1, 7, 10),
+        ]);
+        // The single wrapper is skipped: progress is tracked per chapter
+        assert_eq!(tracked_chapters(&outline), vec![
+            chapter("Cap 1", 1, 1, 3),
+            chapter("Cap 2", 1, 4, 6),
+            chapter("Cap 3", 1, 7, 10),
+        ]);
+    }
+
+    #[test]
+    fn tracked_chapters_keep_a_top_level_with_several_entries() {
+        let outline = vec![
+            chapter("Parte I", 0, 1, 4),
+            chapter("Cap 1", 1, 1, 4),
+            chapter("Parte II", 0, 5, 9),
+        ];
+        assert_eq!(tracked_chapters(&outline), vec![chapter("Parte I", 0, 1, 4), chapter("Parte II", 0, 5, 9)]);
+        assert!(tracked_chapters(&[]).is_empty());
+    }
+
+    #[test]
+    fn malformed_outlines_are_read_without_looping() {
+        let doc = document(4, |doc, pages| {
+            let first = items(doc, vec![
+                ("A", dest(pages[2]), vec![]),
+                // Points backwards: it is kept but does not end "A"
+                ("B", dest(pages[0]), vec![]),
+                ("C", dest(pages[3]), vec![]),
+            ]).unwrap();
+            // "C" links back to "A": a cycle
+            let last = doc.get_dictionary(first).unwrap().get(b"Next").unwrap().as_reference().unwrap();
+            let last = doc.get_dictionary(last).unwrap().get(b"Next").unwrap().as_reference().unwrap();
+            doc.get_dictionary_mut(last).unwrap().set("Next", first);
+            Some(first)
+        });
+        assert_eq!(outline(&doc), vec![
+            chapter("A", 0, 3, 3),
+            chapter("B", 0, 1, 3),
+            chapter("C", 0, 4, 4),
+        ]);
+        assert!(outline(&document(2, |_, _| None)).is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+// ===== PDF Full-Text Index =====
+//
+// Text of the attached PDFs (pdf-notes/), extracted in the background and
+// kept in pdf-index.json so a restart doesn't read every file again.  An
+// entry is extracted again when the file's size or modification time
+// changes and dropped when the file is gone.
+//
+// Search scans the page texts with case and accents folded ("perche"
+// finds "Perché"): a page matches when it contains every word of the
+// query, and hits are ranked by how often the words occur.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pdf;
+
+pub const INDEX_FILE: &str = "pdf-index.json";
+/// Bumped when the extraction changes, so old indexes are rebuilt.
+const INDEX_VERSION: u32 = 1;
+/// Characters of context on each side of a hit.
+const SNIPPET_CONTEXT: usize = 80;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PdfIndex {
+    #[serde(default)]
+    pub version: u32,
+    /// By file name in pdf-notes/.
+    #[serde(default)]
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedFile {
+    pub size: u64,
+    /// Modification time, ms since the epoch.
+    pub modified: u64,
+    /// Text of each page; index 0 is page 1.
+    #[serde(default)]
+    pub pages: Vec<String>,
+    /// Why the file couldn't be read (protected, damaged); it is not
+    /// retried until it changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PdfIndex {
+    pub fn load(dir: &Path) -> PdfIndex {
+        let index = fs::read_to_string(dir.join(INDEX_FILE)).ok()
+            .and_then(|s| serde_json::from_str::<PdfIndex>(&s).ok())
+            .unwrap_or_default();
+        if index.version == INDEX_VERSION {
+            index
+        } else {
+            PdfIndex { version: INDEX_VERSION, files: BTreeMap::new() }
+        }
+    }
+
+    /// Written through a temporary file so the index on disk is never
+    /// half-written.
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let tmp = dir.join(format!("{}.tmp", INDEX_FILE));
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(&tmp, content).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, dir.join(INDEX_FILE)).map_err(|e| e.to_string())
+    }
+
+    /// Pages with text, over all files.
+    pub fn page_count(&self) -> usize {
+        self.files.values().map(|f| f.pages.len()).sum()
+    }
+}
+
+/// In-memory index of the current data directory plus the state of the
+/// background indexer; lives in `AppState` behind a mutex.
+#[derive(Debug, Default)]
+pub struct IndexState {
+    dir: Option<PathBuf>,
+    index: PdfIndex,
+    /// An indexing thread is running.
+    pub running: bool,
+    /// Files changed while indexing: run again when done.
+    pub pending: bool,
+    /// Files indexed so far and files to index in the current run.
+    pub progress: (usize, usize),
+}
+
+impl IndexState {
+    /// The index of `dir`, loaded from disk on first use or when the data
+    /// directory changed.
+    pub fn index_for(&mut self, dir: &Path) -> &mut PdfIndex {
+        if self.dir.as_deref() != Some(dir) {
+            self.index = PdfIndex::load(dir);
+            self.dir = Some(dir.to_path_buf());
+        }
+        &mut self.index
+    }
+}
+
+// ── Indexing ──
+
+/// A PDF of pdf-notes/ that needs its text extracted.
+#[derive(Debug, Clone)]
+pub struct Stale {
+    pub file_name: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Files of `pdf_dir` missing from `index` or changed since they were
+/// indexed, and indexed files that no longer exist.
+pub fn stale(index: &PdfIndex, pdf_dir: &Path) -> (Vec<Stale>, Vec<String>) {
+    let mut present = Vec::new();
+    let mut todo = Vec::new();
+    for entry in fs::read_dir(pdf_dir).into_iter().flatten().flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !file_name.to_lowercase().ends_with(".pdf") { continue; }
+        let Ok(meta) = entry.metadata() else { continue };
+        if !meta.is_file() { continue; }
+        let modified = meta.modified().ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let size = meta.len();
+        let fresh = index.files.get(&file_name).is_some_and(|f| f.size == size && f.modified == modified);
+        if !fresh {
+            todo.push(Stale { file_name: file_name.clone(), size, modified });
+        }
+        present.push(file_name);
+    }
+    let gone = index.files.keys().filter(|k| !present.contains(k)).cloned().collect();
+    (todo, gone)
+}
+
+/// Extracts the text of `file` (in the PDF worker process, see pdf.rs).
+/// Runs outside of any lock: large PDFs take seconds.  A file that can't
+/// be read gets an entry with `error`, so it's skipped until it changes.
+pub fn extract(pdf_dir: &Path, file: &Stale) -> IndexedFile {
+    let path = pdf_dir.join(&file.file_name);
+    let (pages, error) = match pdf::page_texts(&path) {
+        Ok(pages) => (pages, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    IndexedFile { size: file.size, modified: file.modified, pages, error }
+}
+
+// ── Search ──
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub file_name: String,
+    pub page: u32,
+    pub snippet: String,
+    /// Occurrences of the query words on the page.
+    pub score: usize,
+}
+
+/// Pages containing every word of `query`, best first (then by file and
+/// page).
+pub fn search(index: &PdfIndex, query: &str, limit: usize) -> Vec<Hit> {
+    let terms: Vec<String> = query.split_whitespace().map(fold_str).filter(|t| !t.is_empty()).collect();
+    if terms.is_empty() { return Vec::new(); }
+
+    let mut hits = Vec::new();
+    for (file_name, file) in &index.files {
+        for (i, text) in file.pages.iter().enumerate() {
+            let folded = fold_str(text);
+            if !terms.iter().all(|t| folded.contains(t.as_str())) { continue; }
+            let score = terms.iter().map(|t| folded.matches(t.as_str()).count()).sum();
+            // Earliest occurrence of any word, as a char index; folding
+            // keeps one char per char, so it indexes `text` too.
+            let (first, len) = terms.iter()
+                .filter_map(|t| folded.find(t.as_str()).map(|b| (folded[..b].chars().count(), t.chars().count())))
+                .min()
+                .unwrap_or((0, 0));
+            hits.push(Hit {
+                file_name: file_name.clone(),
+                page: i as u32 + 1,
+                snippet: snippet(text, first, len),
+                score,
+            });
+        }
+    }
+    hits.sort_by(|a, b| b.score.cmp(&a.score)
+        .then_with(|| a.file_name.cmp(&b.file_name))
+        .then_with(|| a.page.cmp(&b.page)));
+    hits.truncate(limit);
+    hits
+}
+
+/// Lowercase without accents, one char for each char of the input.
+fn fold(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ò' | 'ó' | 'ô' | 'ö' | 'õ' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        '’' => '\'',
+        c => c,
+    }
+}
+
+fn fold_str(s: &str) -> String {
+    s.chars().map(fold).collect()
+}
+
+/// Text around chars `start..start + len` of `text`, cut at word
+/// boundaries, with "…" where it was cut.
+fn snippet(text: &str, start: usize, len: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut from = start.saturating_sub(SNIPPET_CONTEXT);
+    let mut to = (start + len + SNIPPET_CONTEXT).min(chars.len());
+    if from > 0 {
+        while from < start && !chars[from - 1].is_whitespace() { from += 1; }
+    }
+    if to < chars.len() {
+        while to > start + len && !chars[to].is_whitespace() { to -= 1; }
+    }
+    let mut out: String = chars[from..to].iter().collect::<String>().trim().to_string();
+    if from > 0 { out.insert(0, '…'); }
+    if to < chars.len() { out.push('…'); }
+    out
+}
+
@@ -0,0 +1,298 @@
+// ===== Prerequisites (propedeuticità) =====
+//
+// A career exam lists in `prerequisites` the ids of the exams that must be
+// passed before it can be taken.  The graph must be acyclic: `save_career`
+// rejects an edit that closes a cycle (one already saved is only reported,
+// so it can be fixed in place) and reports, for the exams it saves, which
+// ones became takeable and which were passed with prerequisites still
+// missing.
+
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::career::{Career, CareerExam};
+
+fn by_id(career: &Career) -> HashMap<&str, &CareerExam> {
+    career.exams.iter().map(|e| (e.id.as_str(), e)).collect()
+}
+
+fn label(exam: &CareerExam) -> &str {
+    if exam.name.is_empty() { &exam.id } else { &exam.name }
+}
+
+fn exam_ref(exam: &CareerExam) -> Value {
+    serde_json::json!({ "id": exam.id, "name": exam.name, "year": exam.year, "sem": exam.sem, "cfu": exam.cfu })
+}
+
+/// Prerequisites of `exam` that are not passed yet (unknown ids ignored).
+pub fn missing<'a>(career: &'a Career, exam: &'a CareerExam) -> Vec<&'a CareerExam> {
+    let exams = by_id(career);
+    exam.prerequisites.iter()
+        .filter_map(|id| exams.get(id.as_str()).copied())
+        .filter(|p| !p.is_passed())
+        .collect()
+}
+
+/// Cycles in the prerequisite graph, as exam ids in dependency order.
+/// There is one for each back edge of a depth-first search: the list is
+/// empty exactly when the graph is acyclic, but a cycle sharing its exams
+/// with one already found may be left out (with A → {B, C}, B → C and
+/// C → A only A → B → C is listed, not A → C).  `check` and `report` test
+/// single edges and exams with `path` instead.
+pub fn cycles(career: &Career) -> Vec<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark { Visiting, Done }
+
+    fn visit<'a>(
+        id: &'a str,
+        exams: &HashMap<&'a str, &'a CareerExam>,
+        marks: &mut HashMap<&'a str, Mark>,
+        path: &mut Vec<&'a str>,
+        found: &mut Vec<Vec<String>>,
+    ) {
+        match marks.get(id) {
+            Some(Mark::Done) => return,
+            Some(Mark::Visiting) => {
+                let start = path.iter().position(|p| *p == id).unwrap_or(0);
+                found.push(path[start..].iter().map(|s| s.to_string()).collect());
+                return;
+            }
+            None => {}
+        }
+        let Some(exam) = exams.get(id) else { return };
+        marks.insert(id, Mark::Visiting);
+        path.push(id);
+        for prereq in &exam.prerequisites {
+            visit(prereq.as_str(), exams, marks, path, found);
+        }
+        path.pop();
+        marks.insert(id, Mark::Done);
+    }
+
+    let exams = by_id(career);
+    let mut marks = HashMap::new();
+    let mut found = Vec::new();
+    for exam in &career.exams {
+        visit(exam.id.as_str(), &exams, &mut marks, &mut Vec::new(), &mut found);
+    }
+    found
+}
+
+/// Shortest chain of prerequisites leading from `from` to `to`, both
+/// included; `None` when `to` can't be reached.
+fn path<'a>(exams: &HashMap<&'a str, &'a CareerExam>, from: &'a str, to: &str) -> Option<Vec<&'a str>> {
+    let mut parent: HashMap<&str, &str> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            let mut chain = vec![id];
+            while let Some(prev) = parent.get(chain.last().unwrap()) {
+                chain.push(prev);
+            }
+            chain.reverse();
+            return Some(chain);
+        }
+        let Some(exam) = exams.get(id) else { continue };
+        for prereq in &exam.prerequisites {
+            if seen.insert(prereq.as_str()) {
+                parent.insert(prereq.as_str(), id);
+                queue.push_back(prereq.as_str());
+            }
+        }
+    }
+    None
+}
+
+fn describe(exams: &HashMap<&str, &CareerExam>, cycle: &[&str]) -> String {
+    let names: Vec<&str> = cycle.iter()
+        .map(|id| exams.get(id).map(|e| label(e)).unwrap_or(id))
+        .collect();
+    format!("Propedeuticità circolari: {} → {}", names.join(" → "), names[0])
+}
+
+/// Rejects `after` if a prerequisite it adds (one `before` did not have)
+/// lies on a cycle, i.e. the prerequisite leads back to the exam.  Cycles
+/// made only of edges already saved are returned as warnings, so a career
+/// saved with one can still be edited and the cycle removed.
+pub fn check(before: &Career, after: &Career) -> Result<Vec<String>, String> {
+    let saved: HashSet<(&str, &str)> = before.exams.iter()
+        .flat_map(|e| e.prerequisites.iter().map(move |p| (e.id.as_str(), p.as_str())))
+        .collect();
+    let exams = by_id(after);
+    let mut new: Vec<Vec<&str>> = Vec::new();
+    for exam in &after.exams {
+        for prereq in &exam.prerequisites {
+            let edge = (exam.id.as_str(), prereq.as_str());
+            let reported = new.iter().any(|cycle| {
+                (0..cycle.len()).any(|i| (cycle[i], cycle[(i + 1) % cycle.len()]) == edge)
+            });
+            if saved.contains(&edge) || reported {
+                continue;
+            }
+            if let Some(back) = path(&exams, prereq.as_str(), exam.id.as_str()) {
+                let mut cycle = vec![exam.id.as_str()];
+                cycle.extend(&back[..back.len() - 1]);
+                new.push(cycle);
+            }
+        }
+    }
+    if !new.is_empty() {
+        return Err(new.iter().map(|c| describe(&exams, c)).collect::<Vec<_>>().join("\n"));
+    }
+    Ok(cycles(after).iter()
+        .map(|c| describe(&exams, &c.iter().map(String::as_str).collect::<Vec<_>>()))
+        .collect())
+}
+
+/// Full picture for `get_prerequisites`: cycles, exams that can't be taken
+/// yet, exams passed without their prerequisites and the exams that can be
+/// taken next, ordered by year and semester.
+pub fn report(career: &Career) -> Value {
+    let exams = by_id(career);
+    let cycles = cycles(career);
+    let in_cycle: HashSet<&str> = career.exams.iter()
+        .filter(|e| e.prerequisites.iter().any(|p| path(&exams, p, &e.id).is_some()))
+        .map(|e| e.id.as_str())
+        .collect();
+
+    let mut blocked = Vec::new();
+    let mut eligible: Vec<&CareerExam> = Vec::new();
+    let mut passed_without = Vec::new();
+    for exam in &career.exams {
+        let missing = missing(career, exam);
+        let missing_refs: Vec<Value> = missing.iter().map(|p| exam_ref(p)).collect();
+        if exam.is_passed() {
+            if !missing.is_empty() {
+                let mut entry = exam_ref(exam);
+                entry["missing"] = Value::Array(missing_refs);
+                passed_without.push(entry);
+            }
+        } else if missing.is_empty() && !in_cycle.contains(exam.id.as_str()) {
+            eligible.push(exam);
+        } else {
+            let mut entry = exam_ref(exam);
+            entry["missing"] = Value::Array(missing_refs);
+            blocked.push(entry);
+        }
+    }
+    eligible.sort_by(|a, b| (a.year, a.sem, &a.name).cmp(&(b.year, b.sem, &b.name)));
+
+    let unknown: Vec<Value> = career.exams.iter()
+        .flat_map(|e| e.prerequisites.iter()
+            .filter(|p| !exams.contains_key(p.as_str()))
+            .map(move |p| serde_json::json!({ "id": e.id, "name": e.name, "prerequisite": p })))
+        .collect();
+
+    serde_json::json!({
+        "cycles": cycles.iter().map(|c| c.iter()
+            .map(|id| exams.get(id.as_str()).map(|e| exam_ref(e)).unwrap_or_else(|| Value::from(id.as_str())))
+            .collect::<Vec<_>>()).collect::<Vec<_>>(),
+        "blocked": blocked,
+        "eligible": eligible.into_iter().map(exam_ref).collect::<Vec<_>>(),
+        "passedWithoutPrerequisites": passed_without,
+        "unknown": unknown,
+    })
+}
+
+/// What a save changes: exams that became takeable because their last
+/// missing prerequisite was passed, and warnings for exams newly passed
+/// with prerequisites still missing.
+pub fn on_save(before: &Career, after: &Career) -> (Vec<Value>, Vec<String>) {
+    let was_passed: HashSet<&str> = before.exams.iter()
+        .filter(|e| e.is_passed())
+        .map(|e| e.id.as_str())
+        .collect();
+    let newly_passed: HashSet<&str> = after.exams.iter()
+        .filter(|e| e.is_passed() && !was_passed.contains(e.id.as_str()))
+        .map(|e| e.id.as_str())
+        .collect();
+
+    let unlocked = after.exams.iter()
+        .filter(|e| !e.is_passed())
+        .filter(|e| e.prerequisites.iter().any(|p| newly_passed.contains(p.as_str())))
+        .filter(|e| missing(after, e).is_empty())
+        .map(exam_ref)
+        .collect();
+    let warnings = after.exams.iter()
+        .filter(|e| newly_passed.contains(e.id.as_str()))
+        .filter_map(|e| {
+            let missing = missing(after, e);
+            (!missing.is_empty()).then(|| format!(
+                "{} superato senza le propedeuticità: {}",
+                label(e),
+                missing.iter().map(|p| label(p)).collect::<Vec<_>>().join(", "),
+            ))
+        })
+        .collect();
+    (unlocked, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A career whose exams have the given prerequisites, named by id.
+    fn career(graph: &[(&str, &[&str])]) -> Career {
+        let exams: Vec<Value> = graph.iter()
+            .map(|(id, prereqs)| serde_json::json!({ "id": id, "name": id.to_uppercase(), "cfu": 6, "prerequisites": prereqs }))
+            .collect();
+        Career::from_value(&serde_json::json!({ "exams": exams })).unwrap()
+    }
+
+    #[test]
+    fn added_prerequisite_closing_a_cycle_is_rejected() {
+        let before = career(&[("a", &["b"]), ("b", &[]), ("c", &["a"])]);
+        let after = career(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        assert_eq!(check(&before, &after), Err("Propedeuticità circolari: B → C → A → B".to_string()));
+        assert_eq!(check(&before, &before), Ok(vec![]));
+    }
+
+    #[test]
+    fn cycle_missed_by_the_search_is_still_rejected() {
+        // The depth-first search only lists A → B → C, the edge A → C
+        // closes A → C → A as well
+        let before = career(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"])]);
+        let after = career(&[("a", &["b", "c"]), ("b", &["c"]), ("c", &["a"])]);
+        assert_eq!(cycles(&after), vec![vec!["a".to_string(), "b".to_string(), "c".to_string()]]);
+        assert_eq!(check(&before, &after), Err("Propedeuticità circolari: A → C → A".to_string()));
+    }
+
+    #[test]
+    fn removing_a_prerequisite_from_an_existing_cycle_is_allowed() {
+        let before = career(&[("a", &["b", "c"]), ("b", &["c"]), ("c", &["a"])]);
+        let after = career(&[("a", &["c"]), ("b", &["c"]), ("c", &["a"])]);
+        assert_eq!(check(&before, &after), Ok(vec!["Propedeuticità circolari: A → C → A".to_string()]));
+    }
+
+    #[test]
+    fn self_loop_is_a_cycle() {
+        let before = career(&[("a", &[])]);
+        let after = career(&[("a", &["a"])]);
+        assert_eq!(cycles(&after), vec![vec!["a".to_string()]]);
+        assert_eq!(check(&before, &after), Err("Propedeuticità circolari: A → A".to_string()));
+        assert_eq!(check(&after, &after), Ok(vec!["Propedeuticità circolari: A → A".to_string()]));
+    }
+
+    #[test]
+    fn existing_cycle_is_a_warning_while_other_edits_are_saved() {
+        let before = career(&[("a", &["b"]), ("b", &["a"]), ("c", &[]), ("d", &[])]);
+        let after = career(&[("a", &["b"]), ("b", &["a"]), ("c", &["d"]), ("d", &["x"])]);
+        assert_eq!(check(&before, &after), Ok(vec!["Propedeuticità circolari: A → B → A".to_string()]));
+    }
+
+    #[test]
+    fn report_blocks_every_exam_on_a_cycle() {
+        // C is on A → C → B → A, which the search does not list, and its
+        // only prerequisite is passed
+        let mut career = career(&[("a", &["b", "c"]), ("b", &["a"]), ("c", &["b"]), ("d", &[])]);
+        career.exams[1].status = crate::career::ExamStatus::Passed;
+        assert_eq!(cycles(&career), vec![vec!["a".to_string(), "b".to_string()]]);
+        let report = report(&career);
+        let blocked: Vec<&str> = report["blocked"].as_array().unwrap().iter()
+            .map(|e| e["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(blocked, ["a", "c"]);
+        assert_eq!(report["eligible"][0]["id"], "d");
+    }
+}
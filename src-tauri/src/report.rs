@@ -0,0 +1,538 @@
+// ===== Career Report =====
+//
+// `export_career_report` renders the career for scholarship and Erasmus
+// applications: summary (CFU, averages, lodi, base di laurea), CFU by year,
+// passed exams with grades and upcoming exams.  `Report::build` collects the
+// figures once, from `stats::career_stats` so they match the Career page;
+// `to_html` writes a self-contained page (inline CSS, no scripts or external
+// assets) and `to_pdf` an A4 document set in the standard Helvetica font,
+// which every PDF reader ships, so nothing has to be embedded.
+
+use chrono::NaiveDate;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use serde_json::Value;
+
+use crate::career::{Activity, Career, Grade};
+
+pub struct PassedRow {
+    pub name: String,
+    pub year: u32,
+    pub cfu: u32,
+    pub grade: String,
+    pub date: String,
+}
+
+pub struct UpcomingRow {
+    pub name: String,
+    pub year: u32,
+    pub cfu: u32,
+    pub date: String,
+}
+
+pub struct YearRow {
+    pub year: u32,
+    pub passed: u64,
+    pub exams: u64,
+    pub cfu_earned: u64,
+    pub cfu_planned: u64,
+}
+
+pub struct Report {
+    pub course: String,
+    pub university: String,
+    pub kind: String,
+    pub generated: NaiveDate,
+    /// (label, value) pairs, already formatted
+    pub summary: Vec<(String, String)>,
+    pub by_year: Vec<YearRow>,
+    pub passed: Vec<PassedRow>,
+    pub upcoming: Vec<UpcomingRow>,
+}
+
+fn kind_label(kind: &str) -> String {
+    match kind {
+        "triennale" => "Laurea triennale".to_string(),
+        "magistrale" => "Laurea magistrale".to_string(),
+        "ciclo-unico-5" => "Laurea magistrale a ciclo unico (5 anni)".to_string(),
+        "ciclo-unico-6" => "Laurea magistrale a ciclo unico (6 anni)".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn grade_label(grade: Option<Grade>) -> String {
+    match grade {
+        Some(Grade::Numeric(v)) => format!("{}/30", v),
+        Some(Grade::Lode) => "30 e lode".to_string(),
+        Some(Grade::Idoneo) => "Idoneo".to_string(),
+        Some(Grade::Convalidato) => "Convalidato".to_string(),
+        None => "Superato".to_string(),
+    }
+}
+
+/// YYYY-MM-DD as DD/MM/YYYY; anything else unchanged.
+fn date_it(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.format("%d/%m/%Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Decimal with comma, "—" when missing.
+fn decimal(value: &Value) -> String {
+    value.as_f64().map(|v| format!("{:.2}", v).replace('.', ",")).unwrap_or_else(|| "—".to_string())
+}
+
+impl Report {
+    /// `stats` is the output of `stats::career_stats` for the same career.
+    pub fn build(career: &Career, stats: &Value, today: NaiveDate) -> Report {
+        let num = |key: &str| stats[key].as_u64().unwrap_or(0);
+        let mut summary = vec![
+            ("CFU acquisiti".to_string(), format!("{} / {}", num("cfuEarned"), num("cfuTotal"))),
+            ("Esami superati".to_string(), format!("{} / {}", num("examsPassed"), num("examsTotal"))),
+            ("Media ponderata".to_string(), decimal(&stats["weightedAverage"])),
+            ("Media aritmetica".to_string(), decimal(&stats["arithmeticAverage"])),
+            ("Lodi".to_string(), num("lodi").to_string()),
+            ("Base di laurea".to_string(), decimal(&stats["baseScore"])),
+        ];
+        if num("cfuExtra") > 0 {
+            summary.push(("CFU in sovrannumero".to_string(), num("cfuExtra").to_string()));
+        }
+        if let Some(thesis) = career.exams.iter().find(|e| e.activity == Activity::Thesis) {
+            let state = if thesis.is_passed() { "superata" } else { "da sostenere" };
+            summary.push(("Prova finale".to_string(), format!("{} ({} CFU)", state, thesis.cfu)));
+        }
+
+        let by_year = stats["byYear"].as_array().map(Vec::as_slice).unwrap_or(&[]).iter()
+            .map(|y| YearRow {
+                year: y["year"].as_u64().unwrap_or(0) as u32,
+                passed: y["passed"].as_u64().unwrap_or(0),
+                exams: y["exams"].as_u64().unwrap_or(0),
+                cfu_earned: y["cfuEarned"].as_u64().unwrap_or(0),
+                cfu_planned: y["cfuPlanned"].as_u64().unwrap_or(0),
+            })
+            .collect();
+
+        let mut passed: Vec<_> = career.exams.iter().filter(|e| e.is_passed()).collect();
+        passed.sort_by(|a, b| (&a.pass_date, a.year, &a.name).cmp(&(&b.pass_date, b.year, &b.name)));
+        let today_str = today.format("%Y-%m-%d").to_string();
+        let mut upcoming: Vec<(String, UpcomingRow)> = career.exams.iter()
+            .filter(|e| !e.is_passed())
+            .filter_map(|e| {
+                let date = e.planned_date().filter(|d| *d >= today_str.as_str())?.to_string();
+                Some((date.clone(), UpcomingRow { name: e.name.clone(), year: e.year, cfu: e.cfu, date: date_it(&date) }))
+            })
+            .collect();
+        upcoming.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Report {
+            course: if career.course.is_empty() { "Piano carriera".to_string() } else { career.course.clone() },
+            university: career.university.clone(),
+            kind: kind_label(&career.kind),
+            generated: today,
+            summary,
+            by_year,
+            passed: passed.into_iter().map(|e| PassedRow {
+                name: e.name.clone(),
+                year: e.year,
+                cfu: e.cfu,
+                grade: grade_label(e.grade),
+                date: date_it(&e.pass_date),
+            }).collect(),
+            upcoming: upcoming.into_iter().map(|(_, row)| row).collect(),
+        }
+    }
+
+    fn subtitle(&self) -> String {
+        [self.university.as_str(), self.kind.as_str()].iter()
+            .filter(|s| !s.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
+    // ── HTML ─────────────────────────────────────────────
+
+    pub fn to_html(&self) -> String {
+        fn esc(s: &str) -> String {
+            s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+        }
+        fn table(head: &[&str], rows: Vec<Vec<String>>) -> String {
+            let th: String = head.iter().map(|h| format!("<th>{}</th>", esc(h))).collect();
+            let tr: String = rows.iter()
+                .map(|r| format!("<tr>{}</tr>", r.iter().map(|c| format!("<td>{}</td>", esc(c))).collect::<String>()))
+                .collect();
+            format!("<table><thead><tr>{}</tr></thead><tbody>{}</tbody></table>\n", th, tr)
+        }
+
+        let mut body = String::new();
+        body.push_str(&format!("<h1>{}</h1>\n", esc(&self.course)));
+        let subtitle = self.subtitle();
+        if !subtitle.is_empty() {
+            body.push_str(&format!("<p class=\"sub\">{}</p>\n", esc(&subtitle)));
+        }
+        body.push_str(&format!("<p class=\"muted\">Generato il {}</p>\n", self.generated.format("%d/%m/%Y")));
+
+        body.push_str("<h2>Riepilogo</h2>\n<dl>");
+        for (label, value) in &self.summary {
+            body.push_str(&format!("<div><dt>{}</dt><dd>{}</dd></div>", esc(label), esc(value)));
+        }
+        body.push_str("</dl>\n");
+
+        if !self.by_year.is_empty() {
+            body.push_str("<h2>CFU per anno</h2>\n");
+            body.push_str(&table(&["Anno", "Esami superati", "CFU"], self.by_year.iter().map(|y| vec![
+                format!("{}° anno", y.year),
+                format!("{} / {}", y.passed, y.exams),
+                format!("{} / {}", y.cfu_earned, y.cfu_planned),
+            ]).collect()));
+        }
+
+        body.push_str("<h2>Esami superati</h2>\n");
+        if self.passed.is_empty() {
+            body.push_str("<p class=\"muted\">Nessun esame superato.</p>\n");
+        } else {
+            body.push_str(&table(&["Esame", "Anno", "CFU", "Voto", "Data"], self.passed.iter().map(|e| vec![
+                e.name.clone(), e.year.to_string(), e.cfu.to_string(), e.grade.clone(), e.date.clone(),
+            ]).collect()));
+        }
+
+        if !self.upcoming.is_empty() {
+            body.push_str("<h2>Prossimi esami</h2>\n");
+            body.push_str(&table(&["Esame", "Anno", "CFU", "Data"], self.upcoming.iter().map(|e| vec![
+                e.name.clone(), e.year.to_string(), e.cfu.to_string(), e.date.clone(),
+            ]).collect()));
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"it\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{css}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+            title = esc(&format!("Carriera — {}", self.course)),
+            css = HTML_CSS,
+            body = body,
+        )
+    }
+
+    // ── PDF ──────────────────────────────────────────────
+
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let mut doc = PdfLayout::new();
+        doc.line(&self.course, 18.0, Font::Bold, 24.0);
+        let subtitle = self.subtitle();
+        if !subtitle.is_empty() {
+            doc.line(&subtitle, 11.0, Font::Regular, 16.0);
+        }
+        doc.gray(&format!("Generato il {}", self.generated.format("%d/%m/%Y")), 9.0, 20.0);
+
+        doc.heading("Riepilogo");
+        for pair in self.summary.chunks(2) {
+            doc.need(16.0);
+            for (i, (label, value)) in pair.iter().enumerate() {
+                let x = MARGIN + i as f32 * 250.0;
+                doc.text(x, 9.5, Font::Regular, label, GRAY);
+                doc.text_right(x + 230.0, 9.5, Font::Bold, value, BLACK);
+            }
+            doc.y -= 16.0;
+        }
+
+        if !self.by_year.is_empty() {
+            doc.heading("CFU per anno");
+            doc.table(&YEAR_COLUMNS, self.by_year.iter().map(|y| vec![
+                format!("{}° anno", y.year),
+                format!("{} / {}", y.passed, y.exams),
+                format!("{} / {}", y.cfu_earned, y.cfu_planned),
+            ]).collect());
+        }
+
+        doc.heading("Esami superati");
+        if self.passed.is_empty() {
+            doc.gray("Nessun esame superato.", 9.5, 16.0);
+        } else {
+            doc.table(&EXAM_COLUMNS, self.passed.iter().map(|e| vec![
+                e.name.clone(), e.year.to_string(), e.cfu.to_string(), e.grade.clone(), e.date.clone(),
+            ]).collect());
+        }
+
+        if !self.upcoming.is_empty() {
+            doc.heading("Prossimi esami");
+            doc.table(&UPCOMING_COLUMNS, self.upcoming.iter().map(|e| vec![
+                e.name.clone(), e.year.to_string(), e.cfu.to_string(), e.date.clone(),
+            ]).collect());
+        }
+
+        doc.finish(&format!("Carriera — {}", self.course))
+    }
+}
+
+const HTML_CSS: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #1a1a1a; max-width: 820px; margin: 40px auto; padding: 0 24px; }
+h1 { font-size: 26px; margin: 0 0 4px; }
+h2 { font-size: 16px; margin: 28px 0 10px; padding-bottom: 4px; border-bottom: 1px solid #ddd; }
+.sub { margin: 0; font-size: 15px; }
+.muted { color: #777; font-size: 13px; }
+dl { display: grid; grid-template-columns: 1fr 1fr; gap: 6px 32px; margin: 0; }
+dl div { display: flex; justify-content: space-between; font-size: 14px; }
+dt { color: #666; }
+dd { margin: 0; font-weight: 600; }
+table { width: 100%; border-collapse: collapse; font-size: 13px; }
+th { text-align: left; background: #f2f2f2; font-weight: 600; }
+th, td { padding: 6px 8px; border-bottom: 1px solid #e6e6e6; }
+td:not(:first-child), th:not(:first-child) { text-align: right; white-space: nowrap; }
+tbody tr:nth-child(even) { background: #fafafa; }
+@media print { body { margin: 0; max-width: none; } h2 { break-after: avoid; } tr { break-inside: avoid; } }
+";
+
+// ── PDF layout ───────────────────────────────────────────
+
+const PAGE_W: f32 = 595.0;
+const PAGE_H: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const ROW_H: f32 = 16.0;
+const BLACK: f32 = 0.1;
+const GRAY: f32 = 0.45;
+
+#[derive(Clone, Copy)]
+enum Font { Regular, Bold }
+
+impl Font {
+    fn name(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+        }
+    }
+}
+
+/// Table column: header, x of the left edge (first column) or of the right
+/// edge (the others, right-aligned).
+struct Column(&'static str, f32);
+
+const EXAM_COLUMNS: [Column; 5] = [
+    Column("Esame", MARGIN), Column("Anno", 360.0), Column("CFU", 400.0),
+    Column("Voto", 475.0), Column("Data", PAGE_W - MARGIN),
+];
+const UPCOMING_COLUMNS: [Column; 4] = [
+    Column("Esame", MARGIN), Column("Anno", 400.0), Column("CFU", 450.0), Column("Data", PAGE_W - MARGIN),
+];
+const YEAR_COLUMNS: [Column; 3] = [
+    Column("Anno", MARGIN), Column("Esami superati", 400.0), Column("CFU", PAGE_W - MARGIN),
+];
+
+/// Helvetica advance widths (1/1000 em) for ASCII 32–126, from the AFM.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Text in WinAnsiEncoding, the encoding the standard fonts are set with;
+/// characters outside it become '?'.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars().map(|c| match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        _ => b'?',
+    }).collect()
+}
+
+/// Approximate width in points; accented letters take their base letter's
+/// width, bold is about 5% wider.
+fn text_width(text: &str, size: f32, font: Font) -> f32 {
+    let units: u32 = text.chars().map(|c| {
+        let base = match c {
+            'à' | 'á' => 'a', 'è' | 'é' => 'e', 'ì' | 'í' => 'i', 'ò' | 'ó' => 'o', 'ù' | 'ú' => 'u',
+            'À' | 'Á' => 'A', 'È' | 'É' => 'E', 'Ì' | 'Í' => 'I', 'Ò' | 'Ó' => 'O', 'Ù' | 'Ú' => 'U',
+            c => c,
+        };
+        match base {
+            ' '..='~' => u32::from(HELVETICA_WIDTHS[base as usize - 32]),
+            '—' | '…' | '€' => 1000,
+            _ => 556,
+        }
+    }).sum();
+    let bold = match font { Font::Bold => 1.05, Font::Regular => 1.0 };
+    units as f32 / 1000.0 * size * bold
+}
+
+/// `text` cut with an ellipsis to fit `width`.
+fn fit(text: &str, width: f32, size: f32, font: Font) -> String {
+    if text_width(text, size, font) <= width {
+        return text.to_string();
+    }
+    let mut out: String = text.to_string();
+    while !out.is_empty() && text_width(&format!("{}…", out), size, font) > width {
+        out.pop();
+    }
+    format!("{}…", out.trim_end())
+}
+
+/// A4 pages written top to bottom; `y` is the baseline of the next line.
+struct PdfLayout {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl PdfLayout {
+    fn new() -> PdfLayout {
+        let mut layout = PdfLayout { pages: Vec::new(), y: 0.0 };
+        layout.new_page();
+        layout
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.y = PAGE_H - MARGIN;
+    }
+
+    /// Starts a new page unless `height` fits above the footer.
+    fn need(&mut self, height: f32) {
+        if self.y - height < MARGIN + 20.0 {
+            self.new_page();
+        }
+    }
+
+    fn page(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("at least one page")
+    }
+
+    fn text(&mut self, x: f32, size: f32, font: Font, text: &str, gray: f32) {
+        let y = self.y;
+        let page = self.page();
+        page.set_fill_gray(gray);
+        page.begin_text();
+        page.set_font(font.name(), size);
+        page.next_line(x, y);
+        page.show(Str(&win_ansi(text)));
+        page.end_text();
+    }
+
+    fn text_right(&mut self, right: f32, size: f32, font: Font, text: &str, gray: f32) {
+        self.text(right - text_width(text, size, font), size, font, text, gray);
+    }
+
+    fn line(&mut self, text: &str, size: f32, font: Font, advance: f32) {
+        self.need(advance);
+        self.text(MARGIN, size, font, text, BLACK);
+        self.y -= advance;
+    }
+
+    fn gray(&mut self, text: &str, size: f32, advance: f32) {
+        self.need(advance);
+        self.text(MARGIN, size, Font::Regular, text, GRAY);
+        self.y -= advance;
+    }
+
+    fn heading(&mut self, title: &str) {
+        // Keep the heading with the header row and a first line below it
+        self.need(14.0 + 3.0 * ROW_H);
+        self.y -= 14.0;
+        self.text(MARGIN, 13.0, Font::Bold, title, BLACK);
+        let y = self.y - 5.0;
+        let page = self.page();
+        page.set_stroke_gray(0.8);
+        page.set_line_width(0.6);
+        page.move_to(MARGIN, y);
+        page.line_to(PAGE_W - MARGIN, y);
+        page.stroke();
+        self.y -= 22.0;
+    }
+
+    fn table_header(&mut self, columns: &[Column]) {
+        let y = self.y;
+        let page = self.page();
+        page.set_fill_gray(0.93);
+        page.rect(MARGIN - 4.0, y - 5.0, PAGE_W - 2.0 * MARGIN + 8.0, ROW_H);
+        page.fill_nonzero();
+        for (i, Column(title, x)) in columns.iter().enumerate() {
+            if i == 0 {
+                self.text(*x, 9.0, Font::Bold, title, BLACK);
+            } else {
+                self.text_right(*x, 9.0, Font::Bold, title, BLACK);
+            }
+        }
+        self.y -= ROW_H;
+    }
+
+    /// Rows under a header that is repeated on every page the table spans.
+    fn table(&mut self, columns: &[Column], rows: Vec<Vec<String>>) {
+        self.table_header(columns);
+        // The first column runs up to the second one's widest content
+        let first_width = columns.get(1).map(|c| c.1 - 45.0 - columns[0].1).unwrap_or(PAGE_W);
+        for (n, row) in rows.iter().enumerate() {
+            if self.y - ROW_H < MARGIN + 20.0 {
+                self.new_page();
+                self.table_header(columns);
+            }
+            if n % 2 == 1 {
+                let y = self.y;
+                let page = self.page();
+                page.set_fill_gray(0.975);
+                page.rect(MARGIN - 4.0, y - 5.0, PAGE_W - 2.0 * MARGIN + 8.0, ROW_H);
+                page.fill_nonzero();
+            }
+            for (i, (cell, Column(_, x))) in row.iter().zip(columns).enumerate() {
+                if i == 0 {
+                    let cell = fit(cell, first_width, 9.5, Font::Regular);
+                    self.text(*x, 9.5, Font::Regular, &cell, BLACK);
+                } else {
+                    self.text_right(*x, 9.5, Font::Regular, cell, BLACK);
+                }
+            }
+            self.y -= ROW_H;
+        }
+        self.y -= 6.0;
+    }
+
+    /// Adds the page footers and writes the document.
+    fn finish(mut self, title: &str) -> Vec<u8> {
+        let total = self.pages.len();
+        for i in 0..total {
+            let footer = format!("StudyPlan — Pagina {} di {}", i + 1, total);
+            let x = PAGE_W - MARGIN - text_width(&footer, 8.0, Font::Regular);
+            let page = &mut self.pages[i];
+            page.set_fill_gray(GRAY);
+            page.begin_text();
+            page.set_font(Font::Regular.name(), 8.0);
+            page.next_line(x, MARGIN - 20.0);
+            page.show(Str(&win_ansi(&footer)));
+            page.end_text();
+        }
+
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let page_ids: Vec<Ref> = (0..total).map(|i| Ref::new(6 + 2 * i as i32)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id).kids(page_ids.iter().copied()).count(total as i32);
+        for (font_id, base) in [(regular_id, Name(b"Helvetica")), (bold_id, Name(b"Helvetica-Bold"))] {
+            pdf.type1_font(font_id).base_font(base).encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+        pdf.document_info(info_id).title(TextStr(title)).creator(TextStr("StudyPlan"));
+        for (page_id, content) in page_ids.iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_W, PAGE_H));
+            page.parent(tree_id);
+            page.contents(content_id);
+            page.resources().fonts()
+                .pair(Font::Regular.name(), regular_id)
+                .pair(Font::Bold.name(), bold_id);
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+        pdf.finish()
+    }
+}
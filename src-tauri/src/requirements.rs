@@ -0,0 +1,250 @@
+// ===== Degree Requirements =====
+//
+// How earned CFU count toward the degree.  A career is split into:
+//
+//   core           exams without a `group` (compulsory exams, tirocinio,
+//                  prova finale); required CFU = totalCfu − Σ group CFU
+//   electiveGroups "choose `cfu` CFU from these exams" (kind "elective") or
+//                  free-choice credits (kind "free")
+//
+// CFU passed in a group beyond what it requires are sovrannumerari: they are
+// reported as extra and do not count toward the degree, so `cfuEarned`
+// never exceeds what the plan can use.  Averages still include them (see
+// `stats`).
+
+use serde_json::Value;
+
+use crate::career::{Activity, Career, CareerExam, ElectiveGroup, GroupKind};
+
+fn exam_ref(exam: &CareerExam) -> Value {
+    serde_json::json!({ "id": exam.id, "name": exam.name, "cfu": exam.cfu, "year": exam.year, "sem": exam.sem })
+}
+
+fn in_group<'a>(career: &'a Career, group: &'a ElectiveGroup) -> impl Iterator<Item = &'a CareerExam> {
+    career.exams.iter().filter(move |e| e.group.as_deref() == Some(group.id.as_str()))
+}
+
+/// (CFU counted toward the degree, passed CFU beyond the group limits).
+pub fn counted_cfu(career: &Career) -> (u32, u32) {
+    counted_cfu_if(career, CareerExam::is_passed)
+}
+
+/// `counted_cfu` with `passed` telling which exams are passed (the what-if
+/// simulation passes the hypothetical ones too).
+pub fn counted_cfu_if(career: &Career, passed: impl Fn(&CareerExam) -> bool) -> (u32, u32) {
+    let core: u32 = career.exams.iter()
+        .filter(|e| passed(e) && e.group.is_none())
+        .map(|e| e.cfu)
+        .sum();
+    let (mut counted, mut extra) = (core, 0);
+    for group in &career.elective_groups {
+        let group_passed: u32 = in_group(career, group).filter(|e| passed(e)).map(|e| e.cfu).sum();
+        counted += group_passed.min(group.cfu);
+        extra += group_passed.saturating_sub(group.cfu);
+    }
+    // Exams pointing at a group that does not exist count as core
+    let orphans: u32 = career.exams.iter()
+        .filter(|e| passed(e))
+        .filter(|e| e.group.as_deref().is_some_and(|g| !career.elective_groups.iter().any(|x| x.id == g)))
+        .map(|e| e.cfu)
+        .sum();
+    (counted + orphans, extra)
+}
+
+/// CFU still needed to complete the degree: an `exam` of the plan, or
+/// credits a group requires beyond the options it lists (`exam` `None`).
+#[derive(Debug, Clone, Copy)]
+pub struct Needed<'a> {
+    pub exam: Option<&'a CareerExam>,
+    pub cfu: u32,
+}
+
+/// What is left when the exams for which `passed` holds are passed: the
+/// pending core exams, and for each group its pending options in plan
+/// order until the group's CFU are covered (the last one counts only for
+/// what the group still needs; options beyond are not needed).
+pub fn still_needed(career: &Career, passed: impl Fn(&CareerExam) -> bool) -> Vec<Needed<'_>> {
+    let known = |e: &CareerExam| e.group.as_deref().is_some_and(|g| career.elective_groups.iter().any(|x| x.id == g));
+    let mut needed: Vec<Needed> = career.exams.iter()
+        .filter(|e| !passed(e) && !known(e))
+        .map(|e| Needed { exam: Some(e), cfu: e.cfu })
+        .collect();
+    for group in &career.elective_groups {
+        let group_passed: u32 = in_group(career, group).filter(|e| passed(e)).map(|e| e.cfu).sum();
+        let mut missing = group.cfu.saturating_sub(group_passed);
+        for exam in in_group(career, group).filter(|e| !passed(e)) {
+            if missing == 0 { break; }
+            let cfu = exam.cfu.min(missing);
+            needed.push(Needed { exam: Some(exam), cfu });
+            missing -= cfu;
+        }
+        if missing > 0 {
+            needed.push(Needed { exam: None, cfu: missing });
+        }
+    }
+    needed
+}
+
+/// Requirements report for `get_requirements`: per group and for the core,
+/// CFU required, earned and remaining, the exams still available, plus plan
+/// issues that do not block saving (e.g. options that cannot cover a group).
+pub fn report(career: &Career) -> Value {
+    let mut issues = Vec::new();
+    let groups_cfu: u32 = career.elective_groups.iter().map(|g| g.cfu).sum();
+
+    let groups: Vec<Value> = career.elective_groups.iter().map(|group| {
+        let exams: Vec<&CareerExam> = in_group(career, group).collect();
+        let passed: u32 = exams.iter().filter(|e| e.is_passed()).map(|e| e.cfu).sum();
+        let available: u32 = exams.iter().filter(|e| !e.is_passed()).map(|e| e.cfu).sum();
+        let remaining = group.cfu.saturating_sub(passed);
+        if group.kind == GroupKind::Elective && passed + available < group.cfu {
+            issues.push(format!(
+                "{}: le opzioni coprono {} CFU su {} richiesti",
+                group.name, passed + available, group.cfu,
+            ));
+        }
+        serde_json::json!({
+            "id": group.id,
+            "name": group.name,
+            "kind": group.kind,
+            "cfuRequired": group.cfu,
+            "cfuEarned": passed.min(group.cfu),
+            "cfuExtra": passed.saturating_sub(group.cfu),
+            "cfuRemaining": remaining,
+            "satisfied": remaining == 0,
+            "passed": exams.iter().filter(|e| e.is_passed()).map(|e| exam_ref(e)).collect::<Vec<_>>(),
+            "options": exams.iter().filter(|e| !e.is_passed()).map(|e| exam_ref(e)).collect::<Vec<_>>(),
+        })
+    }).collect();
+
+    // Core: everything outside the groups
+    let core: Vec<&CareerExam> = career.exams.iter()
+        .filter(|e| !e.group.as_deref().is_some_and(|g| career.elective_groups.iter().any(|x| x.id == g)))
+        .collect();
+    let core_planned: u32 = core.iter().map(|e| e.cfu).sum();
+    let core_earned: u32 = core.iter().filter(|e| e.is_passed()).map(|e| e.cfu).sum();
+    let core_required = if career.total_cfu > 0 { career.total_cfu.saturating_sub(groups_cfu) } else { core_planned };
+    if career.total_cfu > 0 {
+        if groups_cfu > career.total_cfu {
+            issues.push(format!("I gruppi a scelta richiedono {} CFU, più dei {} del corso", groups_cfu, career.total_cfu));
+        } else if core_planned != core_required {
+            issues.push(format!(
+                "Gli esami obbligatori valgono {} CFU, il piano ne prevede {} ({} totali − {} a scelta)",
+                core_planned, core_required, career.total_cfu, groups_cfu,
+            ));
+        }
+    }
+
+    let activity = |kind: Activity| -> Value {
+        match career.exams.iter().find(|e| e.activity == kind) {
+            Some(e) => serde_json::json!({ "id": e.id, "name": e.name, "cfu": e.cfu, "passed": e.is_passed() }),
+            None => Value::Null,
+        }
+    };
+
+    let (counted, extra) = counted_cfu(career);
+    let cfu_total = if career.total_cfu > 0 { career.total_cfu } else { core_planned + groups_cfu };
+    serde_json::json!({
+        "cfuTotal": cfu_total,
+        "cfuEarned": counted,
+        "cfuExtra": extra,
+        "cfuRemaining": cfu_total.saturating_sub(counted),
+        "core": {
+            "cfuRequired": core_required,
+            "cfuEarned": core_earned,
+            "cfuRemaining": core_required.saturating_sub(core_earned),
+            "remaining": core.iter().filter(|e| !e.is_passed()).map(|e| exam_ref(e)).collect::<Vec<_>>(),
+        },
+        "groups": groups,
+        "internship": activity(Activity::Internship),
+        "thesis": activity(Activity::Thesis),
+        "issues": issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 120 CFU: 96 core, 12 from a list of three options and 12 free.
+    fn career(passed: &[&str]) -> Career {
+        let exams: Vec<Value> = [
+            ("an", 12, None), ("fi", 9, None), ("al", 9, None), ("pf", 3, None),
+            ("o1", 6, Some("g1")), ("o2", 9, Some("g1")), ("o3", 6, Some("g1")),
+            ("sl", 6, Some("free")), ("x", 6, Some("gone")),
+        ].iter().map(|(id, cfu, group)| {
+            let mut exam = json!({ "id": id, "name": id.to_uppercase(), "cfu": cfu, "group": group });
+            if passed.contains(id) {
+                exam["status"] = json!("passed");
+                exam["grade"] = json!(27);
+            }
+            exam
+        }).collect();
+        Career::from_value(&json!({
+            "totalCfu": 120,
+            "electiveGroups": [
+                { "id": "g1", "name": "A scelta", "cfu": 12 },
+                { "id": "free", "name": "Scelta libera", "cfu": 12, "kind": "free" },
+            ],
+            "exams": exams,
+        })).unwrap()
+    }
+
+    fn needed(career: &Career) -> Vec<(Option<&str>, u32)> {
+        still_needed(career, CareerExam::is_passed).iter()
+            .map(|n| (n.exam.map(|e| e.id.as_str()), n.cfu))
+            .collect()
+    }
+
+    #[test]
+    fn group_cfu_beyond_the_requirement_are_extra() {
+        assert_eq!(counted_cfu(&career(&[])), (0, 0));
+        assert_eq!(counted_cfu(&career(&["an", "o1"])), (18, 0));
+        // 21 CFU passed in a 12 CFU group
+        assert_eq!(counted_cfu(&career(&["an", "o1", "o2", "o3"])), (24, 9));
+        let report = report(&career(&["o1", "o2", "o3"]));
+        assert_eq!(report["cfuEarned"], 12);
+        assert_eq!(report["cfuExtra"], 9);
+        assert_eq!(report["groups"][0]["cfuEarned"], 12);
+        assert_eq!(report["groups"][0]["cfuExtra"], 9);
+        assert_eq!(report["groups"][0]["satisfied"], true);
+    }
+
+    #[test]
+    fn free_choice_credits_and_unknown_groups() {
+        // The free-choice group caps like the others; an exam pointing at a
+        // group that does not exist counts as core
+        assert_eq!(counted_cfu(&career(&["sl", "x"])), (12, 0));
+        assert_eq!(counted_cfu_if(&career(&["sl"]), |e| e.is_passed() || e.id == "x"), (12, 0));
+        let report = report(&career(&["sl"]));
+        assert_eq!(report["groups"][1]["cfuRemaining"], 6);
+        assert_eq!(report["core"]["remaining"].as_array().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn still_needed_takes_group_options_in_plan_order() {
+        assert_eq!(needed(&career(&["an", "fi", "al"])), vec![
+            (Some("pf"), 3),
+            (Some("x"), 6),
+            // o1 and 6 of o2's 9 CFU cover the group; o3 is not needed
+            (Some("o1"), 6),
+            (Some("o2"), 6),
+            (Some("sl"), 6),
+            // Free-choice credits beyond the listed exams
+            (None, 6),
+        ]);
+        assert_eq!(needed(&career(&["an", "fi", "al", "pf", "x", "o2", "o3", "sl"])), vec![(None, 6)]);
+    }
+
+    #[test]
+    fn report_flags_options_that_cannot_cover_a_group() {
+        let mut career = career(&[]);
+        career.elective_groups[0].cfu = 24;
+        let report = report(&career);
+        assert_eq!(report["issues"], json!([
+            "A scelta: le opzioni coprono 21 CFU su 24 richiesti",
+            "Gli esami obbligatori valgono 39 CFU, il piano ne prevede 84 (120 totali − 36 a scelta)",
+        ]));
+    }
+}
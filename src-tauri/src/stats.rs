@@ -0,0 +1,529 @@
+// ===== Career Statistics =====
+//
+// Averages, base di laurea, CFU breakdown and graduation projection for
+// `get_career_stats` and the career widget.  Rules that differ between
+// universities live in career.json under `statsRules`:
+//
+//   "statsRules": {
+//     "baseFormula": "avg*110/30",   — base di laurea from the variables below
+//     "lodeValue": 30,               — weight of a 30 e lode in averages
+//     "idoneitaValue": null          — weight of an idoneità, null = excluded
+//   }
+//
+// Formula variables: avg (CFU-weighted average), arith (arithmetic average),
+// cfu (earned CFU), total (CFU of the degree), lodi (number of lodi).
+// Operators: + - * / and parentheses.
+//
+// CFU earned are counted as in `requirements`: sovrannumerari (group CFU
+// beyond the requirement) are left out.  The averages instead take every
+// graded exam passed, sovrannumerari included, as the transcript does;
+// which of a group's exams would drop out is up to each university.
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::career::{Activity, AttemptOutcome, Career, CareerExam, Grade};
+use crate::requirements;
+
+pub const DEFAULT_BASE_FORMULA: &str = "avg*110/30";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatsRules {
+    pub base_formula: String,
+    pub lode_value: f64,
+    pub idoneita_value: Option<f64>,
+}
+
+impl Default for StatsRules {
+    fn default() -> Self {
+        StatsRules {
+            base_formula: DEFAULT_BASE_FORMULA.to_string(),
+            lode_value: 30.0,
+            idoneita_value: None,
+        }
+    }
+}
+
+impl StatsRules {
+    /// Rules stored in the career, overridden field by field by `overrides`.
+    pub fn resolve(career: &Career, overrides: Option<&Value>) -> Result<StatsRules, String> {
+        let mut merged = career.extra.get("statsRules").cloned().unwrap_or_else(|| Value::Object(Default::default()));
+        if let (Some(obj), Some(Value::Object(extra))) = (merged.as_object_mut(), overrides) {
+            for (k, v) in extra {
+                obj.insert(k.clone(), v.clone());
+            }
+        }
+        serde_json::from_value(merged).map_err(|e| format!("Regole statistiche non valide: {}", e))
+    }
+
+    /// Rejects a base formula that does not evaluate.
+    pub fn check(&self) -> Result<(), String> {
+        let sample = [("avg", 27.0), ("arith", 27.0), ("cfu", 60.0), ("total", 180.0), ("lodi", 1.0)];
+        eval_formula(&self.base_formula, &sample).map(|_| ())
+    }
+
+    /// Weight of `grade` in averages; `None` when it does not count.
+    pub fn points(&self, grade: Grade) -> Option<f64> {
+        match grade {
+            Grade::Numeric(v) => Some(v as f64),
+            Grade::Lode => Some(self.lode_value),
+            Grade::Idoneo => self.idoneita_value,
+            Grade::Convalidato => None,
+        }
+    }
+}
+
+// ── Formula evaluation ────────────────────────────────────────────────────
+
+/// Evaluates `formula` with the given variables (recursive descent over
+/// + - * / and parentheses).
+pub fn eval_formula(formula: &str, vars: &[(&str, f64)]) -> Result<f64, String> {
+    let tokens: Vec<char> = formula.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut parser = FormulaParser { tokens: &tokens, pos: 0, vars };
+    let value = parser.expr()?;
+    if parser.pos < tokens.len() {
+        return Err(format!("Formula non valida: carattere inatteso '{}'", tokens[parser.pos]));
+    }
+    Ok(value)
+}
+
+struct FormulaParser<'a> {
+    tokens: &'a [char],
+    pos: usize,
+    vars: &'a [(&'a str, f64)],
+}
+
+impl FormulaParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.factor()?;
+            value = if op == '*' { value * rhs } else { value / rhs };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(-self.factor()?)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if self.peek() != Some(')') {
+                    return Err("Formula non valida: parentesi non chiusa".to_string());
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let text: String = self.tokens[start..self.pos].iter().collect();
+                text.parse().map_err(|_| format!("Formula non valida: numero '{}'", text))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.tokens[start..self.pos].iter().collect();
+                self.vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| *v)
+                    .ok_or_else(|| format!("Formula non valida: variabile sconosciuta '{}'", name))
+            }
+            Some(c) => Err(format!("Formula non valida: carattere inatteso '{}'", c)),
+            None => Err("Formula non valida: espressione incompleta".to_string()),
+        }
+    }
+}
+
+// ── Statistics ────────────────────────────────────────────────────────────
+
+/// Averages over the exams counted by `rules`.
+pub struct Averages {
+    pub weighted: Option<f64>,
+    pub arithmetic: Option<f64>,
+    pub lodi: usize,
+}
+
+/// Averages of a set of (grade, CFU) pairs.
+pub fn averages<I: IntoIterator<Item = (Grade, u32)>>(grades: I, rules: &StatsRules) -> Averages {
+    let (mut weighted_sum, mut cfu_sum, mut sum, mut count, mut lodi) = (0.0, 0.0, 0.0, 0usize, 0usize);
+    for (grade, cfu) in grades {
+        if grade == Grade::Lode { lodi += 1; }
+        if let Some(points) = rules.points(grade) {
+            weighted_sum += points * cfu as f64;
+            cfu_sum += cfu as f64;
+            sum += points;
+            count += 1;
+        }
+    }
+    Averages {
+        weighted: (cfu_sum > 0.0).then(|| weighted_sum / cfu_sum),
+        arithmetic: (count > 0).then(|| sum / count as f64),
+        lodi,
+    }
+}
+
+/// Base di laurea from `rules.base_formula`; `None` until there is an average.
+pub fn base_score(avg: &Averages, cfu: u32, total: u32, rules: &StatsRules) -> Result<Option<f64>, String> {
+    let (weighted, arithmetic) = match (avg.weighted, avg.arithmetic) {
+        (Some(w), Some(a)) => (w, a),
+        _ => return Ok(None),
+    };
+    let vars = [
+        ("avg", weighted),
+        ("arith", arithmetic),
+        ("cfu", cfu as f64),
+        ("total", total as f64),
+        ("lodi", avg.lodi as f64),
+    ];
+    eval_formula(&rules.base_formula, &vars).map(Some)
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+/// Full statistics for `get_career_stats`.
+pub fn career_stats(career: &Career, rules: &StatsRules, today: NaiveDate) -> Result<Value, String> {
+    let passed: Vec<_> = career.exams.iter().filter(|e| e.is_passed()).collect();
+    // Group CFU beyond what the group requires do not count (see requirements)
+    let (cfu_earned, cfu_extra) = requirements::counted_cfu(career);
+    let cfu_planned: u32 = career.exams.iter().map(|e| e.cfu).sum();
+    let cfu_total = if career.total_cfu > 0 { career.total_cfu } else { cfu_planned };
+    let cfu_remaining = cfu_total.saturating_sub(cfu_earned);
+
+    let avg = averages(passed.iter().filter_map(|e| e.grade.map(|g| (g, e.cfu))), rules);
+    let base = base_score(&avg, cfu_earned, cfu_total, rules)?;
+
+    // CFU by year and by semester
+    let mut by_semester: BTreeMap<(u32, u32), (u32, u32, usize, usize)> = BTreeMap::new();
+    for exam in &career.exams {
+        let entry = by_semester.entry((exam.year, exam.sem)).or_default();
+        entry.0 += exam.cfu;
+        entry.2 += 1;
+        if exam.is_passed() {
+            entry.1 += exam.cfu;
+            entry.3 += 1;
+        }
+    }
+    let mut by_year: BTreeMap<u32, (u32, u32, usize, usize)> = BTreeMap::new();
+    for (&(year, _), s) in &by_semester {
+        let entry = by_year.entry(year).or_default();
+        entry.0 += s.0;
+        entry.1 += s.1;
+        entry.2 += s.2;
+        entry.3 += s.3;
+    }
+    let row = |(planned, earned, exams, passed): (u32, u32, usize, usize)| serde_json::json!({
+        "cfuPlanned": planned, "cfuEarned": earned, "exams": exams, "passed": passed,
+    });
+
+    // Pace: CFU per month since the first passed exam
+    let pass_dates: Vec<NaiveDate> = passed.iter()
+        .filter_map(|e| NaiveDate::parse_from_str(&e.pass_date, "%Y-%m-%d").ok())
+        .collect();
+    let first_pass = pass_dates.iter().min().copied();
+    let cfu_per_month = first_pass.map(|first| {
+        let days = (today - first).num_days().max(30) as f64;
+        cfu_earned as f64 / days * 30.44
+    }).filter(|rate| *rate > 0.0);
+    let projected = match cfu_per_month {
+        _ if cfu_remaining == 0 && cfu_total > 0 => Some(today),
+        Some(rate) => Some(today + chrono::Duration::days((cfu_remaining as f64 / rate * 30.44).ceil() as i64)),
+        None => None,
+    };
+
+    Ok(serde_json::json!({
+        "cfuEarned": cfu_earned,
+        "cfuExtra": cfu_extra,
+        "cfuTotal": cfu_total,
+        "cfuRemaining": cfu_remaining,
+        "progressPct": (cfu_earned * 100).checked_div(cfu_total).unwrap_or(0).min(100),
+        "examsPassed": passed.len(),
+        "examsTotal": career.exams.len(),
+        "weightedAverage": avg.weighted.map(round2),
+        "arithmeticAverage": avg.arithmetic.map(round2),
+        "lodi": avg.lodi,
+        "baseScore": base.map(round2),
+        "baseFormula": rules.base_formula,
+        "byYear": by_year.into_iter().map(|(year, s)| {
+            let mut r = row(s);
+            r["year"] = Value::from(year);
+            r
+        }).collect::<Vec<_>>(),
+        "bySemester": by_semester.into_iter().map(|((year, sem), s)| {
+            let mut r = row(s);
+            r["year"] = Value::from(year);
+            r["sem"] = Value::from(sem);
+            r
+        }).collect::<Vec<_>>(),
+        "cfuPerMonth": cfu_per_month.map(round2),
+        "firstPassDate": first_pass.map(|d| d.format("%Y-%m-%d").to_string()),
+        "projectedGraduation": projected.map(|d| d.format("%Y-%m-%d").to_string()),
+        "attempts": attempt_stats(career),
+    }))
+}
+
+/// Attempt history totals: outcomes, pass rate over the attempts sat and
+/// attempts needed per passed exam.
+fn attempt_stats(career: &Career) -> Value {
+    let count = |outcome: AttemptOutcome| career.exams.iter()
+        .flat_map(|e| &e.attempts)
+        .filter(|a| a.outcome == outcome)
+        .count();
+    let (passed, failed, withdrawn, rejected) = (
+        count(AttemptOutcome::Passed),
+        count(AttemptOutcome::Failed),
+        count(AttemptOutcome::Withdrawn),
+        count(AttemptOutcome::Rejected),
+    );
+    let sat = passed + failed + withdrawn + rejected;
+
+    // Only exams passed through a recorded attempt say how many tries it took
+    let tries: Vec<usize> = career.exams.iter()
+        .filter(|e| e.attempts.iter().any(|a| a.outcome == AttemptOutcome::Passed))
+        .map(|e| e.attempts_sat())
+        .collect();
+    let by_exam: Vec<Value> = career.exams.iter()
+        .filter(|e| e.attempts_sat() > 0)
+        .map(|e| {
+            let n = |outcome: AttemptOutcome| e.attempts.iter().filter(|a| a.outcome == outcome).count();
+            serde_json::json!({
+                "id": e.id,
+                "name": e.name,
+                "attempts": e.attempts_sat(),
+                "failed": n(AttemptOutcome::Failed),
+                "withdrawn": n(AttemptOutcome::Withdrawn),
+                "rejected": n(AttemptOutcome::Rejected),
+                "passed": e.is_passed(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "total": sat,
+        "passed": passed,
+        "failed": failed,
+        "withdrawn": withdrawn,
+        "rejected": rejected,
+        "scheduled": count(AttemptOutcome::Scheduled),
+        "passRate": (sat > 0).then(|| round2(passed as f64 / sat as f64 * 100.0)),
+        "attemptsPerExam": (!tries.is_empty()).then(|| round2(tries.iter().sum::<usize>() as f64 / tries.len() as f64)),
+        "firstTryPasses": tries.iter().filter(|&&n| n == 1).count(),
+        "byExam": by_exam,
+    })
+}
+
+// ── What-if simulation ────────────────────────────────────────────────────
+
+/// Outcome of `simulate_career`: the career as if the pending exams in
+/// `hypothetical` (exam id → grade) were passed with those grades, plus the
+/// average needed on the remaining exams to reach `target` (base di laurea).
+pub fn simulate(
+    career: &Career,
+    rules: &StatsRules,
+    hypothetical: &Map<String, Value>,
+    target: Option<f64>,
+) -> Result<Value, String> {
+    for id in hypothetical.keys() {
+        match career.exams.iter().find(|e| &e.id == id) {
+            None => return Err(format!("Esame sconosciuto: {}", id)),
+            Some(e) if e.is_passed() => return Err(format!("{}: esame già superato", e.name)),
+            Some(_) => {}
+        }
+    }
+
+    let simulated = |exam: &CareerExam| hypothetical.get(&exam.id).is_some_and(|g| !g.is_null());
+    let mut counted: Vec<(Grade, u32)> = Vec::new();
+    let mut cfu_simulated = 0;
+    for exam in &career.exams {
+        if exam.is_passed() {
+            if let Some(grade) = exam.grade {
+                counted.push((grade, exam.cfu));
+            }
+        } else if simulated(exam) {
+            let grade = Grade::parse(&hypothetical[&exam.id]).map_err(|e| format!("{}: {}", exam.name, e))?;
+            counted.push((grade, exam.cfu));
+            cfu_simulated += exam.cfu;
+        }
+    }
+
+    // Counted and still needed by the same rules as get_requirements:
+    // group CFU beyond the requirement don't count, and only the options
+    // that cover a group are left to take
+    let passed_or_simulated = |exam: &CareerExam| exam.is_passed() || simulated(exam);
+    let (cfu_counted, _) = requirements::counted_cfu_if(career, passed_or_simulated);
+    let needed = requirements::still_needed(career, passed_or_simulated);
+    let cfu_planned: u32 = career.exams.iter().map(|e| e.cfu).sum();
+    let cfu_total = if career.total_cfu > 0 { career.total_cfu } else { cfu_planned };
+    let cfu_left: u32 = needed.iter().map(|n| n.cfu).sum();
+    // Tirocinio and prova finale add CFU but no grade
+    let remaining: Vec<u32> = needed.iter()
+        .filter(|n| n.exam.map_or(true, |e| e.activity == Activity::Exam))
+        .map(|n| n.cfu)
+        .collect();
+    let exams_left = needed.iter().filter(|n| n.exam.is_some()).count();
+
+    let avg = averages(counted.iter().copied(), rules);
+    let base = base_score(&avg, cfu_counted, cfu_total, rules)?;
+
+    // Base di laurea if every remaining exam got `grade`
+    let finish_with = |grade: f64| -> Result<Option<f64>, String> {
+        let (mut weighted, mut cfu, mut sum, mut count) = (0.0, 0.0, 0.0, 0usize);
+        for &(g, c) in &counted {
+            if let Some(points) = rules.points(g) {
+                weighted += points * c as f64;
+                cfu += c as f64;
+                sum += points;
+                count += 1;
+            }
+        }
+        for &c in &remaining {
+            weighted += grade * c as f64;
+            cfu += c as f64;
+            sum += grade;
+            count += 1;
+        }
+        let avg = Averages {
+            weighted: (cfu > 0.0).then(|| weighted / cfu),
+            arithmetic: (count > 0).then(|| sum / count as f64),
+            lodi: avg.lodi,
+        };
+        base_score(&avg, cfu_counted + cfu_left, cfu_total, rules)
+    };
+    let min_base = finish_with(18.0)?;
+    let max_base = finish_with(30.0)?;
+
+    let target_result = match target {
+        None => Value::Null,
+        Some(score) => {
+            let (required, reachable) = match (min_base, max_base) {
+                _ if remaining.is_empty() => (None, base.is_some_and(|b| b >= score)),
+                (Some(lo), _) if lo >= score => (Some(18.0), true),
+                (_, Some(hi)) if hi < score => (None, false),
+                (Some(_), Some(_)) => {
+                    // The base grows with the grade: bisect on [18, 30]
+                    let (mut lo, mut hi) = (18.0, 30.0);
+                    for _ in 0..50 {
+                        let mid = (lo + hi) / 2.0;
+                        if finish_with(mid)?.is_some_and(|b| b >= score) { hi = mid } else { lo = mid }
+                    }
+                    (Some((hi * 100.0_f64).ceil() / 100.0), true)
+                }
+                _ => (None, false),
+            };
+            serde_json::json!({
+                "score": score,
+                "reachable": reachable,
+                "requiredAverage": required,
+                "cfuRemaining": cfu_left,
+                "examsRemaining": exams_left,
+            })
+        }
+    };
+
+    Ok(serde_json::json!({
+        "weightedAverage": avg.weighted.map(round2),
+        "arithmeticAverage": avg.arithmetic.map(round2),
+        "lodi": avg.lodi,
+        "baseScore": base.map(round2),
+        "cfuCounted": cfu_counted,
+        "cfuSimulated": cfu_simulated,
+        "cfuRemaining": cfu_left,
+        "examsRemaining": exams_left,
+        "minBaseScore": min_base.map(round2),
+        "maxBaseScore": max_base.map(round2),
+        "target": target_result,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 12 CFU passed in a 6 CFU group, an unchosen option, a thesis and a
+    /// free-choice group with no exams yet.
+    fn career() -> Career {
+        let mut data = json!({
+            "type": "triennale",
+            "totalCfu": 60,
+            "electiveGroups": [
+                { "id": "g1", "name": "A scelta", "cfu": 6 },
+                { "id": "free", "name": "Scelta libera", "cfu": 12, "kind": "free" },
+            ],
+            "exams": [
+                { "id": "an", "name": "Analisi", "cfu": 12, "status": "passed", "grade": 24, "passDate": "2026-02-01" },
+                { "id": "fi", "name": "Fisica", "cfu": 9, "status": "passed", "grade": 30, "passDate": "2026-06-20" },
+                { "id": "o1", "name": "Opzione 1", "cfu": 6, "group": "g1", "status": "passed", "grade": 28, "passDate": "2026-07-01" },
+                { "id": "o2", "name": "Opzione 2", "cfu": 6, "group": "g1", "status": "passed", "grade": 18, "passDate": "2026-07-10" },
+                { "id": "o3", "name": "Opzione 3", "cfu": 6, "group": "g1" },
+                { "id": "al", "name": "Algebra", "cfu": 9 },
+                { "id": "pf", "name": "Prova finale", "cfu": 3, "activity": "thesis" },
+            ],
+        });
+        crate::career::migrate(&mut data);
+        Career::from_value(&data).unwrap()
+    }
+
+    #[test]
+    fn simulate_without_grades_matches_career_stats() {
+        let career = career();
+        let rules = StatsRules::resolve(&career, None).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let stats = career_stats(&career, &rules, today).unwrap();
+        let sim = simulate(&career, &rules, &Map::new(), None).unwrap();
+
+        assert_eq!(sim["baseScore"], stats["baseScore"]);
+        assert_eq!(sim["weightedAverage"], stats["weightedAverage"]);
+        assert_eq!(sim["cfuCounted"], stats["cfuEarned"]);
+        // Opzione 2 is beyond the group's 6 CFU: not earned, still averaged
+        assert_eq!(stats["cfuEarned"], json!(12 + 9 + 6));
+        assert_eq!(stats["cfuExtra"], json!(6));
+        assert_eq!(stats["weightedAverage"], json!(25.27));
+        // Algebra, prova finale and the free-choice credits; not Opzione 3
+        assert_eq!(sim["cfuRemaining"], json!(9 + 3 + 12));
+        assert_eq!(sim["examsRemaining"], json!(2));
+    }
+
+    #[test]
+    fn simulate_counts_hypothetical_grades_like_passed_exams() {
+        let career = career();
+        let rules = StatsRules::resolve(&career, None).unwrap();
+        let grades = json!({ "al": 27 });
+        let sim = simulate(&career, &rules, grades.as_object().unwrap(), Some(100.0)).unwrap();
+
+        assert_eq!(sim["cfuCounted"], json!(12 + 9 + 6 + 9));
+        assert_eq!(sim["cfuSimulated"], json!(9));
+        assert_eq!(sim["cfuRemaining"], json!(3 + 12));
+        // The free-choice credits are the only graded CFU left
+        assert_eq!(sim["target"]["cfuRemaining"], json!(15));
+        assert!(sim["minBaseScore"].as_f64().unwrap() < sim["maxBaseScore"].as_f64().unwrap());
+    }
+}
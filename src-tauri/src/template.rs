@@ -0,0 +1,430 @@
+// ===== Degree-Program Templates =====
+//
+// A shareable description of a course of study (exams, CFU, year, semester,
+// prerequisites, elective groups) that `import_degree_template` turns into
+// career.json and `export_degree_template` writes back out.  The same fields
+// are accepted as JSON or TOML; the format is documented in
+// docs/degree-template.md.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::career::{Activity, Career, GroupKind};
+
+pub const TEMPLATE_FORMAT: &str = "studyplan-degree";
+pub const TEMPLATE_VERSION: u32 = 1;
+
+fn default_format() -> String { TEMPLATE_FORMAT.to_string() }
+fn default_version() -> u32 { TEMPLATE_VERSION }
+fn default_one() -> u32 { 1 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DegreeTemplate {
+    #[serde(default = "default_format")]
+    pub format: String,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub university: String,
+    #[serde(default)]
+    pub course: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub total_cfu: u32,
+    #[serde(default)]
+    pub years: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub elective_groups: Vec<TemplateGroup>,
+    #[serde(default)]
+    pub exams: Vec<TemplateExam>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateExam {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub cfu: u32,
+    #[serde(default = "default_one")]
+    pub year: u32,
+    #[serde(default = "default_one")]
+    pub sem: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prerequisites: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "is_exam")]
+    pub activity: Activity,
+}
+
+fn is_exam(activity: &Activity) -> bool {
+    *activity == Activity::Exam
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateGroup {
+    pub id: String,
+    pub name: String,
+    /// CFU to earn among the exams of the group
+    #[serde(default)]
+    pub cfu: u32,
+    #[serde(default)]
+    pub kind: GroupKind,
+}
+
+/// Lowercase ASCII id from an exam name ("Analisi Matematica I" →
+/// "analisi-matematica-i").
+pub fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.to_lowercase().chars() {
+        let c = match c {
+            'à' | 'á' => 'a',
+            'è' | 'é' => 'e',
+            'ì' | 'í' => 'i',
+            'ò' | 'ó' => 'o',
+            'ù' | 'ú' => 'u',
+            c => c,
+        };
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.ends_with('-') && !out.is_empty() {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+impl DegreeTemplate {
+    /// Parses a template; TOML unless the text looks like JSON or the file
+    /// name ends in `.json`.
+    pub fn parse(text: &str, file_name: Option<&str>) -> Result<DegreeTemplate, String> {
+        let json = file_name.is_some_and(|n| n.to_lowercase().ends_with(".json"))
+            || text.trim_start().starts_with('{');
+        let mut template: DegreeTemplate = if json {
+            serde_json::from_str(text).map_err(|e| format!("Template JSON non valido: {}", e))?
+        } else {
+            toml::from_str(text).map_err(|e| format!("Template TOML non valido: {}", e))?
+        };
+        for exam in &mut template.exams {
+            if exam.id.trim().is_empty() {
+                exam.id = slug(&exam.name);
+            }
+        }
+        template.validate()?;
+        Ok(template)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut issues = Vec::new();
+        if self.format != TEMPLATE_FORMAT {
+            issues.push(format!("Formato \"{}\" non supportato (atteso \"{}\")", self.format, TEMPLATE_FORMAT));
+        }
+        if self.version > TEMPLATE_VERSION {
+            issues.push(format!("Versione {} del template non supportata (massima {})", self.version, TEMPLATE_VERSION));
+        }
+        let ids: HashSet<&str> = self.exams.iter().map(|e| e.id.as_str()).collect();
+        let groups: HashSet<&str> = self.elective_groups.iter().map(|g| g.id.as_str()).collect();
+        if ids.len() != self.exams.len() {
+            issues.push("ID esame duplicati nel template".to_string());
+        }
+        for exam in &self.exams {
+            if exam.name.trim().is_empty() { issues.push(format!("Esame {} senza nome", exam.id)); }
+            if exam.cfu == 0 { issues.push(format!("{}: i CFU devono essere maggiori di zero", exam.name)); }
+            if self.years > 0 && !(1..=self.years).contains(&exam.year) {
+                issues.push(format!("{}: anno {} fuori dal corso ({} anni)", exam.name, exam.year, self.years));
+            }
+            if !(1..=2).contains(&exam.sem) { issues.push(format!("{}: semestre {} non valido", exam.name, exam.sem)); }
+            for p in exam.prerequisites.iter().filter(|p| !ids.contains(p.as_str())) {
+                issues.push(format!("{}: propedeuticità sconosciuta ({})", exam.name, p));
+            }
+            if let Some(g) = exam.group.as_deref().filter(|g| !groups.contains(g)) {
+                issues.push(format!("{}: gruppo a scelta sconosciuto ({})", exam.name, g));
+            }
+        }
+        if issues.is_empty() { Ok(()) } else { Err(issues.join("\n")) }
+    }
+
+    /// Template describing the structure of `career` (no grades, attempts or
+    /// study progress).
+    pub fn from_career(career: &Career) -> DegreeTemplate {
+        let elective_groups = career.elective_groups.iter()
+            .map(|g| TemplateGroup { id: g.id.clone(), name: g.name.clone(), cfu: g.cfu, kind: g.kind })
+            .collect();
+        DegreeTemplate {
+            format: default_format(),
+            version: TEMPLATE_VERSION,
+            university: career.university.clone(),
+            course: career.course.clone(),
+            kind: career.kind.clone(),
+            total_cfu: career.total_cfu,
+            years: career.years,
+            elective_groups,
+            exams: career.exams.iter().map(|e| TemplateExam {
+                id: e.id.clone(),
+                name: e.name.clone(),
+                cfu: e.cfu,
+                year: e.year,
+                sem: e.sem,
+                prerequisites: e.prerequisites.clone(),
+                group: e.group.clone(),
+                activity: e.activity,
+            }).collect(),
+        }
+    }
+
+    /// Serializes as TOML or JSON, chosen by the extension of `path`.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let toml = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("toml"));
+        let text = if toml {
+            toml::to_string_pretty(self).map_err(|e| e.to_string())?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, text).map_err(|e| format!("Scrittura template fallita: {}", e))
+    }
+
+    /// Applies the template to `career` (career.json, `null` when empty).
+    ///
+    /// `replace` starts a new career from the template.  Otherwise exams are
+    /// matched by id, then by name (case-insensitive): matches get the
+    /// template's CFU, year, semester, prerequisites and group and keep
+    /// grades, attempts and files; the rest are added as pending.  Returns
+    /// the counts of added and updated exams.
+    pub fn apply(&self, career: &mut Value, replace: bool) -> (usize, usize) {
+        if replace || !career.is_object() {
+            *career = serde_json::json!({ "exams": [] });
+        }
+        let obj = career.as_object_mut().expect("career is an object");
+        for (key, value) in [
+            ("university", Value::from(self.university.as_str())),
+            ("course", Value::from(self.course.as_str())),
+            ("type", Value::from(self.kind.as_str())),
+        ] {
+            if !value.as_str().unwrap_or("").is_empty() { obj.insert(key.to_string(), value); }
+        }
+        if self.total_cfu > 0 { obj.insert("totalCfu".to_string(), Value::from(self.total_cfu)); }
+        if self.years > 0 { obj.insert("years".to_string(), Value::from(self.years)); }
+        if !self.elective_groups.is_empty() {
+            obj.insert("electiveGroups".to_string(), serde_json::to_value(&self.elective_groups).unwrap_or_default());
+        }
+        if !obj.get("exams").is_some_and(|e| e.is_array()) {
+            obj.insert("exams".to_string(), Value::Array(vec![]));
+        }
+        let exams = obj.get_mut("exams").and_then(|e| e.as_array_mut()).expect("exams is an array");
+
+        // Template id → id of the career exam it lands on
+        let mut ids: HashMap<&str, String> = HashMap::new();
+        let (mut added, mut updated) = (0, 0);
+        for t in &self.exams {
+            let found = exams.iter().position(|e| e.get("id").and_then(|i| i.as_str()) == Some(t.id.as_str()))
+                .or_else(|| exams.iter().position(|e| {
+                    e.get("name").and_then(|n| n.as_str()).is_some_and(|n| n.trim().eq_ignore_ascii_case(t.name.trim()))
+                }));
+            let exam = match found {
+                Some(i) => {
+                    updated += 1;
+                    &mut exams[i]
+                }
+                None => {
+                    added += 1;
+                    exams.push(serde_json::json!({
+                        "id": t.id, "name": t.name, "status": "pending", "grade": null,
+                        "passDate": "", "examDate": "", "progress": 0, "pdfs": [],
+                    }));
+                    exams.last_mut().expect("just pushed")
+                }
+            };
+            exam["cfu"] = Value::from(t.cfu);
+            exam["year"] = Value::from(t.year);
+            exam["sem"] = Value::from(t.sem);
+            match &t.group {
+                Some(g) => exam["group"] = Value::from(g.as_str()),
+                None => { exam.as_object_mut().map(|o| o.remove("group")); }
+            }
+            exam["activity"] = serde_json::to_value(t.activity).unwrap_or_default();
+            ids.insert(t.id.as_str(), exam["id"].as_str().unwrap_or(&t.id).to_string());
+        }
+        for t in &self.exams {
+            let Some(id) = ids.get(t.id.as_str()) else { continue };
+            let prerequisites: Vec<Value> = t.prerequisites.iter()
+                .filter_map(|p| ids.get(p.as_str()))
+                .map(|p| Value::from(p.as_str()))
+                .collect();
+            if let Some(exam) = exams.iter_mut().find(|e| e.get("id").and_then(|i| i.as_str()) == Some(id.as_str())) {
+                exam["prerequisites"] = Value::Array(prerequisites);
+            }
+        }
+        (added, updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOML: &str = r#"
+course = "Informatica"
+type = "triennale"
+totalCfu = 180
+years = 3
+
+[[electiveGroups]]
+id = "g1"
+name = "A scelta"
+cfu = 6
+
+[[exams]]
+name = "Analisi Matematica I"
+cfu = 12
+
+[[exams]]
+id = "fis"
+name = "Fisica"
+cfu = 9
+year = 2
+sem = 2
+prerequisites = ["analisi-matematica-i"]
+
+[[exams]]
+id = "ml"
+name = "Machine Learning"
+cfu = 6
+year = 3
+group = "g1"
+
+[[exams]]
+id = "tir"
+name = "Tirocinio"
+cfu = 6
+year = 3
+activity = "internship"
+"#;
+
+    fn template() -> DegreeTemplate {
+        DegreeTemplate::parse(TOML, Some("informatica.toml")).unwrap()
+    }
+
+    #[test]
+    fn toml_and_json_templates_read_the_same() {
+        let toml = template();
+        assert_eq!(toml.exams[0].id, "analisi-matematica-i");
+        assert_eq!((toml.exams[0].year, toml.exams[0].sem), (1, 1));
+        assert_eq!(toml.exams[3].activity, Activity::Internship);
+
+        let text = serde_json::to_string(&toml).unwrap();
+        let json = DegreeTemplate::parse(&text, None).unwrap();
+        assert_eq!(serde_json::to_value(&json).unwrap(), serde_json::to_value(&toml).unwrap());
+        // A .json name is read as JSON whatever the text looks like
+        assert!(DegreeTemplate::parse(TOML, Some("piano.JSON")).unwrap_err().starts_with("Template JSON non valido"));
+
+        assert!(DegreeTemplate::parse("exams = [", Some("x.toml")).unwrap_err().starts_with("Template TOML non valido"));
+        assert!(DegreeTemplate::parse("{ \"exams\": 1 }", None).unwrap_err().starts_with("Template JSON non valido"));
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        let text = r#"{ "format": "altro", "version": 2, "years": 3, "exams": [
+            { "id": "a", "name": "Analisi", "cfu": 0, "year": 4, "prerequisites": ["z"], "group": "g9" },
+            { "id": "a", "name": "Algebra", "cfu": 6, "sem": 3 }
+        ] }"#;
+        assert_eq!(DegreeTemplate::parse(text, None).unwrap_err().lines().collect::<Vec<_>>(), [
+            "Formato \"altro\" non supportato (atteso \"studyplan-degree\")",
+            "Versione 2 del template non supportata (massima 1)",
+            "ID esame duplicati nel template",
+            "Analisi: i CFU devono essere maggiori di zero",
+            "Analisi: anno 4 fuori dal corso (3 anni)",
+            "Analisi: propedeuticità sconosciuta (z)",
+            "Analisi: gruppo a scelta sconosciuto (g9)",
+            "Algebra: semestre 3 non valido",
+        ]);
+    }
+
+    #[test]
+    fn slugs_are_lowercase_ascii() {
+        assert_eq!(slug("Analisi Matematica I"), "analisi-matematica-i");
+        assert_eq!(slug("  Attività  di laboratorio (mod. B) "), "attivita-di-laboratorio-mod-b");
+        assert_eq!(slug("Perché?"), "perche");
+    }
+
+    #[test]
+    fn merge_matches_by_id_then_name_and_keeps_grades() {
+        let mut career = json!({ "course": "Vecchio nome", "university": "Unipd", "exams": [
+            // By name: keeps its id, so prerequisites point at it
+            { "id": "x1", "name": "analisi matematica i", "cfu": 9, "status": "passed", "grade": 28, "passDate": "2024-02-01",
+              "attempts": [{ "date": "2024-02-01", "outcome": "passed", "grade": 28 }], "pdfs": [{ "fileName": "a.pdf" }] },
+            // By id, despite the different name
+            { "id": "fis", "name": "Fisica generale", "cfu": 6, "group": "old",
+              "attempts": [{ "date": "2024-06-10", "outcome": "failed" }] },
+            { "id": "extra", "name": "Esame fuori piano", "cfu": 3 },
+        ]});
+        assert_eq!(template().apply(&mut career, false), (2, 2));
+
+        assert_eq!(career["course"], "Informatica");
+        assert_eq!(career["university"], "Unipd");
+        assert_eq!(career["totalCfu"], 180);
+        assert_eq!(career["electiveGroups"][0]["id"], "g1");
+        let exams = career["exams"].as_array().unwrap();
+        assert_eq!(exams.iter().map(|e| e["id"].as_str().unwrap()).collect::<Vec<_>>(), ["x1", "fis", "extra", "ml", "tir"]);
+
+        let analisi = &exams[0];
+        assert_eq!(analisi["cfu"], 12);
+        assert_eq!((analisi["status"].clone(), analisi["grade"].clone()), (json!("passed"), json!(28)));
+        assert_eq!(analisi["attempts"].as_array().unwrap().len(), 1);
+        assert_eq!(analisi["pdfs"], json!([{ "fileName": "a.pdf" }]));
+
+        let fisica = &exams[1];
+        assert_eq!(fisica["name"], "Fisica generale");
+        assert_eq!((fisica["cfu"].clone(), fisica["year"].clone(), fisica["sem"].clone()), (json!(9), json!(2), json!(2)));
+        assert_eq!(fisica["prerequisites"], json!(["x1"]));
+        assert!(fisica.get("group").is_none());
+        assert_eq!(fisica["attempts"], json!([{ "date": "2024-06-10", "outcome": "failed" }]));
+
+        assert_eq!(exams[2], json!({ "id": "extra", "name": "Esame fuori piano", "cfu": 3 }));
+        assert_eq!(exams[3]["group"], "g1");
+        assert_eq!(exams[3]["status"], "pending");
+        assert_eq!(exams[4]["activity"], "internship");
+
+        // The merged career is valid once migrated
+        crate::career::migrate(&mut career);
+        let parsed = Career::from_value(&career).unwrap();
+        assert!(parsed.validate().is_empty(), "{:?}", parsed.validate());
+    }
+
+    #[test]
+    fn replace_starts_a_new_career() {
+        let mut career = json!({ "id": "c1", "university": "Unipd", "exams": [
+            { "id": "fis", "name": "Fisica", "cfu": 6, "status": "passed", "grade": 30 },
+        ]});
+        assert_eq!(template().apply(&mut career, true), (4, 0));
+        assert!(career.get("id").is_none() && career.get("university").is_none());
+        assert_eq!(career["exams"][1]["id"], "fis");
+        assert_eq!(career["exams"][1]["status"], "pending");
+        assert_eq!(career["exams"][1]["prerequisites"], json!(["analisi-matematica-i"]));
+
+        let mut empty = Value::Null;
+        assert_eq!(template().apply(&mut empty, false), (4, 0));
+    }
+
+    #[test]
+    fn exported_template_reads_back() {
+        let mut career = Value::Null;
+        template().apply(&mut career, false);
+        let exported = DegreeTemplate::from_career(&Career::from_value(&career).unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["piano.toml", "piano.json"] {
+            let path = dir.path().join(name);
+            exported.write(&path).unwrap();
+            let text = std::fs::read_to_string(&path).unwrap();
+            let read = DegreeTemplate::parse(&text, Some(name)).unwrap();
+            assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&exported).unwrap());
+        }
+        assert_eq!(serde_json::to_value(&exported).unwrap(), serde_json::to_value(template()).unwrap());
+    }
+}
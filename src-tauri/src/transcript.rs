@@ -0,0 +1,510 @@
+// ===== Transcript Import =====
+//
+// `import_transcript` reads the libretto exported by university portals
+// (Esse3 and similar) as CSV or spreadsheet (XLSX, XLS, ODS) and merges it
+// into career.json.  Columns are found by header name (`Field::synonyms`) or
+// chosen explicitly with a `ColumnMapping`.  Each row is matched to an
+// existing exam by name, tolerating small spelling differences, so importing
+// an updated libretto again updates exams instead of duplicating them.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::career::Grade;
+use crate::template::slug;
+
+/// Minimum name similarity (normalized Levenshtein on `match_key`) for a row
+/// to update an existing exam instead of adding a new one.
+const MATCH_THRESHOLD: f64 = 0.85;
+
+/// Portals put student name, matricola and course above the table: the
+/// header row is looked for among the first rows.
+const HEADER_SCAN_ROWS: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field { Name, Cfu, Grade, Date, Ssd, Year }
+
+const FIELDS: [Field; 6] = [Field::Name, Field::Cfu, Field::Grade, Field::Date, Field::Ssd, Field::Year];
+
+impl Field {
+    fn key(self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Cfu => "cfu",
+            Field::Grade => "grade",
+            Field::Date => "date",
+            Field::Ssd => "ssd",
+            Field::Year => "year",
+        }
+    }
+
+    /// Header names recognized when the mapping leaves the field out, as
+    /// slugs and by priority.
+    fn synonyms(self) -> &'static [&'static str] {
+        match self {
+            Field::Name => &[
+                "attivita-didattica", "insegnamento", "denominazione", "descrizione-attivita",
+                "descrizione", "esame", "nome", "attivita", "course",
+            ],
+            Field::Cfu => &["cfu", "crediti", "peso", "peso-in-crediti", "peso-cfu", "credits", "ects"],
+            Field::Grade => &["voto", "voto-esame", "esito", "voto-giudizio", "giudizio", "grade"],
+            Field::Date => &[
+                "data-esame", "data-superamento", "data-appello", "data-verbalizzazione", "data", "date",
+            ],
+            Field::Ssd => &["ssd", "settore", "settore-scientifico-disciplinare"],
+            Field::Year => &["anno-di-corso", "anno-corso", "anno", "year"],
+        }
+    }
+}
+
+/// Explicit column per field: header text (case and accents ignored) or
+/// 1-based column number.  Fields left out are detected from the headers.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub cfu: Option<String>,
+    #[serde(default)]
+    pub grade: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub ssd: Option<String>,
+    #[serde(default)]
+    pub year: Option<String>,
+    /// 1-based row holding the headers; found automatically when omitted
+    #[serde(default)]
+    pub header_row: Option<usize>,
+}
+
+impl ColumnMapping {
+    fn get(&self, field: Field) -> Option<&str> {
+        let value = match field {
+            Field::Name => &self.name,
+            Field::Cfu => &self.cfu,
+            Field::Grade => &self.grade,
+            Field::Date => &self.date,
+            Field::Ssd => &self.ssd,
+            Field::Year => &self.year,
+        };
+        value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
+}
+
+// ── Reading ──────────────────────────────────────────────
+
+/// Cells of the file as text, one vector per row.  Spreadsheet dates become
+/// YYYY-MM-DD; only the first non-empty sheet is read.
+pub fn read_table(path: &Path) -> Result<Vec<Vec<String>>, String> {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "csv" | "txt" => read_csv(path),
+        "xlsx" | "xlsm" | "xlsb" | "xls" | "ods" => read_sheet(path),
+        _ => Err(format!("Formato \"{}\" non supportato (CSV, XLSX, XLS o ODS)", ext)),
+    }
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Vec<String>>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Lettura file fallita: {}", e))?;
+    let text = decode(bytes);
+    let text = text.trim_start_matches('\u{feff}');
+    // The delimiter that splits some row of the header area the most
+    let delimiter = [b',', b'\t', b';'].into_iter()
+        .max_by_key(|d| text.lines().take(HEADER_SCAN_ROWS).map(|l| l.matches(char::from(*d)).count()).max())
+        .unwrap_or(b';');
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|r| {
+            r.map(|record| record.iter().map(|c| c.trim().to_string()).collect())
+                .map_err(|e| format!("CSV non valido: {}", e))
+        })
+        .collect()
+}
+
+/// UTF-8, or Windows-1252 for files that are not valid UTF-8 (Excel's
+/// "CSV" export on Italian Windows: accented letters, €, curly quotes).
+fn decode(bytes: Vec<u8>) -> String {
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => encoding_rs::WINDOWS_1252.decode_without_bom_handling(e.as_bytes()).0.into_owned(),
+    }
+}
+
+fn read_sheet(path: &Path) -> Result<Vec<Vec<String>>, String> {
+    use calamine::{Data, DataType, Reader};
+
+    let cell_text = |cell: &Data| match cell {
+        Data::DateTime(_) | Data::DateTimeIso(_) => {
+            cell.as_date().map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+        }
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        Data::Empty | Data::Error(_) => String::new(),
+        other => other.to_string().trim().to_string(),
+    };
+
+    let mut workbook = calamine::open_workbook_auto(path)
+        .map_err(|e| format!("Foglio di calcolo non leggibile: {}", e))?;
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)
+            .map_err(|e| format!("Foglio \"{}\" non leggibile: {}", name, e))?;
+        if !range.is_empty() {
+            return Ok(range.rows().map(|row| row.iter().map(cell_text).collect()).collect());
+        }
+    }
+    Err("Il file non contiene fogli con dati".to_string())
+}
+
+// ── Columns ──────────────────────────────────────────────
+
+/// Column index of each field found in `headers`.
+fn resolve(headers: &[String], mapping: &ColumnMapping) -> Result<Vec<(Field, usize)>, String> {
+    let slugs: Vec<String> = headers.iter().map(|h| slug(h)).collect();
+    let mut columns: Vec<(Field, usize)> = Vec::new();
+    // Explicit choices first, so detection can't take their columns
+    for field in FIELDS {
+        let Some(wanted) = mapping.get(field) else { continue };
+        let index = match wanted.parse::<usize>() {
+            Ok(n) if n > 0 => n - 1,
+            _ => slugs.iter().position(|s| *s == slug(wanted))
+                .ok_or_else(|| format!("Colonna \"{}\" non trovata", wanted))?,
+        };
+        columns.push((field, index));
+    }
+    for field in FIELDS.into_iter().filter(|f| mapping.get(*f).is_none()) {
+        let found = field.synonyms().iter().find_map(|syn| {
+            slugs.iter().enumerate()
+                .position(|(i, s)| s == syn && !columns.iter().any(|(_, c)| *c == i))
+        });
+        if let Some(index) = found {
+            columns.push((field, index));
+        }
+    }
+    Ok(columns)
+}
+
+/// Header row (0-based) and its columns: the first row where the name and
+/// at least CFU or grade are found.
+fn find_header(rows: &[Vec<String>], mapping: &ColumnMapping) -> Result<(usize, Vec<(Field, usize)>), String> {
+    if let Some(n) = mapping.header_row.filter(|n| *n > 0) {
+        let headers = rows.get(n - 1).ok_or_else(|| format!("Riga {} oltre la fine del file", n))?;
+        return Ok((n - 1, resolve(headers, mapping)?));
+    }
+    let mut error = None;
+    for (i, headers) in rows.iter().enumerate().take(HEADER_SCAN_ROWS) {
+        match resolve(headers, mapping) {
+            Ok(columns) => {
+                let has = |f: Field| columns.iter().any(|(c, _)| *c == f);
+                if has(Field::Name) && (has(Field::Cfu) || has(Field::Grade)) {
+                    return Ok((i, columns));
+                }
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+    Err(error.unwrap_or_else(|| {
+        "Intestazione del libretto non trovata: indica le colonne di nome, CFU e voto".to_string()
+    }))
+}
+
+// ── Values ───────────────────────────────────────────────
+
+/// Grade as written by the portals ("28", "28/30", "30 e lode", "30L/30",
+/// "IDO", "Superato", ...); `None` when the row has no passing result.
+fn parse_grade(text: &str) -> Result<Option<Grade>, String> {
+    let lower = text.trim().to_lowercase();
+    let mut t = lower.strip_suffix("/30").unwrap_or(&lower).trim();
+    // "27,00" → "27"
+    if let Some((int, dec)) = t.split_once([',', '.']) {
+        if !int.is_empty() && int.chars().all(|c| c.is_ascii_digit()) && dec.chars().all(|c| c == '0') {
+            t = int;
+        }
+    }
+    match t {
+        "" | "-" => Ok(None),
+        "rit" | "ritirato" | "ass" | "assente" | "respinto" | "ins" | "insufficiente" | "non superato" => Ok(None),
+        "ido" | "sup" | "superato" | "superata" | "approvato" | "approvata" | "positivo" => Ok(Some(Grade::Idoneo)),
+        "30 con lode" | "30 cum laude" | "30 l" | "30/30 e lode" => Ok(Some(Grade::Lode)),
+        "conv" | "ric" | "riconosciuta" => Ok(Some(Grade::Convalidato)),
+        _ => Grade::parse(&Value::from(t)).map(Some),
+    }
+}
+
+/// Date as YYYY-MM-DD from the common Italian layouts; a trailing time
+/// is ignored.  Empty text gives an empty date.
+fn parse_date(text: &str) -> Result<String, String> {
+    let t = text.split_whitespace().next().unwrap_or("");
+    if t.is_empty() {
+        return Ok(String::new());
+    }
+    ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y", "%d/%m/%y"].iter()
+        .filter_map(|fmt| chrono::NaiveDate::parse_from_str(t, fmt).ok())
+        .find(|d| chrono::Datelike::year(d) >= 1900)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .ok_or_else(|| format!("data non valida \"{}\"", text.trim()))
+}
+
+/// Positive whole number ("9", "9,0", "6.00"); `None` otherwise.
+fn parse_number(text: &str) -> Option<u32> {
+    text.trim().replace(',', ".").parse::<f64>().ok()
+        .filter(|v| *v > 0.0)
+        .map(|v| v.round() as u32)
+}
+
+// ── Matching ─────────────────────────────────────────────
+
+/// Slug as compared: Roman numerals as digits and the "mod"/"modulo"
+/// marker dropped, so "Fisica II - Mod. A", "Fisica 2 modulo A" and
+/// "fisica-2-a" are the same name (punctuation is already gone in the slug).
+fn match_key(slug: &str) -> String {
+    slug.split('-')
+        .filter(|t| !t.is_empty() && *t != "mod" && *t != "modulo")
+        .map(|token| match token {
+            "i" => "1",
+            "ii" => "2",
+            "iii" => "3",
+            "iv" => "4",
+            "v" => "5",
+            "vi" => "6",
+            t => t,
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Numbering of a match key: digits and module letters ("fisica-2-a" →
+/// ["2", "a"]).
+fn numbering(key: &str) -> Vec<&str> {
+    key.split('-')
+        .filter(|t| matches!(*t, "a" | "b" | "c") || t.chars().all(|c| c.is_ascii_digit()))
+        .collect()
+}
+
+/// Name similarity in [0, 1] between two slugs; 0 when the numbering
+/// differs ("Analisi I" and "Analisi II" are one edit apart).
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (match_key(a), match_key(b));
+    if a == b {
+        return 1.0;
+    }
+    if numbering(&a) != numbering(&b) {
+        return 0.0;
+    }
+    strsim::normalized_levenshtein(&a, &b)
+}
+
+// ── Merge ────────────────────────────────────────────────
+
+struct Row {
+    line: usize,
+    name: String,
+    cfu: Option<u32>,
+    grade: Option<Grade>,
+    date: String,
+    ssd: String,
+    year: Option<u32>,
+}
+
+fn diff(field: &str, from: Value, to: Value) -> Value {
+    serde_json::json!({ "field": field, "from": from, "to": to })
+}
+
+/// Writes the row into a career exam, returning the fields that changed.
+/// A grade marks the exam passed: an existing passed attempt is corrected,
+/// otherwise pending bookings are replaced by the passed attempt.
+fn update_exam(exam: &mut Value, row: &Row) -> Vec<Value> {
+    let mut diffs = Vec::new();
+    if let Some(cfu) = row.cfu.filter(|c| exam["cfu"].as_u64() != Some(u64::from(*c))) {
+        diffs.push(diff("cfu", exam["cfu"].clone(), Value::from(cfu)));
+        exam["cfu"] = Value::from(cfu);
+    }
+    if let Some(year) = row.year.filter(|y| exam["year"].as_u64() != Some(u64::from(*y))) {
+        diffs.push(diff("year", exam["year"].clone(), Value::from(year)));
+        exam["year"] = Value::from(year);
+    }
+    if !row.ssd.is_empty() && exam["ssd"].as_str() != Some(row.ssd.as_str()) {
+        diffs.push(diff("ssd", exam["ssd"].clone(), Value::from(row.ssd.as_str())));
+        exam["ssd"] = Value::from(row.ssd.as_str());
+    }
+    let Some(grade) = row.grade else { return diffs };
+
+    if !exam.get("attempts").is_some_and(|a| a.is_array()) {
+        exam["attempts"] = Value::Array(vec![]);
+    }
+    let attempts = exam["attempts"].as_array_mut().expect("attempts is an array");
+    let passed = attempts.iter_mut().find(|a| a.get("outcome").and_then(|o| o.as_str()) == Some("passed"));
+    match passed {
+        Some(attempt) => {
+            if attempt["grade"] != grade.to_value() {
+                diffs.push(diff("grade", attempt["grade"].clone(), grade.to_value()));
+                attempt["grade"] = grade.to_value();
+            }
+            if !row.date.is_empty() && attempt["date"].as_str() != Some(row.date.as_str()) {
+                diffs.push(diff("passDate", attempt["date"].clone(), Value::from(row.date.as_str())));
+                attempt["date"] = Value::from(row.date.as_str());
+            }
+        }
+        None => {
+            attempts.retain(|a| a.get("outcome").and_then(|o| o.as_str()).unwrap_or("scheduled") != "scheduled");
+            attempts.push(serde_json::json!({ "date": row.date, "outcome": "passed", "grade": grade.to_value() }));
+            diffs.push(diff("status", exam["status"].clone(), Value::from("passed")));
+            diffs.push(diff("grade", Value::Null, grade.to_value()));
+            if !row.date.is_empty() {
+                diffs.push(diff("passDate", Value::Null, Value::from(row.date.as_str())));
+            }
+        }
+    }
+    diffs
+}
+
+/// Merges `rows` (as read by `read_table`) into `career` (career.json,
+/// migrated) and returns the preview: resolved columns and, for each row,
+/// the action ("add", "update", "unchanged", "skip") with the fields that
+/// change.  Saving is left to the caller, so a dry run is simply not
+/// writing the result.
+pub fn merge(rows: &[Vec<String>], mapping: &ColumnMapping, career: &mut Value) -> Result<Value, String> {
+    let (header, columns) = find_header(rows, mapping)?;
+    let headers = &rows[header];
+    let column = |f: Field| columns.iter().find(|(c, _)| *c == f).map(|(_, i)| *i);
+    let cell = |row: &[String], f: Field| column(f).and_then(|i| row.get(i)).map(|s| s.trim().to_string()).unwrap_or_default();
+
+    let mut changes = Vec::new();
+    let mut parsed = Vec::new();
+    for (i, cells) in rows.iter().enumerate().skip(header + 1) {
+        let name = cell(cells, Field::Name);
+        if name.is_empty() {
+            continue;
+        }
+        let grade = parse_grade(&cell(cells, Field::Grade));
+        let date = parse_date(&cell(cells, Field::Date));
+        match (grade, date) {
+            (Ok(grade), Ok(date)) => parsed.push(Row {
+                line: i + 1,
+                name,
+                cfu: parse_number(&cell(cells, Field::Cfu)),
+                grade,
+                date,
+                ssd: cell(cells, Field::Ssd),
+                year: parse_number(&cell(cells, Field::Year)),
+            }),
+            (grade, date) => {
+                let reason = [grade.err(), date.err()].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                changes.push(serde_json::json!({ "row": i + 1, "name": name, "action": "skip", "reason": reason }));
+            }
+        }
+    }
+
+    if !career.is_object() {
+        *career = serde_json::json!({ "exams": [] });
+    }
+    if !career.get("exams").is_some_and(|e| e.is_array()) {
+        career["exams"] = Value::Array(vec![]);
+    }
+    let exams = career["exams"].as_array_mut().expect("exams is an array");
+
+    // Best matches first, each exam and each row used once
+    let slugs: Vec<String> = exams.iter().map(|e| slug(e.get("name").and_then(|n| n.as_str()).unwrap_or(""))).collect();
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+    for (r, row) in parsed.iter().enumerate() {
+        let key = slug(&row.name);
+        for (e, exam_slug) in slugs.iter().enumerate() {
+            let score = similarity(&key, exam_slug);
+            if score >= MATCH_THRESHOLD {
+                pairs.push((score, r, e));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut matched: Vec<Option<(usize, f64)>> = vec![None; parsed.len()];
+    let mut taken = HashSet::new();
+    for (score, r, e) in pairs {
+        if matched[r].is_none() && taken.insert(e) {
+            matched[r] = Some((e, score));
+        }
+    }
+
+    let mut ids: HashSet<String> = exams.iter()
+        .filter_map(|e| e.get("id").and_then(|i| i.as_str()).map(str::to_string))
+        .collect();
+    let (mut added, mut updated, mut unchanged) = (0, 0, 0);
+    for (row, found) in parsed.iter().zip(matched) {
+        let entry = match found {
+            Some((e, score)) => {
+                let diffs = update_exam(&mut exams[e], row);
+                let action = if diffs.is_empty() { unchanged += 1; "unchanged" } else { updated += 1; "update" };
+                serde_json::json!({
+                    "row": row.line, "name": row.name, "action": action,
+                    "examId": exams[e]["id"], "examName": exams[e]["name"],
+                    "score": (score * 100.0).round() / 100.0, "changes": diffs,
+                })
+            }
+            None if row.cfu.is_none() => serde_json::json!({
+                "row": row.line, "name": row.name, "action": "skip", "reason": "CFU mancanti",
+            }),
+            None => {
+                let base = slug(&row.name);
+                let base = if base.is_empty() { "esame".to_string() } else { base };
+                let id = (1..).map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+                    .find(|id| !ids.contains(id))
+                    .expect("unbounded range");
+                ids.insert(id.clone());
+                let mut exam = serde_json::json!({
+                    "id": id, "name": row.name, "cfu": row.cfu, "year": row.year.unwrap_or(1), "sem": 1, "status": "pending",
+                    "grade": null, "passDate": "", "examDate": "", "progress": 0, "pdfs": [],
+                });
+                let diffs = update_exam(&mut exam, row);
+                exams.push(exam);
+                added += 1;
+                serde_json::json!({
+                    "row": row.line, "name": row.name, "action": "add", "examId": id, "changes": diffs,
+                })
+            }
+        };
+        changes.push(entry);
+    }
+    changes.sort_by_key(|c| c["row"].as_u64());
+    let skipped = changes.iter().filter(|c| c["action"] == "skip").count();
+
+    let resolved: serde_json::Map<String, Value> = FIELDS.iter()
+        .map(|f| (f.key().to_string(), column(*f).and_then(|i| headers.get(i)).map(|h| Value::from(h.as_str())).unwrap_or(Value::Null)))
+        .collect();
+    Ok(serde_json::json!({
+        "headerRow": header + 1,
+        "headers": headers,
+        "columns": resolved,
+        "changes": changes,
+        "added": added,
+        "updated": updated,
+        "unchanged": unchanged,
+        "skipped": skipped,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbering_and_module_suffixes_do_not_lower_the_score() {
+        let score = |a: &str, b: &str| similarity(&slug(a), &slug(b));
+        assert_eq!(score("Fisica II", "Fisica 2"), 1.0);
+        assert_eq!(score("FISICA II - MOD. A", "Fisica 2 modulo A"), 1.0);
+        assert_eq!(score("Analisi Matematica I.", "analisi matematica 1"), 1.0);
+        assert!(score("Fondamenti di Informatica II", "Fondamenti d'informatica 2") >= MATCH_THRESHOLD);
+        assert_eq!(score("Analisi I", "Analisi II"), 0.0);
+        assert_eq!(score("Fisica II mod. A", "Fisica II mod. B"), 0.0);
+    }
+
+    #[test]
+    fn non_utf8_files_are_read_as_windows_1252() {
+        assert_eq!(decode(b"Universit\xe0;\x93Voto\x94;\x80".to_vec()), "Università;“Voto”;€");
+        assert_eq!(decode("Già superato".as_bytes().to_vec()), "Già superato");
+    }
+}
@@ -63,6 +63,7 @@
     openPdf: (fileName) => invoke('open_pdf', { fileName }),
     deletePdf: (fileName) => invoke('delete_pdf', { fileName }),
     getPdfPages: (fileName) => invoke('get_pdf_pages', { fileName }),
+    getPdfThumbnail: (fileName, page, width) => invoke('get_pdf_thumbnail', { fileName, page: page ?? null, width: width ?? null }),
 
     // Widgets
     getWidgetToday: () => invoke('get_widget_today'),